@@ -0,0 +1,163 @@
+//! Online backup and restore.
+//!
+//! Backups are consistent snapshots taken from within a read transaction, so other connections can keep
+//! working while a backup is written.
+
+use std::path::{Path, PathBuf};
+
+use libsql::TransactionBehavior;
+
+use crate::{
+    db::{
+        check_plaintext_header, open, parse_schema_version, read_meta, remove_if_exists,
+        sibling_path, write_copy, HeaderChange, Overwrite,
+    },
+    kdf::KdfHeader,
+    Db, DbOptions, Error, Result,
+};
+
+/// File name extension of rotating backups.
+const BACKUP_EXTENSION: &str = "bak";
+
+impl Db {
+    /// Write a consistent snapshot of this database to `dst`, with the same encryption config.
+    ///
+    /// If this database has a passphrase key header, it is copied alongside the backup.
+    /// Fails if `dst` exists.
+    pub async fn backup_to(&self, dst: impl AsRef<Path>) -> Result<()> {
+        let dst = dst.as_ref();
+        self.backup_to_with_options(dst, self.options()).await?;
+
+        if let Some(header) = KdfHeader::load(self.path())? {
+            header.store(dst)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a consistent snapshot of this database to `dst`, with a different encryption config.
+    ///
+    /// Fails if `dst` exists.
+    pub async fn backup_to_with_options(
+        &self,
+        dst: impl AsRef<Path>,
+        options: DbOptions,
+    ) -> Result<()> {
+        let conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Deferred)
+            .await
+            .map_err(Error::libsql("beginning backup transaction"))?;
+        write_copy(&tx, dst.as_ref(), options, Overwrite::Deny).await
+    }
+
+    /// Write a timestamped backup next to this database, keeping at most `keep` such backups.
+    ///
+    /// Older backups beyond that count are deleted, but the new backup is always kept, even if `keep`
+    /// is 0. Returns the path of the new backup.
+    pub async fn backup_rotating(&self, keep: usize) -> Result<PathBuf> {
+        let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ");
+        let dst = sibling_path(self.path(), &format!("{timestamp}.{BACKUP_EXTENSION}"));
+        self.backup_to(&dst).await?;
+
+        let mut backups = self.rotating_backups()?;
+        backups.retain(|backup| *backup != dst);
+        // timestamps sort lexically, so the oldest backups come first
+        backups.sort();
+        let excess = backups.len().saturating_sub(keep.saturating_sub(1));
+        for backup in &backups[..excess] {
+            remove_if_exists(backup)?;
+            remove_if_exists(&KdfHeader::path_for(backup))?;
+        }
+
+        Ok(dst)
+    }
+
+    /// List the rotating backups which belong to this database.
+    pub fn rotating_backups(&self) -> Result<Vec<PathBuf>> {
+        let Some(dir) = self.path().parent() else {
+            return Ok(Vec::new());
+        };
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        let file_name = self
+            .path()
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let prefix = format!("{file_name}.");
+        let suffix = format!(".{BACKUP_EXTENSION}");
+
+        let mut backups = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(Error::io("listing backups"))? {
+            let entry = entry.map_err(Error::io("reading backup directory entry"))?;
+            let file_name = entry.file_name();
+            let file_name = file_name.to_string_lossy();
+            if file_name.starts_with(&prefix) && file_name.ends_with(&suffix) {
+                backups.push(entry.path());
+            }
+        }
+
+        Ok(backups)
+    }
+
+    /// Replace the contents of this database with those of the backup at `src`.
+    ///
+    /// The backup must have been written with the same encryption config as this database is currently
+    /// using; see [`Self::restore_from_with_options`] otherwise. Its schema version is validated before
+    /// anything is replaced, and the swap itself is atomic. Backups with an older schema are upgraded.
+    ///
+    /// If the backup has a passphrase key header, it replaces this database's, as [`Self::backup_to`]
+    /// copied it alongside the backup.
+    pub async fn restore_from(&self, src: impl AsRef<Path>) -> Result<()> {
+        let src = src.as_ref();
+        let header = match KdfHeader::load(src)? {
+            Some(header) => HeaderChange::Replace(header),
+            None => HeaderChange::Keep,
+        };
+        self.restore(src, self.options(), header).await
+    }
+
+    /// Replace the contents of this database with those of the backup at `src`, which was written with
+    /// `src_options`.
+    ///
+    /// The restored database keeps this database's current encryption config.
+    pub async fn restore_from_with_options(
+        &self,
+        src: impl AsRef<Path>,
+        src_options: DbOptions,
+    ) -> Result<()> {
+        self.restore(src.as_ref(), src_options, HeaderChange::Keep)
+            .await
+    }
+
+    async fn restore(
+        &self,
+        src: &Path,
+        src_options: DbOptions,
+        header: HeaderChange,
+    ) -> Result<()> {
+        if !src.exists() {
+            let err = std::io::Error::from(std::io::ErrorKind::NotFound);
+            return Err(Error::io("opening backup")(err));
+        }
+        check_plaintext_header(src, src_options.encryption_mode())?;
+
+        let backup = open(src, src_options.encryption).await?;
+        let conn = backup
+            .connect()
+            .map_err(Error::libsql("establishing connection to backup"))?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Deferred)
+            .await
+            .map_err(Error::libsql("beginning restore transaction"))?;
+
+        // backups from older versions are brought up to date by the copy
+        parse_schema_version(read_meta(&tx, "schema_version").await?.as_deref())?;
+
+        self.replace_with(Some(&tx), self.options(), header).await
+    }
+}
@@ -0,0 +1,523 @@
+use std::{
+    io::Read as _,
+    path::{Path, PathBuf},
+    sync::{PoisonError, RwLock},
+};
+
+use libsql::{params, params_from_iter, Connection, Database, TransactionBehavior, Value};
+
+use crate::{
+    kdf::KdfHeader, sync, uuids, DbOptions, EncryptionConfig, EncryptionMode, Error, KdfCost,
+    Result,
+};
+
+/// Migrations applied after `schema.sql`, in order.
+///
+/// `schema.sql` creates version 1 of the schema, and each migration moves it to the next version.
+/// Migrations are append-only: to change the schema, add a new one rather than editing an old one.
+const MIGRATIONS: &[&str] = &[
+    include_str!("migrations/0002_item_details.sql"),
+    include_str!("migrations/0003_item_due.sql"),
+    include_str!("migrations/0004_uuids.sql"),
+    include_str!("migrations/0005_sync.sql"),
+    include_str!("migrations/0006_sync_peers.sql"),
+    include_str!("migrations/0007_row_versions.sql"),
+    include_str!("migrations/0008_item_positions.sql"),
+];
+
+/// Version of the schema after all migrations have been applied.
+pub const SCHEMA_VERSION: u32 = 1 + MIGRATIONS.len() as u32;
+
+/// The first 16 bytes of every plaintext SQLite database.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+pub struct Db {
+    path: PathBuf,
+    inner: RwLock<Inner>,
+}
+
+struct Inner {
+    database: Database,
+    options: DbOptions,
+}
+
+impl Db {
+    pub async fn new(path: impl AsRef<Path>, encryption_config: EncryptionConfig) -> Result<Self> {
+        Self::open(path, DbOptions::encrypted(encryption_config)).await
+    }
+
+    /// Open or create the database at `path`.
+    ///
+    /// Fails with [`Error::EncryptionMismatch`] or [`Error::UnexpectedlyEncrypted`] if the database
+    /// was created with a different encryption mode than requested.
+    pub async fn open(path: impl AsRef<Path>, options: DbOptions) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+        check_plaintext_header(&path, options.encryption_mode())?;
+        let database = open(&path, options.encryption.clone()).await?;
+
+        let db = Self {
+            path,
+            inner: RwLock::new(Inner { database, options }),
+        };
+        let conn = db.conn()?;
+        // wait for anyone else opening the database to finish setting it up, rather than failing
+        conn.query("PRAGMA busy_timeout = 5000", ())
+            .await
+            .map_err(Error::libsql("setting busy timeout"))?;
+        db.ensure_schema(&conn).await?;
+        db.check_encryption_mode(&conn).await?;
+
+        Ok(db)
+    }
+
+    /// Path to the database file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Options with which this database is currently open.
+    pub fn options(&self) -> DbOptions {
+        self.inner
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .options
+            .clone()
+    }
+
+    pub(crate) fn conn(&self) -> Result<Connection> {
+        self.inner
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .database
+            .connect()
+            .map_err(Error::libsql("establishing connection to db"))
+    }
+
+    async fn ensure_schema(&self, conn: &Connection) -> Result<()> {
+        const SCHEMA: &str = include_str!("schema.sql");
+
+        // reading the version and migrating in one immediate transaction ensures that concurrent
+        // openers apply each migration once
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .await
+            .map_err(Error::libsql("beginning migration transaction"))?;
+
+        for command in SCHEMA.split("\n\n") {
+            tx.execute(command, ())
+                .await
+                .map_err(Error::libsql("executing schema"))?;
+        }
+
+        tx.execute(
+            "INSERT INTO meta(key, value) VALUES ('schema_version', '1') ON CONFLICT DO NOTHING",
+            (),
+        )
+        .await
+        .map_err(Error::libsql("recording schema version"))?;
+
+        let found = read_meta(&tx, "schema_version").await?;
+        let version = parse_schema_version(found.as_deref())?;
+
+        for (idx, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+            for command in migration.split("\n\n") {
+                tx.execute(command, ())
+                    .await
+                    .map_err(Error::libsql("executing migration"))?;
+            }
+            tx.execute(
+                "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+                [(idx + 2).to_string()],
+            )
+            .await
+            .map_err(Error::libsql("recording schema version"))?;
+        }
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing migration transaction"))?;
+
+        sync::init(conn).await?;
+        uuids::fill_missing(conn).await
+    }
+
+    /// Record the encryption mode if this is a new database, or ensure that it matches if not.
+    async fn check_encryption_mode(&self, conn: &Connection) -> Result<()> {
+        let expected = self.options().encryption_mode();
+
+        conn.execute(
+            "INSERT INTO meta(key, value) VALUES ('encryption', ?1) ON CONFLICT DO NOTHING",
+            [expected.to_string()],
+        )
+        .await
+        .map_err(Error::libsql("recording encryption mode"))?;
+
+        let found = read_meta(conn, "encryption")
+            .await?
+            .expect("encryption mode was inserted above if missing");
+        let found = found
+            .parse::<EncryptionMode>()
+            .map_err(|_| Error::UnknownEncryptionMode(found.to_owned()))?;
+
+        if found != expected {
+            return Err(Error::EncryptionMismatch { expected, found });
+        }
+        Ok(())
+    }
+
+    /// Re-encrypt this database with a new encryption config.
+    ///
+    /// The contents are copied into a temporary file next to the database, which is then atomically
+    /// renamed over the original. A crash at any point leaves either the old or the new database in place,
+    /// never a partially-written one. Any key header is removed once the new database is in place, since
+    /// the new key is not derived from it.
+    ///
+    /// Writers using this `Db` are blocked for the duration. Other processes which already hold the database
+    /// open will continue to see the old file until they reopen it.
+    pub async fn rekey(&self, encryption_config: EncryptionConfig) -> Result<()> {
+        let options = DbOptions::encrypted(encryption_config);
+        self.replace_with(None, options, HeaderChange::Remove).await
+    }
+
+    /// Re-encrypt this database with a key derived from `passphrase`, using a new key header with a
+    /// fresh salt and the given cost.
+    ///
+    /// The new header is synced to disk beside the database before the database is replaced, and renamed
+    /// over the old header straight afterwards. See [`Self::rekey`].
+    pub async fn rekey_with_passphrase(&self, passphrase: &[u8], cost: KdfCost) -> Result<()> {
+        let header = KdfHeader::generate(cost)?;
+        let options = DbOptions::encrypted(header.encryption_config(passphrase)?);
+        self.replace_with(None, options, HeaderChange::Replace(header))
+            .await
+    }
+
+    /// Atomically replace the database file with a copy of `src`, written using `options`, and change its
+    /// key header to match.
+    ///
+    /// When `src` is `None`, the database is copied from itself.
+    pub(crate) async fn replace_with(
+        &self,
+        src: Option<&Connection>,
+        options: DbOptions,
+        header: HeaderChange,
+    ) -> Result<()> {
+        let header_path = KdfHeader::path_for(&self.path);
+        let staged = match &header {
+            HeaderChange::Replace(header) => Some(header.stage(&header_path)?),
+            HeaderChange::Keep | HeaderChange::Remove => None,
+        };
+
+        let conn = self.conn()?;
+        // an immediate transaction ensures that nobody can write to the old file while we copy it
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .await
+            .map_err(Error::libsql("beginning replacement transaction"))?;
+
+        let copied = write_copy(
+            src.unwrap_or(&tx),
+            &self.path,
+            options.clone(),
+            Overwrite::Allow,
+        )
+        .await;
+        if let Err(err) = copied {
+            if let Some(staged) = &staged {
+                let _ = std::fs::remove_file(staged);
+            }
+            return Err(err);
+        }
+
+        // the database is in place, so the old header no longer belongs to it
+        let header = match (&header, staged) {
+            (HeaderChange::Replace(_), Some(staged)) => std::fs::rename(staged, &header_path)
+                .map_err(Error::io(
+                    "moving new kdf header into place; it is left beside the old one, with .tmp appended",
+                )),
+            (HeaderChange::Remove, _) => KdfHeader::remove(&self.path),
+            _ => Ok(()),
+        };
+
+        let database = open(&self.path, options.encryption.clone()).await?;
+        *self.inner.write().unwrap_or_else(PoisonError::into_inner) = Inner { database, options };
+
+        // nothing was written through this transaction; we only held it for its lock
+        tx.rollback()
+            .await
+            .map_err(Error::libsql("ending replacement transaction"))?;
+
+        header
+    }
+
+    /// Create a new encrypted database at `dst` from the contents of the plaintext SQLite database at `src`.
+    ///
+    /// `src` is not modified; it must already use this library's schema. Fails if `dst` exists.
+    pub async fn encrypt_from_plain(
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        encryption_config: EncryptionConfig,
+    ) -> Result<Self> {
+        let src = src.as_ref();
+        let dst = dst.as_ref();
+        if !src.exists() {
+            let err = std::io::Error::from(std::io::ErrorKind::NotFound);
+            return Err(Error::io("opening plaintext database")(err));
+        }
+        check_plaintext_header(src, EncryptionMode::None)?;
+
+        {
+            let src = open(src, None).await?;
+            let conn = src
+                .connect()
+                .map_err(Error::libsql("establishing connection to plaintext db"))?;
+            let tx = conn
+                .transaction_with_behavior(TransactionBehavior::Deferred)
+                .await
+                .map_err(Error::libsql("beginning read transaction"))?;
+            let options = DbOptions::encrypted(encryption_config.clone());
+            write_copy(&tx, dst, options, Overwrite::Deny).await?;
+        }
+
+        Self::new(dst, encryption_config).await
+    }
+
+    /// Write a decrypted copy of this database to `dst`.
+    ///
+    /// The copy is a consistent snapshot, and can be inspected with ordinary SQLite tools.
+    /// Fails if `dst` exists.
+    pub async fn export_plain(&self, dst: impl AsRef<Path>) -> Result<()> {
+        self.backup_to_with_options(dst, DbOptions::unencrypted())
+            .await
+    }
+}
+
+pub(crate) async fn open(path: &Path, encryption: Option<EncryptionConfig>) -> Result<Database> {
+    let mut builder = libsql::Builder::new_local(path);
+    if let Some(encryption_config) = encryption {
+        builder = builder.encryption_config(encryption_config);
+    }
+    builder
+        .build()
+        .await
+        .map_err(Error::libsql("building local db connection"))
+}
+
+/// Check an existing database file's header against the requested encryption mode.
+///
+/// Plaintext SQLite files begin with a well-known header, so we can tell up front whether a file
+/// is encrypted at all. Without this check, a mismatch surfaces as "file is not a database".
+pub(crate) fn check_plaintext_header(path: &Path, expected: EncryptionMode) -> Result<()> {
+    let mut header = [0; SQLITE_HEADER.len()];
+    let read = match std::fs::File::open(path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => true,
+        Err(err)
+            if matches!(
+                err.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::UnexpectedEof
+            ) =>
+        {
+            false
+        }
+        Err(err) => return Err(Error::io("reading database header")(err)),
+    };
+    if !read {
+        // new or empty database; it will be created in the requested mode
+        return Ok(());
+    }
+
+    match (&header == SQLITE_HEADER, expected) {
+        (true, EncryptionMode::None) | (false, EncryptionMode::Aes256Cbc) => Ok(()),
+        (true, expected) => Err(Error::EncryptionMismatch {
+            expected,
+            found: EncryptionMode::None,
+        }),
+        (false, EncryptionMode::None) => Err(Error::UnexpectedlyEncrypted),
+    }
+}
+
+/// What becomes of the key header when [`Db::replace_with`] replaces the database file.
+pub(crate) enum HeaderChange {
+    Keep,
+    Remove,
+    Replace(KdfHeader),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Overwrite {
+    Allow,
+    Deny,
+}
+
+/// Atomically write a copy of the database behind `src` to `dst_path`, using `options`.
+///
+/// The copy is written to a temporary file next to `dst_path`, synced to disk, and then renamed into place,
+/// so `dst_path` never holds a partially-written database.
+pub(crate) async fn write_copy(
+    src: &Connection,
+    dst_path: &Path,
+    options: DbOptions,
+    overwrite: Overwrite,
+) -> Result<()> {
+    if overwrite == Overwrite::Deny && dst_path.exists() {
+        return Err(Error::DestinationExists(dst_path.to_owned()));
+    }
+
+    let tmp_path = sibling_path(dst_path, "tmp");
+    remove_if_exists(&tmp_path)?;
+
+    let result = write_copy_to(src, &tmp_path, options).await.and_then(|()| {
+        std::fs::rename(&tmp_path, dst_path).map_err(Error::io("moving database copy into place"))
+    });
+    if result.is_err() {
+        // best-effort cleanup; the destination is untouched
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+async fn write_copy_to(src: &Connection, path: &Path, options: DbOptions) -> Result<()> {
+    {
+        let mode = options.encryption_mode();
+        let dst = Db::open(path, options).await?;
+        let dst_conn = dst.conn()?;
+        // the copied rows keep the stamps they have in the source
+        sync::set_applying(&dst_conn, true).await?;
+        copy_tables(src, &dst_conn).await?;
+        sync::set_applying(&dst_conn, false).await?;
+        uuids::fill_missing(&dst_conn).await?;
+        // the copy brought the source's encryption mode along with everything else
+        dst_conn
+            .execute(
+                "UPDATE meta SET value = ?1 WHERE key = 'encryption'",
+                params!(mode.to_string()),
+            )
+            .await
+            .map_err(Error::libsql("recording encryption mode of copy"))?;
+        // likewise its schema version, but the copy has the current schema whatever the source's was
+        dst_conn
+            .execute(
+                "UPDATE meta SET value = ?1 WHERE key = 'schema_version'",
+                params!(SCHEMA_VERSION.to_string()),
+            )
+            .await
+            .map_err(Error::libsql("recording schema version of copy"))?;
+    }
+
+    std::fs::File::open(path)
+        .and_then(|file| file.sync_all())
+        .map_err(Error::io("syncing database copy to disk"))
+}
+
+/// Produce a path in the same directory as `path`, with `suffix` appended to the file name.
+///
+/// Keeping temporary files in the same directory means that they are on the same filesystem,
+/// so a rename is atomic.
+pub(crate) fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_owned();
+    file_name.push(".");
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Parse a schema version read from the `meta` table, ensuring that this library understands it.
+pub(crate) fn parse_schema_version(found: Option<&str>) -> Result<u32> {
+    found
+        .and_then(|found| found.parse::<u32>().ok())
+        .filter(|version| (1..=SCHEMA_VERSION).contains(version))
+        .ok_or_else(|| Error::SchemaVersionMismatch {
+            expected: SCHEMA_VERSION,
+            found: found.map(ToOwned::to_owned),
+        })
+}
+
+/// Read a value from the `meta` table.
+pub(crate) async fn read_meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    let mut rows = conn
+        .query("SELECT value FROM meta WHERE key = ?1", [key])
+        .await
+        .map_err(Error::libsql("reading metadata"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for metadata"))?;
+
+    row.map(|row| {
+        row.get_str(0)
+            .map(ToOwned::to_owned)
+            .map_err(Error::libsql("getting metadata value from result row"))
+    })
+    .transpose()
+}
+
+pub(crate) fn remove_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+            Err(Error::io("removing stale temporary file")(err))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Copy every row of every table from `src` into `dst`.
+///
+/// `dst` must already have the schema in place and be empty. Tables are copied in creation order,
+/// so referenced tables are populated before the tables which reference them.
+async fn copy_tables(src: &Connection, dst: &Connection) -> Result<()> {
+    let mut tables = Vec::new();
+    let mut rows = src
+        .query(
+            "SELECT name FROM sqlite_schema WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY rowid",
+            (),
+        )
+        .await
+        .map_err(Error::libsql("listing tables to copy"))?;
+    while let Some(row) = rows.next().await.map_err(Error::libsql(
+        "getting next row while listing tables to copy",
+    ))? {
+        let name = row.get_str(0).map_err(Error::libsql(
+            "getting table name while listing tables to copy",
+        ))?;
+        tables.push(name.to_owned());
+    }
+
+    let tx = dst
+        .transaction()
+        .await
+        .map_err(Error::libsql("beginning copy transaction"))?;
+
+    for table in tables {
+        let mut rows = src
+            .query(&format!("SELECT * FROM \"{table}\""), ())
+            .await
+            .map_err(Error::libsql("selecting rows to copy"))?;
+        let columns = (0..rows.column_count())
+            .map(|idx| format!("\"{}\"", rows.column_name(idx).unwrap_or_default()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let placeholders = (1..=rows.column_count())
+            .map(|idx| format!("?{idx}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        // tables such as `meta` are pre-populated by the schema, so source rows take precedence.
+        // Naming the columns allows copying from a source with an older schema.
+        let insert =
+            format!("INSERT OR REPLACE INTO \"{table}\" ({columns}) VALUES ({placeholders})");
+
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next row to copy"))?
+        {
+            let values = (0..row.column_count())
+                .map(|idx| row.get_value(idx))
+                .collect::<Result<Vec<Value>, _>>()
+                .map_err(Error::libsql("getting value from row to copy"))?;
+            tx.execute(&insert, params_from_iter(values))
+                .await
+                .map_err(Error::libsql("inserting copied row"))?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(Error::libsql("committing copy transaction"))
+}
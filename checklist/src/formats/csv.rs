@@ -0,0 +1,247 @@
+//! CSV for spreadsheets.
+//!
+//! Each item is written as one row, beneath a header row:
+//!
+//! ```csv
+//! checklist_id,checklist_uuid,checklist,item_id,item_uuid,item,checked,priority,created_at,completed_at,due_at
+//! 1,0194b1c2-5e40-7a31-9c2e-3f1d2a4b5c6d,groceries,1,0194b1c2-5e41-7b02-8d3f-4a5b6c7d8e9f,milk,false,,2025-01-29T10:00:00.000Z,,
+//! 1,0194b1c2-5e40-7a31-9c2e-3f1d2a4b5c6d,groceries,2,0194b1c2-6d83-7c13-a1e4-5b6c7d8e9fa0,"eggs, free range",true,A,2025-01-29T10:00:05.000Z,2025-01-31T08:02:00.000Z,
+//! ```
+//!
+//! - Fields are quoted as needed, and the text is UTF-8.
+//! - `checklist_uuid` and `item_uuid` carry each record's uuid, which identifies it across imports.
+//! - `checked` is `true` or `false`; `1`/`0`, `yes`/`no` and `x`/empty are also accepted when reading.
+//! - Timestamps are RFC 3339, and empty when unset.
+//! - A checklist without items is written as a single row with empty item columns.
+//!
+//! When reading, columns are matched by their header, so they may appear in any order; only
+//! `checklist` and `item` are required. Rows are grouped into checklists by the `checklist` column, in
+//! the order in which each name first appears.
+
+use std::io::{Read, Write};
+
+use chrono::{DateTime, Utc};
+
+use super::{ChecklistRecord, Document, ItemRecord};
+use crate::{Error, Result, Uuid};
+
+const HEADER: [&str; 11] = [
+    "checklist_id",
+    "checklist_uuid",
+    "checklist",
+    "item_id",
+    "item_uuid",
+    "item",
+    "checked",
+    "priority",
+    "created_at",
+    "completed_at",
+    "due_at",
+];
+
+/// Write a document as CSV, one row per item.
+pub fn write(document: &Document, writer: impl Write) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(writer);
+    let write_err = |err: ::csv::Error| Error::io("writing csv")(err.into());
+
+    writer.write_record(HEADER).map_err(write_err)?;
+    for checklist in &document.checklists {
+        let checklist_id = checklist.id.map(|id| id.to_string()).unwrap_or_default();
+        let checklist_uuid = checklist
+            .uuid
+            .map(|uuid| uuid.to_string())
+            .unwrap_or_default();
+        if checklist.items.is_empty() {
+            writer
+                .write_record([
+                    checklist_id.as_str(),
+                    &checklist_uuid,
+                    &checklist.name,
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                    "",
+                ])
+                .map_err(write_err)?;
+        }
+        for item in &checklist.items {
+            let timestamp = |timestamp: &Option<DateTime<Utc>>| {
+                timestamp
+                    .as_ref()
+                    .map(crate::timestamp::to_sql)
+                    .unwrap_or_default()
+            };
+            writer
+                .write_record([
+                    checklist_id.clone(),
+                    checklist_uuid.clone(),
+                    checklist.name.clone(),
+                    item.id.map(|id| id.to_string()).unwrap_or_default(),
+                    item.uuid.map(|uuid| uuid.to_string()).unwrap_or_default(),
+                    item.item.clone(),
+                    item.checked.to_string(),
+                    item.priority.map(String::from).unwrap_or_default(),
+                    timestamp(&item.created_at),
+                    timestamp(&item.completed_at),
+                    timestamp(&item.due_at),
+                ])
+                .map_err(write_err)?;
+        }
+    }
+
+    writer.flush().map_err(Error::io("flushing csv"))
+}
+
+/// Read a document from CSV.
+///
+/// Errors identify the offending row and column.
+pub fn read(reader: impl Read) -> Result<Document> {
+    // spreadsheets often omit trailing empty fields
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(reader);
+    let read_err = |err: ::csv::Error| {
+        let path = match err.position() {
+            Some(position) => format!("row {}", position.line()),
+            None => "header".to_owned(),
+        };
+        Error::invalid_document(path, err)
+    };
+
+    let header = reader.headers().map_err(read_err)?.clone();
+    let column = |name: &str| header.iter().position(|column| column.trim() == name);
+    let required = |name: &str| {
+        column(name).ok_or_else(|| {
+            Error::invalid_document("header", format!("missing required column `{name}`"))
+        })
+    };
+    let checklist_column = required("checklist")?;
+    let item_column = required("item")?;
+    let checklist_id_column = column("checklist_id");
+    let checklist_uuid_column = column("checklist_uuid");
+    let item_id_column = column("item_id");
+    let item_uuid_column = column("item_uuid");
+    let checked_column = column("checked");
+    let priority_column = column("priority");
+    let created_at_column = column("created_at");
+    let completed_at_column = column("completed_at");
+    let due_at_column = column("due_at");
+
+    let mut checklists = Vec::<ChecklistRecord>::new();
+    for record in reader.records() {
+        let record = record.map_err(read_err)?;
+        let row = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default();
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        let invalid = |name: &str, reason: String| {
+            Error::invalid_document(format!("row {row}, column `{name}`"), reason)
+        };
+        let parse_id = |column, name| {
+            field(column)
+                .map(|id: &str| {
+                    id.parse::<i64>()
+                        .map_err(|err| invalid(name, format!("invalid id {id:?}: {err}")))
+                })
+                .transpose()
+        };
+        let parse_uuid = |column, name| {
+            field(column)
+                .map(|uuid: &str| {
+                    Uuid::try_parse(uuid)
+                        .map_err(|err| invalid(name, format!("invalid uuid {uuid:?}: {err}")))
+                })
+                .transpose()
+        };
+        let parse_timestamp = |column, name| {
+            field(column)
+                .map(|timestamp: &str| {
+                    DateTime::parse_from_rfc3339(timestamp)
+                        .map(|timestamp| timestamp.to_utc())
+                        .map_err(|err| {
+                            invalid(name, format!("invalid timestamp {timestamp:?}: {err}"))
+                        })
+                })
+                .transpose()
+        };
+
+        let name = record.get(checklist_column).unwrap_or_default();
+        let checklist_id = parse_id(checklist_id_column, "checklist_id")?;
+        let checklist_uuid = parse_uuid(checklist_uuid_column, "checklist_uuid")?;
+        let checklist = match checklists
+            .iter_mut()
+            .position(|checklist| checklist.name == name)
+        {
+            Some(idx) => &mut checklists[idx],
+            None => {
+                checklists.push(ChecklistRecord {
+                    id: checklist_id.map(Into::into),
+                    uuid: checklist_uuid,
+                    name: name.to_owned(),
+                    items: Vec::new(),
+                });
+                checklists.last_mut().expect("just pushed")
+            }
+        };
+
+        let item = record.get(item_column).unwrap_or_default();
+        let item_id = parse_id(item_id_column, "item_id")?;
+        let item_uuid = parse_uuid(item_uuid_column, "item_uuid")?;
+        if item.is_empty() && item_id.is_none() && item_uuid.is_none() {
+            // a checklist without items
+            continue;
+        }
+
+        let checked = match field(checked_column)
+            .map(str::to_ascii_lowercase)
+            .as_deref()
+        {
+            None | Some("false" | "0" | "no") => false,
+            Some("true" | "1" | "yes" | "x") => true,
+            Some(other) => {
+                return Err(invalid(
+                    "checked",
+                    format!("expected `true` or `false`, not {other:?}"),
+                ))
+            }
+        };
+        let priority = match field(priority_column) {
+            None => None,
+            Some(priority) => {
+                let mut chars = priority.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(priority), None) => Some(priority),
+                    _ => {
+                        return Err(invalid(
+                            "priority",
+                            format!("expected a single letter, not {priority:?}"),
+                        ))
+                    }
+                }
+            }
+        };
+
+        checklist.items.push(ItemRecord {
+            id: item_id.map(Into::into),
+            uuid: item_uuid,
+            item: item.to_owned(),
+            checked,
+            priority,
+            created_at: parse_timestamp(created_at_column, "created_at")?,
+            completed_at: parse_timestamp(completed_at_column, "completed_at")?,
+            due_at: parse_timestamp(due_at_column, "due_at")?,
+        });
+    }
+
+    Ok(Document { checklists })
+}
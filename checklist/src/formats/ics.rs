@@ -0,0 +1,413 @@
+//! iCalendar ([RFC 5545](https://www.rfc-editor.org/rfc/rfc5545)) to-dos.
+//!
+//! Each checklist is written as a `VTODO` marked with `X-CHECKLIST-KIND:CHECKLIST`, and each of its
+//! items as a `VTODO` with a `RELATED-TO` property naming the checklist's `UID`:
+//!
+//! ```text
+//! BEGIN:VTODO
+//! UID:0194b1c2-5e41-7b02-8d3f-4a5b6c7d8e9f
+//! DTSTAMP:20250131T120000Z
+//! SUMMARY:milk
+//! STATUS:NEEDS-ACTION
+//! DUE:20250201T000000Z
+//! RELATED-TO;RELTYPE=PARENT:0194b1c2-5e40-7a31-9c2e-3f1d2a4b5c6d
+//! END:VTODO
+//! ```
+//!
+//! - `UID`s are uuids, so importing an export with [`ImportMode::Update`] updates the checklists and
+//!   items it was exported from instead of duplicating them. Other `UID`s are read as name-based
+//!   (version 5) uuids, so that re-importing a foreign calendar is stable too.
+//! - `STATUS` is `COMPLETED` for checked items and `NEEDS-ACTION` otherwise.
+//! - `PRIORITY` maps `1` through `9` to priorities `A` through `I`; lower priorities are written as `9`,
+//!   and priorities other than `A` through `Z` are not written.
+//! - `CREATED`, `COMPLETED` and `DUE` map to the item's timestamps.
+//!
+//! When reading, any `VTODO` which is marked as a checklist or named by another's `RELATED-TO` is a
+//! checklist. Other `VTODO`s are items; those without a known parent go into the
+//! [`DEFAULT_CHECKLIST`]. Times with a `TZID` or without a time zone are read as UTC.
+//!
+//! [`ImportMode::Update`]: super::ImportMode::Update
+
+use std::{collections::HashSet, fmt::Write as _};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use super::{ChecklistRecord, Document, ItemRecord, DEFAULT_CHECKLIST};
+use crate::{uuids, Error, Result, Uuid};
+
+/// Namespace for uuids derived from foreign `UID`s.
+const UID_NAMESPACE: Uuid = Uuid::from_u128(0x5d0c_3f7e_8a41_4b6e_9f0a_2c1d_7e3b_6a95);
+const CHECKLIST_KIND: &str = "X-CHECKLIST-KIND";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Write a document as an iCalendar object of `VTODO`s.
+pub fn to_string(document: &Document) -> String {
+    let mut ics = Writer::default();
+    let dtstamp = Utc::now().format(DATE_TIME_FORMAT).to_string();
+
+    ics.line("BEGIN:VCALENDAR");
+    ics.line("VERSION:2.0");
+    ics.line("PRODID:-//checklist//checklist//EN");
+    for checklist in &document.checklists {
+        let checklist_uid = checklist.uuid.unwrap_or_else(uuids::new).to_string();
+        ics.line("BEGIN:VTODO");
+        ics.property("UID", &checklist_uid);
+        ics.property("DTSTAMP", &dtstamp);
+        ics.text("SUMMARY", &checklist.name);
+        ics.property(CHECKLIST_KIND, "CHECKLIST");
+        ics.line("END:VTODO");
+
+        for item in &checklist.items {
+            let uid = item.uuid.unwrap_or_else(uuids::new).to_string();
+            ics.line("BEGIN:VTODO");
+            ics.property("UID", &uid);
+            ics.property("DTSTAMP", &dtstamp);
+            ics.text("SUMMARY", &item.item);
+            let status = if item.checked {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            };
+            ics.property("STATUS", status);
+            let rank = item
+                .priority
+                .filter(char::is_ascii_uppercase)
+                .and_then(|priority| u32::from(priority).checked_sub(u32::from('A')));
+            if let Some(rank) = rank {
+                ics.property("PRIORITY", &(rank + 1).min(9).to_string());
+            }
+            for (name, timestamp) in [
+                ("CREATED", &item.created_at),
+                ("COMPLETED", &item.completed_at),
+                ("DUE", &item.due_at),
+            ] {
+                if let Some(timestamp) = timestamp {
+                    ics.property(name, &timestamp.format(DATE_TIME_FORMAT).to_string());
+                }
+            }
+            ics.property("RELATED-TO;RELTYPE=PARENT", &checklist_uid);
+            ics.line("END:VTODO");
+        }
+    }
+    ics.line("END:VCALENDAR");
+
+    ics.0
+}
+
+#[derive(Default)]
+struct Writer(String);
+
+impl Writer {
+    /// Write a content line, folding it at 75 octets.
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            if width + c.len_utf8() > 75 {
+                self.0.push_str("\r\n ");
+                width = 1;
+            }
+            self.0.push(c);
+            width += c.len_utf8();
+        }
+        self.0.push_str("\r\n");
+    }
+
+    fn property(&mut self, name: &str, value: &str) {
+        self.line(&format!("{name}:{value}"));
+    }
+
+    fn text(&mut self, name: &str, value: &str) {
+        let mut escaped = String::with_capacity(value.len());
+        for c in value.chars() {
+            match c {
+                '\\' | ';' | ',' => write!(escaped, "\\{c}").expect("writing to a string"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => {}
+                c => escaped.push(c),
+            }
+        }
+        self.property(name, &escaped);
+    }
+}
+
+/// A content line: name, parameters and value.
+struct Property {
+    line: usize,
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Default)]
+struct Todo {
+    line: usize,
+    properties: Vec<Property>,
+}
+
+impl Todo {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name.eq_ignore_ascii_case(name))
+    }
+
+    fn uid(&self) -> Option<&str> {
+        self.get("UID").map(|uid| uid.value.as_str())
+    }
+
+    /// The `UID` of this to-do's parent, if any.
+    fn parent(&self) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|property| {
+                property.name.eq_ignore_ascii_case("RELATED-TO")
+                    && property
+                        .param("RELTYPE")
+                        .is_none_or(|reltype| reltype.eq_ignore_ascii_case("PARENT"))
+            })
+            .map(|property| property.value.as_str())
+    }
+}
+
+/// Read a document from an iCalendar object.
+///
+/// Components other than `VTODO` are ignored. Errors identify the offending line.
+pub fn from_str(ics: &str) -> Result<Document> {
+    let todos = parse_todos(ics)?;
+
+    let parents = todos
+        .iter()
+        .filter_map(Todo::parent)
+        .collect::<HashSet<_>>();
+    let is_checklist = |todo: &Todo| {
+        todo.get(CHECKLIST_KIND)
+            .is_some_and(|kind| kind.value.eq_ignore_ascii_case("CHECKLIST"))
+            || todo.uid().is_some_and(|uid| parents.contains(uid))
+    };
+
+    let mut checklists = Vec::new();
+    let mut checklist_uids = Vec::new();
+    for todo in todos.iter().filter(|todo| is_checklist(todo)) {
+        checklists.push(ChecklistRecord {
+            id: None,
+            uuid: todo.uid().map(parse_uid),
+            name: summary(todo)?,
+            items: Vec::new(),
+        });
+        checklist_uids.push(todo.uid());
+    }
+
+    for todo in todos.iter().filter(|todo| !is_checklist(todo)) {
+        let item = parse_item(todo)?;
+        let parent = todo
+            .parent()
+            .and_then(|parent| checklist_uids.iter().position(|uid| *uid == Some(parent)));
+        let checklist = match parent {
+            Some(idx) => idx,
+            None => match checklists.iter().position(|checklist| {
+                checklist.uuid.is_none() && checklist.name == DEFAULT_CHECKLIST
+            }) {
+                Some(idx) => idx,
+                None => {
+                    checklists.push(ChecklistRecord {
+                        id: None,
+                        uuid: None,
+                        name: DEFAULT_CHECKLIST.to_owned(),
+                        items: Vec::new(),
+                    });
+                    checklist_uids.push(None);
+                    checklists.len() - 1
+                }
+            },
+        };
+        checklists[checklist].items.push(item);
+    }
+
+    Ok(Document { checklists })
+}
+
+fn parse_item(todo: &Todo) -> Result<ItemRecord> {
+    let invalid = |property: &Property, reason: String| {
+        Error::invalid_document(format!("line {}", property.line), reason)
+    };
+    let timestamp = |name| {
+        todo.get(name)
+            .map(|property| {
+                parse_timestamp(property).ok_or_else(|| {
+                    invalid(property, format!("invalid {name} {:?}", property.value))
+                })
+            })
+            .transpose()
+    };
+
+    let priority = todo
+        .get("PRIORITY")
+        .map(|property| match property.value.trim().parse::<u8>() {
+            // 0 means undefined
+            Ok(0) => Ok(None),
+            Ok(priority @ 1..=9) => Ok(Some(char::from(b'A' + priority - 1))),
+            _ => Err(invalid(
+                property,
+                format!("invalid PRIORITY {:?}", property.value),
+            )),
+        })
+        .transpose()?
+        .flatten();
+
+    Ok(ItemRecord {
+        id: None,
+        uuid: todo.uid().map(parse_uid),
+        item: summary(todo)?,
+        checked: todo
+            .get("STATUS")
+            .is_some_and(|status| status.value.eq_ignore_ascii_case("COMPLETED")),
+        priority,
+        created_at: timestamp("CREATED")?,
+        completed_at: timestamp("COMPLETED")?,
+        due_at: timestamp("DUE")?,
+    })
+}
+
+fn summary(todo: &Todo) -> Result<String> {
+    todo.get("SUMMARY")
+        .map(|summary| unescape(&summary.value))
+        .ok_or_else(|| {
+            Error::invalid_document(format!("line {}", todo.line), "VTODO has no SUMMARY")
+        })
+}
+
+/// The uuid identified by a `UID`, which is either a uuid itself or derived from a foreign `UID`.
+fn parse_uid(uid: &str) -> Uuid {
+    Uuid::try_parse(uid.trim()).unwrap_or_else(|_| Uuid::new_v5(&UID_NAMESPACE, uid.as_bytes()))
+}
+
+fn parse_timestamp(property: &Property) -> Option<DateTime<Utc>> {
+    let value = property.value.trim();
+    if property
+        .param("VALUE")
+        .is_some_and(|value| value.eq_ignore_ascii_case("DATE"))
+        || value.len() == "YYYYMMDD".len()
+    {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    let value = value.strip_suffix('Z').unwrap_or(value);
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|timestamp| timestamp.and_utc())
+}
+
+fn parse_todos(ics: &str) -> Result<Vec<Todo>> {
+    let mut todos = Vec::new();
+    let mut current: Option<Todo> = None;
+    // depth of components nested within the current VTODO, such as VALARM
+    let mut nested = 0;
+
+    for property in unfold(ics) {
+        let property = parse_property(property)?;
+        let is = |name: &str| property.name.eq_ignore_ascii_case(name);
+        let is_vtodo = property.value.eq_ignore_ascii_case("VTODO");
+
+        match &mut current {
+            None if is("BEGIN") && is_vtodo => {
+                current = Some(Todo {
+                    line: property.line,
+                    properties: Vec::new(),
+                });
+            }
+            None => {}
+            Some(_) if is("BEGIN") => nested += 1,
+            Some(_) if is("END") && nested > 0 => nested -= 1,
+            Some(_) if is("END") && is_vtodo => {
+                todos.extend(current.take());
+            }
+            Some(_) if nested > 0 => {}
+            Some(todo) => todo.properties.push(property),
+        }
+    }
+
+    if let Some(todo) = current {
+        return Err(Error::invalid_document(
+            format!("line {}", todo.line),
+            "VTODO is never ended",
+        ));
+    }
+    Ok(todos)
+}
+
+/// Join folded content lines, yielding each with the number of the line on which it starts.
+fn unfold(ics: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (idx, line) in ics.lines().enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, last))) => last.push_str(continuation),
+            _ if line.is_empty() => {}
+            _ => lines.push((idx + 1, line.to_owned())),
+        }
+    }
+    lines
+}
+
+fn parse_property((line, text): (usize, String)) -> Result<Property> {
+    let invalid = |reason: &str| Error::invalid_document(format!("line {line}"), reason);
+
+    // the value starts at the first colon outside a quoted parameter value
+    let mut quoted = false;
+    let colon = text
+        .char_indices()
+        .find(|&(_, c)| {
+            if c == '"' {
+                quoted = !quoted;
+            }
+            c == ':' && !quoted
+        })
+        .map(|(idx, _)| idx)
+        .ok_or_else(|| invalid("expected `NAME:VALUE`"))?;
+    let (head, value) = (&text[..colon], &text[colon + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next().unwrap_or_default().trim().to_owned();
+    if name.is_empty() {
+        return Err(invalid("property has no name"));
+    }
+    let params = parts
+        .filter_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            Some((name.to_owned(), value.trim_matches('"').to_owned()))
+        })
+        .collect();
+
+    Ok(Property {
+        line,
+        name,
+        params,
+        value: value.to_owned(),
+    })
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
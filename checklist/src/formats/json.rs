@@ -0,0 +1,95 @@
+//! JSON interchange format.
+//!
+//! A JSON export is a single object:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "exported_at": "2025-01-31T12:00:00Z",
+//!   "checklists": [
+//!     {
+//!       "id": 1,
+//!       "uuid": "0194b1c2-5e40-7a31-9c2e-3f1d2a4b5c6d",
+//!       "name": "groceries",
+//!       "items": [
+//!         { "id": 1, "uuid": "0194b1c2-5e41-7b02-8d3f-4a5b6c7d8e9f", "item": "milk", "checked": false },
+//!         { "id": 2, "uuid": "0194b1c2-6d83-7c13-a1e4-5b6c7d8e9fa0", "item": "eggs", "checked": true }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - `version` is required, and must equal [`FORMAT_VERSION`]. It is incremented whenever a change to
+//!   the format would cause an older reader to misinterpret a document.
+//! - `exported_at` is informational, and ignored on import.
+//! - `id` and `uuid` fields are optional. See [`ImportMode`] for how each kind of import treats them.
+//! - `items` defaults to empty, and `checked` defaults to `false`.
+//! - Items may also have a `priority` from `"A"` to `"Z"`, and `created_at`, `completed_at` and
+//!   `due_at` RFC 3339 timestamps. Each is omitted when unset.
+//!
+//! Unknown fields are rejected, so that typos surface as errors instead of silently losing data.
+//!
+//! [`ImportMode`]: super::ImportMode
+
+use serde::{Deserialize, Serialize};
+
+use super::{ChecklistRecord, Document};
+use crate::{Error, Result};
+
+/// Version of the JSON format written by [`to_string`].
+pub const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    exported_at: String,
+    checklists: &'a [ChecklistRecord],
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OwnedEnvelope {
+    version: u32,
+    #[serde(default)]
+    #[allow(dead_code)]
+    exported_at: Option<String>,
+    checklists: Vec<ChecklistRecord>,
+}
+
+/// Serialize a document as pretty-printed JSON.
+pub fn to_string(document: &Document) -> Result<String> {
+    let envelope = Envelope {
+        version: FORMAT_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        checklists: &document.checklists,
+    };
+    serde_json::to_string_pretty(&envelope).map_err(|err| Error::invalid_document("$", err))
+}
+
+/// Parse a document from JSON.
+///
+/// Errors identify the offending part of the input by path.
+pub fn from_str(json: &str) -> Result<Document> {
+    let deserializer = &mut serde_json::Deserializer::from_str(json);
+    let envelope: OwnedEnvelope =
+        serde_path_to_error::deserialize(deserializer).map_err(|err| {
+            let path = err.path().to_string();
+            let path = if path == "." { "$".to_owned() } else { path };
+            Error::invalid_document(path, err.into_inner())
+        })?;
+
+    if envelope.version != FORMAT_VERSION {
+        return Err(Error::invalid_document(
+            "version",
+            format!(
+                "unsupported format version {}; expected {FORMAT_VERSION}",
+                envelope.version
+            ),
+        ));
+    }
+
+    Ok(Document {
+        checklists: envelope.checklists,
+    })
+}
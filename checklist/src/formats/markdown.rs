@@ -0,0 +1,267 @@
+//! GitHub-style Markdown task lists.
+//!
+//! A checklist is written as a level-one heading followed by one task-list item per item:
+//!
+//! ```markdown
+//! # groceries <!-- uuid:0194b1c2-5e40-7a31-9c2e-3f1d2a4b5c6d -->
+//!
+//! - [ ] milk <!-- uuid:0194b1c2-5e41-7b02-8d3f-4a5b6c7d8e9f priority:A created:2025-01-30T09:15:00Z -->
+//! - [x] eggs <!-- uuid:0194b1c2-6d83-7c13-a1e4-5b6c7d8e9fa0 completed:2025-01-31T17:00:00Z -->
+//! ```
+//!
+//! The trailing HTML comments carry each record's uuid, and each item's priority and creation,
+//! completion and due times, and are hidden when the Markdown is rendered. They are optional when
+//! reading, as is each attribute within them. A trailing comment which is not made of these
+//! attributes is read as part of the text.
+//!
+//! Backslashes and line breaks within names and items are escaped as `\\` and `\n`, so that anything
+//! written by [`to_string`] is read back unchanged by [`from_str`].
+//!
+//! When reading, the first heading of any level names the checklist. Task-list items may use `-`, `*`
+//! or `+` as their bullet, and `x` or `X` to mark completion. Checklists have no nesting, so indented
+//! items are read as ordinary items of the checklist. Lines which are neither headings nor task-list
+//! items are ignored, which allows importing a task list from a larger document.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::{ChecklistRecord, ItemRecord};
+use crate::{Error, Result, Uuid};
+
+const COMMENT_PREFIX: &str = "<!-- ";
+const COMMENT_SUFFIX: &str = " -->";
+
+/// Write a checklist as a Markdown task list.
+pub fn to_string(checklist: &ChecklistRecord) -> String {
+    let mut markdown = format!("# {}", escape(&checklist.name));
+    write_comment(
+        &mut markdown,
+        &Attributes {
+            uuid: checklist.uuid,
+            ..Attributes::default()
+        },
+    );
+    markdown.push('\n');
+    if !checklist.items.is_empty() {
+        markdown.push('\n');
+    }
+    for item in &checklist.items {
+        let mark = if item.checked { 'x' } else { ' ' };
+        write!(markdown, "- [{mark}] {}", escape(&item.item)).expect("writing to a string");
+        write_comment(&mut markdown, &Attributes::of(item));
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// The attributes of a checklist or item which are written in its trailing comment.
+#[derive(Default)]
+struct Attributes {
+    uuid: Option<Uuid>,
+    priority: Option<char>,
+    created_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    due_at: Option<DateTime<Utc>>,
+}
+
+impl Attributes {
+    fn of(item: &ItemRecord) -> Self {
+        Self {
+            uuid: item.uuid,
+            priority: item.priority,
+            created_at: item.created_at,
+            completed_at: item.completed_at,
+            due_at: item.due_at,
+        }
+    }
+
+    /// Read the space-separated `name:value` attributes of a comment.
+    fn parse(comment: &str) -> Option<Self> {
+        let mut attributes = Self::default();
+        for attribute in comment.split_whitespace() {
+            let (name, value) = attribute.split_once(':')?;
+            match name {
+                "uuid" => attributes.uuid = Some(Uuid::try_parse(value).ok()?),
+                "priority" => attributes.priority = Some(parse_priority(value)?),
+                "created" => attributes.created_at = Some(parse_timestamp(value)?),
+                "completed" => attributes.completed_at = Some(parse_timestamp(value)?),
+                "due" => attributes.due_at = Some(parse_timestamp(value)?),
+                _ => return None,
+            }
+        }
+        Some(attributes)
+    }
+}
+
+fn write_comment(markdown: &mut String, attributes: &Attributes) {
+    let mut comment = Vec::new();
+    comment.extend(attributes.uuid.map(|uuid| format!("uuid:{uuid}")));
+    comment.extend(
+        attributes
+            .priority
+            .map(|priority| format!("priority:{priority}")),
+    );
+    let timestamps = [
+        ("created", &attributes.created_at),
+        ("completed", &attributes.completed_at),
+        ("due", &attributes.due_at),
+    ];
+    for (name, timestamp) in timestamps {
+        if let Some(timestamp) = timestamp {
+            let timestamp = timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+            comment.push(format!("{name}:{timestamp}"));
+        }
+    }
+    if !comment.is_empty() {
+        write!(
+            markdown,
+            " {COMMENT_PREFIX}{}{COMMENT_SUFFIX}",
+            comment.join(" ")
+        )
+        .expect("writing to a string");
+    }
+}
+
+/// Split a trailing attribute comment from `text`, if it has one.
+fn parse_comment(text: &str) -> (&str, Attributes) {
+    let attributes = text.strip_suffix(COMMENT_SUFFIX).and_then(|rest| {
+        let start = rest.rfind(COMMENT_PREFIX)?;
+        let attributes = Attributes::parse(&rest[start + COMMENT_PREFIX.len()..])?;
+        let text = &rest[..start];
+        Some((text.strip_suffix(' ').unwrap_or(text), attributes))
+    });
+    attributes.unwrap_or((text, Attributes::default()))
+}
+
+fn parse_priority(text: &str) -> Option<char> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(priority), None) if priority.is_ascii_uppercase() => Some(priority),
+        _ => None,
+    }
+}
+
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+/// Read a checklist from a Markdown task list.
+///
+/// Errors identify the offending line.
+pub fn from_str(markdown: &str) -> Result<ChecklistRecord> {
+    let mut name = None;
+    let mut uuid = None;
+    let mut items = Vec::new();
+
+    for (idx, line) in markdown.lines().enumerate() {
+        let path = || format!("line {}", idx + 1);
+        let line = line.trim_start();
+
+        if let Some(heading) = parse_heading(line) {
+            if name.is_none() {
+                let (heading, attributes) = parse_comment(heading);
+                name = Some(unescape(heading));
+                uuid = attributes.uuid;
+            }
+            continue;
+        }
+
+        let Some((checked, item)) = parse_task(line) else {
+            continue;
+        };
+        if name.is_none() {
+            return Err(Error::invalid_document(
+                path(),
+                "task-list item appears before the checklist heading",
+            ));
+        }
+        let (item, attributes) = parse_comment(item);
+        let item = unescape(item);
+        if item.trim().is_empty() {
+            return Err(Error::invalid_document(
+                path(),
+                "item text must not be empty",
+            ));
+        }
+        items.push(ItemRecord {
+            id: None,
+            uuid: attributes.uuid,
+            item,
+            checked,
+            priority: attributes.priority,
+            created_at: attributes.created_at,
+            completed_at: attributes.completed_at,
+            due_at: attributes.due_at,
+        });
+    }
+
+    let name =
+        name.ok_or_else(|| Error::invalid_document("line 1", "missing checklist heading"))?;
+    if name.trim().is_empty() {
+        return Err(Error::invalid_document(
+            "line 1",
+            "checklist name must not be empty",
+        ));
+    }
+
+    Ok(ChecklistRecord {
+        id: None,
+        uuid,
+        name,
+        items,
+    })
+}
+
+/// The text of an ATX heading, if `line` is one.
+fn parse_heading(line: &str) -> Option<&str> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+    text.strip_prefix(' ')
+}
+
+/// The check state and text of a task-list item, if `line` is one.
+fn parse_task(line: &str) -> Option<(bool, &str)> {
+    let line = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))?;
+    let checked = match line.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &line[3..];
+    Some((checked, text.strip_prefix(' ').unwrap_or(text)))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            // other backslashes are ordinary Markdown escapes, which are kept as written
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
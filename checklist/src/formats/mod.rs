@@ -0,0 +1,734 @@
+//! Interchange formats.
+//!
+//! Every format converts to and from a [`Document`], which is a complete, format-independent snapshot
+//! of the database. Importing a document into a database happens in a single transaction.
+
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod ics;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod markdown;
+pub mod org;
+pub mod todotxt;
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection, Row};
+
+use crate::{timestamp, uuids, Checklist, ChecklistId, Db, Error, ItemId, Result, Uuid};
+
+/// Checklist for imported items which do not name one.
+pub const DEFAULT_CHECKLIST: &str = "inbox";
+
+/// A format-independent snapshot of some or all of a database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Document {
+    pub checklists: Vec<ChecklistRecord>,
+}
+
+/// A checklist and all of its items.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct ChecklistRecord {
+    /// Id of the checklist in the database it came from, if known
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<ChecklistId>,
+    /// Globally unique id of the checklist, if known
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub uuid: Option<Uuid>,
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub items: Vec<ItemRecord>,
+}
+
+/// A single checklist item and its check state.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct ItemRecord {
+    /// Id of the item in the database it came from, if known
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<ItemId>,
+    /// Globally unique id of the item, if known
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub uuid: Option<Uuid>,
+    pub item: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub checked: bool,
+    /// Priority from `A` (highest) to `Z` (lowest)
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub priority: Option<char>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl ItemRecord {
+    /// Read a row of `id, uuid, item, checked, priority, created_at, completed_at, due_at`.
+    fn from_row(row: &Row) -> Result<Self> {
+        let id = row.get::<i64>(0).map_err(Error::libsql(
+            "getting id from result row while exporting items",
+        ))?;
+        let uuid = row.get_str(1).map_err(Error::libsql(
+            "getting uuid from result row while exporting items",
+        ))?;
+        let item = row.get_str(2).map_err(Error::libsql(
+            "getting item from result row while exporting items",
+        ))?;
+        let checked = row.get::<bool>(3).map_err(Error::libsql(
+            "getting checked status from result row while exporting items",
+        ))?;
+        let priority = row.get::<Option<String>>(4).map_err(Error::libsql(
+            "getting priority from result row while exporting items",
+        ))?;
+        let created_at = row.get::<Option<String>>(5).map_err(Error::libsql(
+            "getting creation time from result row while exporting items",
+        ))?;
+        let completed_at = row.get::<Option<String>>(6).map_err(Error::libsql(
+            "getting completion time from result row while exporting items",
+        ))?;
+        let due_at = row.get::<Option<String>>(7).map_err(Error::libsql(
+            "getting due time from result row while exporting items",
+        ))?;
+
+        Ok(Self {
+            id: Some(id.into()),
+            uuid: Some(uuids::from_sql(uuid)?),
+            item: item.to_owned(),
+            checked,
+            priority: priority.and_then(|priority| priority.chars().next()),
+            created_at: created_at.as_deref().map(timestamp::from_sql).transpose()?,
+            completed_at: completed_at
+                .as_deref()
+                .map(timestamp::from_sql)
+                .transpose()?,
+            due_at: due_at.as_deref().map(timestamp::from_sql).transpose()?,
+        })
+    }
+}
+
+/// How to combine an imported document with the existing contents of a database.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, derive_more::Display, derive_more::FromStr,
+)]
+pub enum ImportMode {
+    /// Add everything in the document as new checklists and items, assigning fresh ids
+    ///
+    /// Uuids are kept, except those which already exist in the database.
+    #[default]
+    #[display("merge")]
+    Merge,
+    /// Delete everything in the database, then insert the document, keeping its ids and uuids where
+    /// present
+    #[display("replace")]
+    Replace,
+    /// Overwrite checklists and items which already exist, and insert the rest
+    ///
+    /// Records are matched by uuid where present, and by id otherwise. Checklists with neither, as read
+    /// from formats which identify checklists only by name, are matched by name.
+    #[display("update")]
+    Update,
+}
+
+/// What an import wrote to the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ImportSummary {
+    pub checklists: usize,
+    pub items: usize,
+}
+
+/// What applying an edited Markdown task list changed, from [`Checklist::apply_markdown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EditSummary {
+    pub renamed: bool,
+    pub added: usize,
+    pub removed: usize,
+    /// Items whose text changed
+    pub edited: usize,
+    /// Items which were checked or unchecked
+    pub toggled: usize,
+    /// The fewest items which could have been moved to give the new order
+    pub moved: usize,
+}
+
+impl Document {
+    /// Snapshot every checklist and item in the database.
+    pub async fn export(db: &Db) -> Result<Self> {
+        let conn = db.conn()?;
+        let mut ids = Vec::new();
+        let mut rows = conn
+            .query("SELECT id FROM checklists ORDER BY id", ())
+            .await
+            .map_err(Error::libsql("listing checklists for export"))?;
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next row while exporting checklists"))?
+        {
+            let id = row.get::<i64>(0).map_err(Error::libsql(
+                "getting id from result row while exporting checklists",
+            ))?;
+            ids.push(ChecklistId::new(id));
+        }
+
+        let mut checklists = Vec::with_capacity(ids.len());
+        for id in ids {
+            checklists.extend(ChecklistRecord::load(db, id).await?);
+        }
+
+        Ok(Self { checklists })
+    }
+
+    /// Check that this document can be imported with the given mode.
+    ///
+    /// Errors identify the offending part of the document by path, e.g. `checklists[2].items[0].id`.
+    pub fn validate(&self, mode: ImportMode) -> Result<()> {
+        let mut checklist_ids = HashSet::new();
+        let mut item_ids = HashSet::new();
+        let mut uuids = HashSet::new();
+
+        for (checklist_idx, checklist) in self.checklists.iter().enumerate() {
+            let path = format!("checklists[{checklist_idx}]");
+            if checklist.name.trim().is_empty() {
+                return Err(Error::invalid_document(
+                    format!("{path}.name"),
+                    "checklist name must not be empty",
+                ));
+            }
+            if mode != ImportMode::Merge {
+                if let Some(id) = checklist.id {
+                    if !checklist_ids.insert(id) {
+                        return Err(Error::invalid_document(
+                            format!("{path}.id"),
+                            format!("duplicate checklist id {id}"),
+                        ));
+                    }
+                }
+                if let Some(uuid) = checklist.uuid {
+                    if !uuids.insert(uuid) {
+                        return Err(Error::invalid_document(
+                            format!("{path}.uuid"),
+                            format!("duplicate uuid {uuid}"),
+                        ));
+                    }
+                }
+            }
+
+            for (item_idx, item) in checklist.items.iter().enumerate() {
+                let path = format!("{path}.items[{item_idx}]");
+                if item.item.trim().is_empty() {
+                    return Err(Error::invalid_document(
+                        format!("{path}.item"),
+                        "item text must not be empty",
+                    ));
+                }
+                if let Some(priority) = item.priority {
+                    if !priority.is_ascii_uppercase() {
+                        return Err(Error::invalid_document(
+                            format!("{path}.priority"),
+                            format!("priority must be a letter from A to Z, not {priority:?}"),
+                        ));
+                    }
+                }
+                if mode != ImportMode::Merge {
+                    if let Some(id) = item.id {
+                        if !item_ids.insert(id) {
+                            return Err(Error::invalid_document(
+                                format!("{path}.id"),
+                                format!("duplicate item id {id}"),
+                            ));
+                        }
+                    }
+                    if let Some(uuid) = item.uuid {
+                        if !uuids.insert(uuid) {
+                            return Err(Error::invalid_document(
+                                format!("{path}.uuid"),
+                                format!("duplicate uuid {uuid}"),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Import this document into the database in a single transaction.
+    pub async fn import(&self, db: &Db, mode: ImportMode) -> Result<ImportSummary> {
+        self.validate(mode)?;
+
+        let conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .await
+            .map_err(Error::libsql("beginning import transaction"))?;
+
+        if mode == ImportMode::Replace {
+            tx.execute("DELETE FROM items", ())
+                .await
+                .map_err(Error::libsql("clearing items for replacement"))?;
+            tx.execute("DELETE FROM checklists", ())
+                .await
+                .map_err(Error::libsql("clearing checklists for replacement"))?;
+        }
+
+        let kept = self.kept_ids(mode);
+        let mut summary = ImportSummary::default();
+        for checklist in &self.checklists {
+            let (_, items) = checklist.insert(&tx, mode, kept).await?;
+            summary.checklists += 1;
+            summary.items += items;
+        }
+
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing import transaction"))?;
+
+        Ok(summary)
+    }
+}
+
+impl Document {
+    /// The greatest ids which records of this document keep when imported with `mode`.
+    fn kept_ids(&self, mode: ImportMode) -> KeptIds {
+        let mut kept = KeptIds::default();
+        for checklist in &self.checklists {
+            if let Some(id) = import_id(checklist.id, checklist.uuid, mode) {
+                kept.checklists = kept.checklists.max(*id);
+            }
+            for item in &checklist.items {
+                if let Some(id) = import_id(item.id, item.uuid, mode) {
+                    kept.items = kept.items.max(*id);
+                }
+            }
+        }
+        kept
+    }
+}
+
+/// The greatest ids which the records of an imported document keep.
+///
+/// Records which do not keep their ids are given ids above these, so that a later record which keeps
+/// its id cannot overwrite one imported before it.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct KeptIds {
+    checklists: i64,
+    items: i64,
+}
+
+impl ChecklistRecord {
+    /// Snapshot a single checklist and its items.
+    pub async fn load(db: &Db, id: ChecklistId) -> Result<Option<Self>> {
+        let Some(checklist) = Checklist::load(db, id).await? else {
+            return Ok(None);
+        };
+
+        let items = checklist_items(&db.conn()?, id).await?;
+        Ok(Some(Self {
+            id: Some(checklist.id),
+            uuid: Some(checklist.uuid),
+            name: checklist.name,
+            items,
+        }))
+    }
+
+    /// Insert this checklist and its items, returning the new checklist's id and the number of items.
+    ///
+    /// See [`ImportMode`] for how ids and uuids are treated.
+    pub(crate) async fn insert(
+        &self,
+        conn: &Connection,
+        mode: ImportMode,
+        kept: KeptIds,
+    ) -> Result<(ChecklistId, usize)> {
+        let existing = match (mode, self.id, self.uuid) {
+            (ImportMode::Update, None, None) => checklist_by_name(conn, &self.name).await?,
+            _ => None,
+        };
+        let checklist_id = match existing {
+            Some(id) => id,
+            None => {
+                let id = import_id(self.id, self.uuid, mode);
+                let uuid = import_uuid(conn, "checklists", self.uuid, mode).await?;
+                insert_checklist(conn, id, uuid, &self.name, kept.checklists).await?
+            }
+        };
+        for item in &self.items {
+            let id = import_id(item.id, item.uuid, mode);
+            let uuid = import_uuid(conn, "items", item.uuid, mode).await?;
+            insert_item(conn, id, uuid, checklist_id, item, kept.items).await?;
+        }
+        Ok((checklist_id, self.items.len()))
+    }
+
+    /// Make the checklist `id` match this record, which is an edited copy of `original`.
+    ///
+    /// Items are matched by uuid, and the first item with each of the checklist's uuids is kept. The
+    /// rest are added with new uuids, and items which are no longer listed are deleted. Fails with
+    /// [`Error::Conflict`] if the checklist no longer matches `original`.
+    pub(crate) async fn apply_edit(
+        &self,
+        conn: &Connection,
+        id: ChecklistId,
+        original: &str,
+    ) -> Result<EditSummary> {
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, name, version FROM checklists WHERE id = ?1",
+                [*id],
+            )
+            .await
+            .map_err(Error::libsql("getting checklist to edit"))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting row of checklist to edit"))?
+            .ok_or(Error::MissingChecklist)?;
+        let checklist = Checklist::from_row(&row)?;
+        let current = Self {
+            id: Some(checklist.id),
+            uuid: Some(checklist.uuid),
+            name: checklist.name,
+            items: checklist_items(conn, id).await?,
+        };
+        if markdown::to_string(&current) != original {
+            return Err(Error::Conflict {
+                current: checklist.version,
+            });
+        }
+
+        let mut summary = EditSummary::default();
+        if self.name != current.name {
+            conn.execute(
+                "UPDATE checklists SET name = ?1 WHERE id = ?2",
+                params!(self.name.as_str(), *id),
+            )
+            .await
+            .map_err(Error::libsql("renaming edited checklist"))?;
+            summary.renamed = true;
+        }
+
+        let mut kept = HashSet::new();
+        let mut order = Vec::with_capacity(self.items.len());
+        for item in &self.items {
+            let existing = item
+                .uuid
+                .filter(|uuid| kept.insert(*uuid))
+                .and_then(|uuid| current.items.iter().find(|old| old.uuid == Some(uuid)));
+            let Some(existing) = existing else {
+                let uuid = uuids::new();
+                let now = Utc::now();
+                let added = ItemRecord {
+                    id: None,
+                    uuid: Some(uuid),
+                    created_at: Some(now),
+                    completed_at: item.checked.then_some(now),
+                    ..item.clone()
+                };
+                insert_item(conn, None, uuid, id, &added, 0).await?;
+                order.push(uuid);
+                summary.added += 1;
+                continue;
+            };
+            let item_id = existing.id.map(|id| *id);
+            if item.item != existing.item {
+                conn.execute(
+                    "UPDATE items SET item = ?1 WHERE id = ?2",
+                    params!(item.item.as_str(), item_id),
+                )
+                .await
+                .map_err(Error::libsql("editing item text"))?;
+                summary.edited += 1;
+            }
+            if item.checked != existing.checked {
+                conn.execute(
+                    "UPDATE items SET checked = ?1, completed_at = ?2 WHERE id = ?3",
+                    params!(item.checked, item.checked.then(timestamp::now), item_id),
+                )
+                .await
+                .map_err(Error::libsql("toggling edited item"))?;
+                summary.toggled += 1;
+            }
+            order.extend(existing.uuid);
+        }
+
+        for old in &current.items {
+            if old.uuid.is_some_and(|uuid| !kept.contains(&uuid)) {
+                conn.execute("DELETE FROM items WHERE id = ?1", [old.id.map(|id| *id)])
+                    .await
+                    .map_err(Error::libsql("deleting removed item"))?;
+                summary.removed += 1;
+            }
+        }
+
+        // only items whose position changes are written, and so stamped for sync
+        for (position, uuid) in order.iter().enumerate() {
+            conn.execute(
+                "UPDATE items SET position = ?1 WHERE uuid = ?2 AND position IS NOT ?1",
+                params!(position as i64 + 1, uuids::to_sql(uuid)),
+            )
+            .await
+            .map_err(Error::libsql("updating edited item position"))?;
+        }
+        let before: Vec<usize> = order
+            .iter()
+            .filter_map(|uuid| current.items.iter().position(|old| old.uuid == Some(*uuid)))
+            .collect();
+        summary.moved = before.len() - longest_increasing(&before);
+
+        Ok(summary)
+    }
+}
+
+/// Read the items of a checklist, in order.
+async fn checklist_items(conn: &Connection, id: ChecklistId) -> Result<Vec<ItemRecord>> {
+    let mut items = Vec::new();
+    let mut rows = conn
+        .query(
+            "SELECT id, uuid, item, checked, priority, created_at, completed_at, due_at
+            FROM items WHERE checklist = ?1 ORDER BY position, id",
+            [*id],
+        )
+        .await
+        .map_err(Error::libsql("listing checklist items for export"))?;
+    while let Some(row) = rows.next().await.map_err(Error::libsql(
+        "getting next row while exporting checklist items",
+    ))? {
+        items.push(ItemRecord::from_row(&row)?);
+    }
+    Ok(items)
+}
+
+/// The length of the longest increasing subsequence of `values`.
+///
+/// Given the old places of the kept items in their new order, the rest are the fewest items which
+/// could have been moved to give that order.
+fn longest_increasing(values: &[usize]) -> usize {
+    // the smallest last value of an increasing subsequence of each length
+    let mut tails: Vec<usize> = Vec::new();
+    for &value in values {
+        let length = tails.partition_point(|tail| *tail < value);
+        if length == tails.len() {
+            tails.push(value);
+        } else {
+            tails[length] = value;
+        }
+    }
+    tails.len()
+}
+
+/// The id with which to import a record.
+///
+/// Ids are only meaningful within the database they came from, so they are discarded when merging,
+/// and when updating a record which can be matched by its uuid instead.
+fn import_id<Id>(id: Option<Id>, uuid: Option<Uuid>, mode: ImportMode) -> Option<Id> {
+    match mode {
+        ImportMode::Merge => None,
+        ImportMode::Replace => id,
+        ImportMode::Update => id.filter(|_| uuid.is_none()),
+    }
+}
+
+/// The uuid with which to import a record into `table`.
+///
+/// When merging, a record whose uuid already exists is a copy, so it is given a fresh uuid.
+async fn import_uuid(
+    conn: &Connection,
+    table: &str,
+    uuid: Option<Uuid>,
+    mode: ImportMode,
+) -> Result<Uuid> {
+    let Some(uuid) = uuid else {
+        return Ok(uuids::new());
+    };
+    if mode != ImportMode::Merge {
+        return Ok(uuid);
+    }
+
+    let mut rows = conn
+        .query(
+            &format!("SELECT EXISTS(SELECT 1 FROM {table} WHERE uuid = ?1)"),
+            [uuids::to_sql(&uuid)],
+        )
+        .await
+        .map_err(Error::libsql("checking for existing uuid"))?;
+    let exists = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for existing uuid"))?
+        .expect("EXISTS always produces a row")
+        .get::<bool>(0)
+        .map_err(Error::libsql("getting result of existing uuid check"))?;
+
+    Ok(if exists { uuids::new() } else { uuid })
+}
+
+/// The id of the oldest checklist named `name`, if any.
+async fn checklist_by_name(conn: &Connection, name: &str) -> Result<Option<ChecklistId>> {
+    let mut rows = conn
+        .query(
+            "SELECT id FROM checklists WHERE name = ?1 ORDER BY id LIMIT 1",
+            [name],
+        )
+        .await
+        .map_err(Error::libsql("finding checklist by name"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for checklist by name"))?;
+    row.map(|row| {
+        row.get::<i64>(0).map(Into::into).map_err(Error::libsql(
+            "getting id from result row while finding checklist by name",
+        ))
+    })
+    .transpose()
+}
+
+/// The row of `table` to overwrite with an imported record: the one with its uuid, or else its id.
+///
+/// An upsert would be simpler, but its conflict handling would override the `INSERT OR REPLACE` of the
+/// sync triggers which the update fires.
+fn existing_row(table: &str) -> String {
+    format!(
+        "(SELECT id FROM {table} WHERE uuid = ?2 UNION ALL SELECT id FROM {table} WHERE id = ?1 LIMIT 1)"
+    )
+}
+
+/// The id of a record inserted into `table`: its own, `?1`, or else one above both `kept` and every
+/// existing id.
+fn new_id(table: &str, kept: i64) -> String {
+    format!("coalesce(?1, (SELECT max(coalesce(max(id), 0), {kept}) + 1 FROM {table}))")
+}
+
+/// Overwrite the checklist which an imported record matches, or insert it.
+async fn insert_checklist(
+    conn: &Connection,
+    id: Option<ChecklistId>,
+    uuid: Uuid,
+    name: &str,
+    kept: i64,
+) -> Result<ChecklistId> {
+    let params = || params!(id.map(|id| *id), uuids::to_sql(&uuid), name);
+    let mut rows = conn
+        .query(
+            &format!(
+                "UPDATE checklists SET name = ?3 WHERE id = {} RETURNING id",
+                existing_row("checklists")
+            ),
+            params(),
+        )
+        .await
+        .map_err(Error::libsql("importing existing checklist"))?;
+    let row = match rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for importing checklist"))?
+    {
+        Some(row) => row,
+        None => conn
+            .query(
+                &format!(
+                    "INSERT INTO checklists(id, uuid, name) VALUES ({}, ?2, ?3) RETURNING id",
+                    new_id("checklists", kept)
+                ),
+                params(),
+            )
+            .await
+            .map_err(Error::libsql("importing checklist"))?
+            .next()
+            .await
+            .map_err(Error::libsql("getting result row for importing checklist"))?
+            .expect("insert query with RETURNING always produces at least one row"),
+    };
+    row.get::<i64>(0).map(Into::into).map_err(Error::libsql(
+        "getting id from result row while importing checklist",
+    ))
+}
+
+/// Overwrite the item which an imported record matches, or insert it.
+async fn insert_item(
+    conn: &Connection,
+    id: Option<ItemId>,
+    uuid: Uuid,
+    checklist: ChecklistId,
+    item: &ItemRecord,
+    kept: i64,
+) -> Result<()> {
+    let params = || {
+        params!(
+            id.map(|id| *id),
+            uuids::to_sql(&uuid),
+            *checklist,
+            item.item.as_str(),
+            item.checked,
+            item.priority.map(String::from),
+            item.created_at.as_ref().map(timestamp::to_sql),
+            item.completed_at.as_ref().map(timestamp::to_sql),
+            item.due_at.as_ref().map(timestamp::to_sql),
+        )
+    };
+    let updated = conn
+        .execute(
+            &format!(
+                "UPDATE items SET
+                    checklist = ?3, item = ?4, checked = ?5, priority = ?6, created_at = ?7,
+                    completed_at = ?8, due_at = ?9
+                WHERE id = {}",
+                existing_row("items")
+            ),
+            params(),
+        )
+        .await
+        .map_err(Error::libsql("importing existing item"))?;
+    if updated == 0 {
+        conn.execute(
+            &format!(
+                "INSERT INTO items(
+                    id, uuid, checklist, item, checked, priority, created_at, completed_at, due_at,
+                    position
+                )
+                VALUES (
+                    {}, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9,
+                    (SELECT coalesce(max(position), 0) + 1 FROM items WHERE checklist = ?3)
+                )",
+                new_id("items", kept)
+            ),
+            params(),
+        )
+        .await
+        .map_err(Error::libsql("importing item"))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,335 @@
+//! Emacs [Org mode](https://orgmode.org/) outlines.
+//!
+//! Each checklist is written as a top-level headline, and each of its items as a `TODO` or `DONE`
+//! headline beneath it:
+//!
+//! ```org
+//! * house
+//! :PROPERTIES:
+//! :ID: 0194b1c2-5e40-7a31-9c2e-3f1d2a4b5c6d
+//! :END:
+//! ** TODO [#A] call the plumber
+//! DEADLINE: <2025-02-01 Sat>
+//! :PROPERTIES:
+//! :ID: 0194b1c2-5e41-7b02-8d3f-4a5b6c7d8e9f
+//! :CREATED: [2025-01-30 Thu 09:15]
+//! :END:
+//! ** DONE buy a new washer
+//! CLOSED: [2025-01-31 Fri 17:00]
+//! ```
+//!
+//! - `TODO` and `DONE` map to the item's check state, and `[#A]` through `[#Z]` to its priority.
+//! - `DEADLINE` maps to the item's due date, or `SCHEDULED` when there is no deadline.
+//! - `CLOSED` maps to the item's completion time, and the `CREATED` property to its creation time.
+//! - The `ID` property, as used by `org-id`, maps to the uuid of a checklist or item. Other ids are
+//!   ignored.
+//!
+//! When reading, any headline without a `TODO` or `DONE` keyword starts a new checklist, whatever its
+//! level. Plain-list checkboxes such as `- [ ] item` and `- [X] item` also become items of the
+//! current checklist. Items which appear before any checklist headline go into the
+//! [`DEFAULT_CHECKLIST`]. Other text is ignored.
+//!
+//! Names and item text which would otherwise be read as a keyword or priority are written after a
+//! `\`, which is removed when reading headlines, as is a leading `\` of any other headline text.
+//!
+//! Org timestamps have no time zone; they are read and written as UTC, with minute precision. Line
+//! breaks within names and items are written as spaces.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+
+use super::{ChecklistRecord, Document, ItemRecord, DEFAULT_CHECKLIST};
+use crate::{Result, Uuid};
+
+/// Write a document as an Org outline.
+pub fn to_string(document: &Document) -> String {
+    let mut org = String::new();
+    for checklist in &document.checklists {
+        writeln!(org, "* {}", headline_text(&checklist.name)).expect("writing to a string");
+        if let Some(uuid) = checklist.uuid {
+            writeln!(org, ":PROPERTIES:\n:ID: {uuid}\n:END:").expect("writing to a string");
+        }
+        for item in &checklist.items {
+            write_item(&mut org, item);
+        }
+    }
+    org
+}
+
+fn write_item(org: &mut String, item: &ItemRecord) {
+    let keyword = if item.checked { "DONE" } else { "TODO" };
+    write!(org, "** {keyword} ").expect("writing to a string");
+    if let Some(priority) = item.priority {
+        write!(org, "[#{priority}] ").expect("writing to a string");
+    }
+    writeln!(org, "{}", headline_text(&item.item)).expect("writing to a string");
+
+    let mut planning = Vec::new();
+    if let Some(completed_at) = &item.completed_at {
+        planning.push(format!("CLOSED: [{}]", timestamp(completed_at)));
+    }
+    if let Some(due_at) = &item.due_at {
+        planning.push(format!("DEADLINE: <{}>", timestamp(due_at)));
+    }
+    if !planning.is_empty() {
+        writeln!(org, "{}", planning.join(" ")).expect("writing to a string");
+    }
+
+    let mut properties = Vec::new();
+    if let Some(uuid) = item.uuid {
+        properties.push(format!(":ID: {uuid}"));
+    }
+    if let Some(created_at) = &item.created_at {
+        properties.push(format!(":CREATED: [{}]", timestamp(created_at)));
+    }
+    if !properties.is_empty() {
+        writeln!(org, ":PROPERTIES:\n{}\n:END:", properties.join("\n"))
+            .expect("writing to a string");
+    }
+}
+
+/// Text for a headline, escaped if it would be read as anything other than text.
+fn headline_text(text: &str) -> String {
+    let text = text.replace(['\r', '\n'], " ");
+    if text.starts_with('\\') || parse_keyword(&text).is_some() || parse_priority(&text).0.is_some()
+    {
+        format!("\\{text}")
+    } else {
+        text
+    }
+}
+
+/// Headline text with any escaping `\` removed.
+fn unescape(text: &str) -> &str {
+    text.strip_prefix('\\').unwrap_or(text)
+}
+
+/// Format the body of an Org timestamp, omitting the time at midnight.
+fn timestamp(timestamp: &DateTime<Utc>) -> String {
+    if timestamp.time() == NaiveTime::MIN {
+        timestamp.format("%Y-%m-%d %a").to_string()
+    } else {
+        timestamp.format("%Y-%m-%d %a %H:%M").to_string()
+    }
+}
+
+/// A checklist or item read from a headline.
+#[derive(Clone, Copy)]
+enum Headline {
+    Checklist(usize),
+    Item { checklist: usize, item: usize },
+}
+
+/// Where planning lines and properties following a line should be applied.
+#[derive(Clone, Copy)]
+enum Context {
+    /// Nothing; they are ignored
+    None,
+    /// The most recent headline
+    Headline(Headline),
+    /// The properties drawer of the most recent headline
+    Properties(Headline),
+}
+
+/// Read a document from an Org outline.
+pub fn from_str(org: &str) -> Result<Document> {
+    let mut checklists = Vec::<ChecklistRecord>::new();
+    let mut current = None;
+    let mut context = Context::None;
+
+    for line in org.lines() {
+        let trimmed = line.trim();
+
+        if let Some(title) = parse_headline(line) {
+            let headline = match parse_keyword(title) {
+                Some((checked, rest)) => {
+                    let (priority, text) = parse_priority(rest);
+                    let checklist =
+                        *current.get_or_insert_with(|| default_checklist(&mut checklists));
+                    let items = &mut checklists[checklist].items;
+                    items.push(ItemRecord {
+                        item: unescape(text).to_owned(),
+                        checked,
+                        priority,
+                        ..ItemRecord::default()
+                    });
+                    Headline::Item {
+                        checklist,
+                        item: items.len() - 1,
+                    }
+                }
+                None => {
+                    checklists.push(ChecklistRecord {
+                        id: None,
+                        uuid: None,
+                        name: unescape(title).to_owned(),
+                        items: Vec::new(),
+                    });
+                    current = Some(checklists.len() - 1);
+                    Headline::Checklist(checklists.len() - 1)
+                }
+            };
+            context = Context::Headline(headline);
+            continue;
+        }
+
+        match context {
+            Context::Headline(Headline::Item { checklist, item }) if is_planning(trimmed) => {
+                apply_planning(&mut checklists[checklist].items[item], trimmed);
+                continue;
+            }
+            Context::Headline(headline) if trimmed.eq_ignore_ascii_case(":PROPERTIES:") => {
+                context = Context::Properties(headline);
+                continue;
+            }
+            Context::Properties(headline) => {
+                if trimmed.eq_ignore_ascii_case(":END:") {
+                    context = Context::Headline(headline);
+                } else {
+                    apply_property(&mut checklists, headline, trimmed);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some((checked, text)) = parse_checkbox(trimmed) {
+            let checklist = *current.get_or_insert_with(|| default_checklist(&mut checklists));
+            checklists[checklist].items.push(ItemRecord {
+                item: text.to_owned(),
+                checked,
+                ..ItemRecord::default()
+            });
+        }
+        if !trimmed.is_empty() {
+            context = Context::None;
+        }
+    }
+
+    Ok(Document { checklists })
+}
+
+fn apply_property(checklists: &mut [ChecklistRecord], headline: Headline, line: &str) {
+    let Some((name, value)) = line.strip_prefix(':').and_then(|line| line.split_once(':')) else {
+        return;
+    };
+    let value = value.trim();
+
+    match (headline, name.to_ascii_uppercase().as_str()) {
+        (Headline::Checklist(checklist), "ID") => {
+            checklists[checklist].uuid = Uuid::try_parse(value).ok();
+        }
+        (Headline::Item { checklist, item }, "ID") => {
+            checklists[checklist].items[item].uuid = Uuid::try_parse(value).ok();
+        }
+        (Headline::Item { checklist, item }, "CREATED") => {
+            if let Some(created) = parse_timestamp(value) {
+                checklists[checklist].items[item].created_at = Some(created);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn default_checklist(checklists: &mut Vec<ChecklistRecord>) -> usize {
+    checklists.push(ChecklistRecord {
+        id: None,
+        uuid: None,
+        name: DEFAULT_CHECKLIST.to_owned(),
+        items: Vec::new(),
+    });
+    checklists.len() - 1
+}
+
+/// The title of a headline, if `line` is one.
+fn parse_headline(line: &str) -> Option<&str> {
+    let title = line.trim_start_matches('*');
+    if title.len() == line.len() {
+        return None;
+    }
+    title.strip_prefix(' ').map(str::trim_start)
+}
+
+/// The check state implied by a headline's `TODO` or `DONE` keyword, and the rest of its title.
+fn parse_keyword(title: &str) -> Option<(bool, &str)> {
+    [("TODO", false), ("DONE", true)]
+        .into_iter()
+        .find_map(|(keyword, checked)| {
+            let rest = title.strip_prefix(keyword)?;
+            if rest.is_empty() {
+                return Some((checked, rest));
+            }
+            rest.strip_prefix(' ').map(|rest| (checked, rest))
+        })
+}
+
+fn parse_priority(title: &str) -> (Option<char>, &str) {
+    match title.as_bytes() {
+        [b'[', b'#', priority, b']', ..] if priority.is_ascii_uppercase() => {
+            (Some(char::from(*priority)), title[4..].trim_start())
+        }
+        _ => (None, title),
+    }
+}
+
+fn parse_checkbox(line: &str) -> Option<(bool, &str)> {
+    let line = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("+ "))?;
+    let checked = match line.get(..3)? {
+        "[ ]" | "[-]" => false,
+        "[X]" | "[x]" => true,
+        _ => return None,
+    };
+    let text = line[3..].trim_start();
+    (!text.is_empty()).then_some((checked, text))
+}
+
+const PLANNING_KEYWORDS: [&str; 3] = ["CLOSED:", "DEADLINE:", "SCHEDULED:"];
+
+fn is_planning(line: &str) -> bool {
+    PLANNING_KEYWORDS
+        .iter()
+        .any(|keyword| line.starts_with(keyword))
+}
+
+fn apply_planning(item: &mut ItemRecord, line: &str) {
+    let mut scheduled = None;
+    let mut rest = line;
+    while let Some((keyword, after)) = PLANNING_KEYWORDS
+        .iter()
+        .find_map(|keyword| Some((*keyword, rest.strip_prefix(keyword)?.trim_start())))
+    {
+        let Some(end) = after.find([']', '>']) else {
+            break;
+        };
+        let value = parse_timestamp(&after[..=end]);
+        match keyword {
+            "CLOSED:" => item.completed_at = value.or(item.completed_at),
+            "DEADLINE:" => item.due_at = value.or(item.due_at),
+            _ => scheduled = value,
+        }
+        rest = after[end + 1..].trim_start();
+    }
+    if item.due_at.is_none() {
+        item.due_at = scheduled;
+    }
+}
+
+/// Parse an active `<...>` or inactive `[...]` Org timestamp, ignoring repeaters and time ranges.
+fn parse_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let body = text
+        .strip_prefix('<')
+        .and_then(|body| body.strip_suffix('>'))
+        .or_else(|| text.strip_prefix('[')?.strip_suffix(']'))?;
+    let mut parts = body.split_whitespace();
+    let date = NaiveDate::parse_from_str(parts.next()?, "%Y-%m-%d").ok()?;
+    let time = parts
+        .find_map(|part| {
+            let start = part.split('-').next()?;
+            NaiveTime::parse_from_str(start, "%H:%M").ok()
+        })
+        .unwrap_or(NaiveTime::MIN);
+    Some(date.and_time(time).and_utc())
+}
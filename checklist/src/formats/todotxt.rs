@@ -0,0 +1,286 @@
+//! [todo.txt](https://github.com/todotxt/todo.txt) interoperability.
+//!
+//! Each item is written as one task, tagged with its checklist as a `+project`:
+//!
+//! ```text
+//! (A) 2025-01-30 call the plumber @phone +house uuid:0194b1c2-5e41-7b02-8d3f-4a5b6c7d8e9f
+//! x 2025-01-31 2025-01-29 buy milk +groceries uuid:0194b1c2-6d83-7c13-a1e4-5b6c7d8e9fa0
+//! x 2025-01-31 2025-01-29 file taxes due:2025-04-15 +admin pri:B
+//! ```
+//!
+//! - A leading `x` marks the item as checked, and is followed by the completion date if known.
+//! - A priority `(A)` through `(Z)` maps to the item's priority. todo.txt drops priorities from
+//!   completed tasks, so those are written as a `pri:` extension instead.
+//! - The creation date follows the priority, or the completion date for completed tasks. A date
+//!   alone after `x` is a completion date, so the creation date of a completed task without one is
+//!   written as a `created:` extension instead.
+//! - The last `+project` of a task names its checklist, and is removed from the item's text. Tasks
+//!   without a project go into the [`DEFAULT_CHECKLIST`]. Projects cannot contain whitespace, so
+//!   spaces in checklist names are written as `_`, and other whitespace, `_` and `%` as `%` and two
+//!   hex digits per byte. A line holding only a project is an empty checklist.
+//! - Item text which would otherwise be read as a completion mark, priority or date is written after
+//!   a `\`, which is removed when reading, as is a leading `\` of any other text.
+//! - A `uuid:` extension carries the item's uuid, which identifies it across imports. Checklists are
+//!   identified only by name.
+//! - Everything else, including further projects, `@context`s and `key:value` extensions, is kept in
+//!   the item's text, so that it survives a round trip.
+//!
+//! Dates are read as midnight UTC, and only the date of each timestamp is written. Line breaks within
+//! items are written as spaces.
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use super::{ChecklistRecord, Document, ItemRecord, DEFAULT_CHECKLIST};
+use crate::{Error, Result, Uuid};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATE_FORMAT_LEN: usize = "YYYY-MM-DD".len();
+
+/// Write a document as todo.txt, one task per item.
+pub fn to_string(document: &Document) -> String {
+    let mut todo = String::new();
+    for checklist in &document.checklists {
+        let project = encode_project(&checklist.name);
+        if checklist.items.is_empty() {
+            writeln!(todo, "+{project}").expect("writing to a string");
+        }
+        for item in &checklist.items {
+            write_task(&mut todo, &project, item);
+        }
+    }
+    todo
+}
+
+fn encode_project(name: &str) -> String {
+    let mut project = String::new();
+    for char in name.chars() {
+        match char {
+            ' ' => project.push('_'),
+            '_' | '%' => write!(project, "%{:02X}", char as u32).expect("writing to a string"),
+            char if char.is_whitespace() => {
+                for byte in char.to_string().bytes() {
+                    write!(project, "%{byte:02X}").expect("writing to a string");
+                }
+            }
+            char => project.push(char),
+        }
+    }
+    project
+}
+
+/// Read a project written by [`encode_project`], leaving any `%` not followed by two hex digits.
+fn decode_project(project: &str) -> String {
+    let mut bytes = Vec::new();
+    let mut rest = project.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        let escaped = after
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, escaped) {
+            (b'%', Some(escaped)) => {
+                bytes.push(escaped);
+                rest = &after[2..];
+                continue;
+            }
+            (b'_', _) => bytes.push(b' '),
+            (byte, _) => bytes.push(byte),
+        }
+        rest = after;
+    }
+    String::from_utf8(bytes).unwrap_or_else(|_| project.to_owned())
+}
+
+fn write_task(todo: &mut String, project: &str, item: &ItemRecord) {
+    let date = |timestamp: &DateTime<Utc>| timestamp.format(DATE_FORMAT).to_string();
+
+    let mut prefix = Vec::new();
+    // a date alone after `x` would be read as the completion date
+    let created_in_prefix = !item.checked || item.completed_at.is_some();
+    if item.checked {
+        prefix.push("x".to_owned());
+        prefix.extend(item.completed_at.as_ref().map(date));
+    } else if let Some(priority) = item.priority {
+        prefix.push(format!("({priority})"));
+    }
+    if created_in_prefix {
+        prefix.extend(item.created_at.as_ref().map(date));
+    }
+
+    for part in prefix {
+        write!(todo, "{part} ").expect("writing to a string");
+    }
+    let text = item.item.replace(['\r', '\n'], " ");
+    if needs_escape(&text) {
+        todo.push('\\');
+    }
+    todo.push_str(&text);
+    write!(todo, " +{project}").expect("writing to a string");
+    if let (true, Some(priority)) = (item.checked, item.priority) {
+        write!(todo, " pri:{priority}").expect("writing to a string");
+    }
+    if let (false, Some(created_at)) = (created_in_prefix, &item.created_at) {
+        write!(todo, " created:{}", date(created_at)).expect("writing to a string");
+    }
+    if let Some(uuid) = item.uuid {
+        write!(todo, " uuid:{uuid}").expect("writing to a string");
+    }
+    todo.push('\n');
+}
+
+/// Whether item text would be read as anything other than text at the start of a task.
+fn needs_escape(text: &str) -> bool {
+    text.starts_with("x ")
+        || text.starts_with('\\')
+        || parse_priority(text).is_some()
+        || parse_date(text).is_some()
+}
+
+/// Read a document from todo.txt.
+///
+/// Checklists appear in the order in which their projects first appear. Errors identify the offending
+/// line.
+pub fn from_str(todo: &str) -> Result<Document> {
+    let mut checklists = Vec::<ChecklistRecord>::new();
+
+    for (idx, line) in todo.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let path = || format!("line {}", idx + 1);
+        let (project, item) =
+            parse_task(line).map_err(|reason| Error::invalid_document(path(), reason))?;
+
+        let project = project.map_or_else(|| DEFAULT_CHECKLIST.to_owned(), decode_project);
+        let index = match checklists
+            .iter()
+            .position(|checklist| checklist.name == project)
+        {
+            Some(index) => index,
+            None => {
+                checklists.push(ChecklistRecord {
+                    id: None,
+                    uuid: None,
+                    name: project,
+                    items: Vec::new(),
+                });
+                checklists.len() - 1
+            }
+        };
+        checklists[index].items.extend(item);
+    }
+
+    Ok(Document { checklists })
+}
+
+/// Parse a single task into its project, if any, and item, which is `None` for a line holding only a
+/// project.
+fn parse_task(line: &str) -> Result<(Option<&str>, Option<ItemRecord>), String> {
+    let mut rest = line;
+    let mut item = ItemRecord::default();
+
+    if let Some(after) = rest.strip_prefix("x ") {
+        item.checked = true;
+        rest = after;
+    } else if let Some((priority, after)) = parse_priority(rest) {
+        item.priority = Some(priority);
+        rest = after;
+    }
+
+    // completed tasks may have both a completion and a creation date, in that order
+    let max_dates = if item.checked { 2 } else { 1 };
+    let mut dates = Vec::new();
+    while dates.len() < max_dates {
+        let Some((date, after)) = parse_date(rest) else {
+            break;
+        };
+        dates.push(date);
+        rest = after;
+    }
+    match (item.checked, dates.as_slice()) {
+        (true, [completed, created]) => {
+            item.completed_at = Some(*completed);
+            item.created_at = Some(*created);
+        }
+        (true, [completed]) => item.completed_at = Some(*completed),
+        (false, [created]) => item.created_at = Some(*created),
+        _ => {}
+    }
+
+    let mut words = rest.split(' ').collect::<Vec<_>>();
+    let project = words
+        .iter()
+        .rposition(|word| word.len() > 1 && word.starts_with('+'))
+        .map(|idx| &words.remove(idx)[1..]);
+    if item.checked {
+        if let Some(idx) = words
+            .iter()
+            .position(|word| parse_pri_extension(word).is_some())
+        {
+            item.priority = parse_pri_extension(words.remove(idx));
+        }
+        if item.completed_at.is_none() {
+            if let Some(idx) = words
+                .iter()
+                .position(|word| parse_created_extension(word).is_some())
+            {
+                item.created_at = parse_created_extension(words.remove(idx));
+            }
+        }
+    }
+    if let Some(idx) = words
+        .iter()
+        .position(|word| parse_uuid_extension(word).is_some())
+    {
+        item.uuid = parse_uuid_extension(words.remove(idx));
+    }
+
+    let text = words.join(" ");
+    let text = text.trim();
+    if text.is_empty() {
+        return match (project, line.trim().strip_prefix('+')) {
+            (Some(project), Some(only)) if only == project => Ok((Some(project), None)),
+            _ => Err("task has no description".to_owned()),
+        };
+    }
+    item.item = text.strip_prefix('\\').unwrap_or(text).to_owned();
+
+    Ok((project, Some(item)))
+}
+
+fn parse_priority(text: &str) -> Option<(char, &str)> {
+    let bytes = text.as_bytes();
+    match bytes {
+        [b'(', priority, b')', b' ', ..] if priority.is_ascii_uppercase() => {
+            Some((char::from(*priority), &text[4..]))
+        }
+        _ => None,
+    }
+}
+
+fn parse_date(text: &str) -> Option<(DateTime<Utc>, &str)> {
+    let date = text.get(..DATE_FORMAT_LEN)?;
+    let after = text[DATE_FORMAT_LEN..].strip_prefix(' ')?;
+    let date = NaiveDate::parse_from_str(date, DATE_FORMAT).ok()?;
+    Some((date.and_hms_opt(0, 0, 0)?.and_utc(), after))
+}
+
+fn parse_pri_extension(word: &str) -> Option<char> {
+    let priority = word.strip_prefix("pri:")?;
+    let mut chars = priority.chars();
+    match (chars.next(), chars.next()) {
+        (Some(priority), None) if priority.is_ascii_uppercase() => Some(priority),
+        _ => None,
+    }
+}
+
+fn parse_created_extension(word: &str) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(word.strip_prefix("created:")?, DATE_FORMAT).ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+fn parse_uuid_extension(word: &str) -> Option<Uuid> {
+    Uuid::try_parse(word.strip_prefix("uuid:")?).ok()
+}
@@ -0,0 +1,270 @@
+//! Passphrase-based key derivation.
+//!
+//! Raw key bytes are awkward for humans to manage, so we also support deriving the database key from a
+//! passphrase with Argon2id. Each database gets its own random salt; that salt and the cost parameters
+//! are not secret, and live in a small header file next to the database (`<db>.kdf`).
+//!
+//! The header is written atomically, and only for a database which does not exist yet; an existing
+//! database whose header is missing fails with [`Error::MissingKdfHeader`] rather than getting a new
+//! salt from which its key cannot be derived. Headers whose costs exceed [`KdfCost::MAX`] are
+//! rejected, so that a tampered header cannot make opening the database exhaust memory.
+
+use std::path::{Path, PathBuf};
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use bytes::Bytes;
+use zeroize::Zeroizing;
+
+use crate::{
+    db::{remove_if_exists, sibling_path},
+    Cipher, EncryptionConfig, Error, Result,
+};
+
+const HEADER_MAGIC: &str = "checklist-kdf v1";
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// Argon2id cost parameters.
+///
+/// These only take effect when a database's key header is first created; afterwards the
+/// parameters recorded in the header are used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfCost {
+    /// Memory size in KiB
+    pub memory_kib: u32,
+    /// Number of passes over memory
+    pub iterations: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+impl KdfCost {
+    /// The greatest costs accepted: 256 MiB of memory, 16 passes and 16 lanes.
+    pub const MAX: Self = Self {
+        memory_kib: 256 * 1024,
+        iterations: 16,
+        parallelism: 16,
+    };
+
+    /// Whether each cost is at most that of [`Self::MAX`].
+    pub(crate) fn is_bounded(&self) -> bool {
+        self.memory_kib <= Self::MAX.memory_kib
+            && self.iterations <= Self::MAX.iterations
+            && self.parallelism <= Self::MAX.parallelism
+    }
+}
+
+impl Default for KdfCost {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// The non-secret inputs to key derivation for a particular database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KdfHeader {
+    pub cost: KdfCost,
+    pub salt: [u8; SALT_LEN],
+}
+
+impl KdfHeader {
+    /// Create a new header with a fresh random salt.
+    ///
+    /// Fails if `cost` exceeds [`KdfCost::MAX`].
+    pub fn generate(cost: KdfCost) -> Result<Self> {
+        if !cost.is_bounded() {
+            return Err(Error::InvalidKdfHeader(
+                "key derivation cost is too high".to_owned(),
+            ));
+        }
+        let mut salt = [0; SALT_LEN];
+        getrandom::getrandom(&mut salt).map_err(Error::Random)?;
+        Ok(Self { cost, salt })
+    }
+
+    /// Path of the header file which belongs to the database at `db_path`.
+    pub fn path_for(db_path: impl AsRef<Path>) -> PathBuf {
+        sibling_path(db_path.as_ref(), "kdf")
+    }
+
+    /// Load the header for the database at `db_path`, if it exists.
+    pub fn load(db_path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let contents = match std::fs::read_to_string(Self::path_for(db_path)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::io("reading kdf header")(err)),
+        };
+        contents.parse().map(Some)
+    }
+
+    /// Load the header for the database at `db_path`, creating and storing one if the database does not
+    /// yet exist.
+    ///
+    /// Fails with [`Error::MissingKdfHeader`] if the database exists but its header does not.
+    pub fn load_or_create(db_path: impl AsRef<Path>, cost: KdfCost) -> Result<Self> {
+        let db_path = db_path.as_ref();
+        if let Some(header) = Self::load(db_path)? {
+            return Ok(header);
+        }
+        // an empty file is a database which has not been written yet
+        let exists = match std::fs::metadata(db_path) {
+            Ok(metadata) => metadata.len() > 0,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => false,
+            Err(err) => return Err(Error::io("checking for database")(err)),
+        };
+        if exists {
+            return Err(Error::MissingKdfHeader(Self::path_for(db_path)));
+        }
+        let header = Self::generate(cost)?;
+        header.store(db_path)?;
+        Ok(header)
+    }
+
+    /// Atomically write this header next to the database at `db_path`, replacing any existing header.
+    pub fn store(&self, db_path: impl AsRef<Path>) -> Result<()> {
+        let path = Self::path_for(db_path);
+        let tmp_path = self.stage(&path)?;
+        std::fs::rename(&tmp_path, &path).map_err(Error::io("moving kdf header into place"))
+    }
+
+    /// Write this header to a temporary file next to the header at `path`, synced to disk, and return the
+    /// temporary file's path. Renaming it to `path` then replaces the header atomically.
+    pub(crate) fn stage(&self, path: &Path) -> Result<PathBuf> {
+        let tmp_path = sibling_path(path, "tmp");
+        remove_if_exists(&tmp_path)?;
+        let result = std::fs::write(&tmp_path, self.to_string())
+            .and_then(|()| std::fs::File::open(&tmp_path)?.sync_all())
+            .map_err(Error::io("writing kdf header"));
+        if result.is_err() {
+            // best-effort cleanup; the header is untouched
+            let _ = std::fs::remove_file(&tmp_path);
+        }
+        result.map(|()| tmp_path)
+    }
+
+    /// Remove the header next to the database at `db_path`, if there is one.
+    pub fn remove(db_path: impl AsRef<Path>) -> Result<()> {
+        match std::fs::remove_file(Self::path_for(db_path)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                Err(Error::io("removing kdf header")(err))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Derive a database key from a passphrase.
+    pub fn derive_key(&self, passphrase: &[u8]) -> Result<Zeroizing<Vec<u8>>> {
+        let KdfCost {
+            memory_kib,
+            iterations,
+            parallelism,
+        } = self.cost;
+        let params =
+            Params::new(memory_kib, iterations, parallelism, Some(KEY_LEN)).map_err(Error::Kdf)?;
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut key = Zeroizing::new(vec![0; KEY_LEN]);
+        argon2
+            .hash_password_into(passphrase, &self.salt, &mut key)
+            .map_err(Error::Kdf)?;
+        Ok(key)
+    }
+
+    /// Derive a database key from a passphrase and wrap it in an encryption config.
+    ///
+    /// The key material in the returned config is zeroized when the last reference to it is dropped.
+    pub fn encryption_config(&self, passphrase: &[u8]) -> Result<EncryptionConfig> {
+        let key = self.derive_key(passphrase)?;
+        Ok(EncryptionConfig {
+            cipher: Cipher::Aes256Cbc,
+            encryption_key: Bytes::from_owner(key),
+        })
+    }
+}
+
+/// Derive the encryption config for the database at `db_path` from a passphrase.
+///
+/// If the database does not exist yet, a key header is created with the given cost and a fresh salt.
+/// See [`KdfHeader::load_or_create`].
+pub fn passphrase_encryption_config(
+    db_path: impl AsRef<Path>,
+    passphrase: &[u8],
+    cost: KdfCost,
+) -> Result<EncryptionConfig> {
+    KdfHeader::load_or_create(db_path, cost)?.encryption_config(passphrase)
+}
+
+impl std::fmt::Display for KdfHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let KdfCost {
+            memory_kib,
+            iterations,
+            parallelism,
+        } = self.cost;
+        writeln!(f, "{HEADER_MAGIC}")?;
+        writeln!(f, "algorithm=argon2id")?;
+        writeln!(f, "memory_kib={memory_kib}")?;
+        writeln!(f, "iterations={iterations}")?;
+        writeln!(f, "parallelism={parallelism}")?;
+        write!(f, "salt=")?;
+        for byte in self.salt {
+            write!(f, "{byte:02x}")?;
+        }
+        writeln!(f)
+    }
+}
+
+impl std::str::FromStr for KdfHeader {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidKdfHeader(reason.to_owned());
+        let parse_u32 = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| invalid("cost parameter is not a number"))
+        };
+
+        let mut lines = s.lines();
+        if lines.next() != Some(HEADER_MAGIC) {
+            return Err(invalid("unrecognized header version"));
+        }
+
+        let mut cost = KdfCost::default();
+        let mut salt = None;
+        for line in lines.filter(|line| !line.is_empty()) {
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("line is not a key=value pair"))?;
+            match key {
+                "algorithm" if value == "argon2id" => {}
+                "algorithm" => return Err(invalid("unsupported algorithm")),
+                "memory_kib" => cost.memory_kib = parse_u32(value)?,
+                "iterations" => cost.iterations = parse_u32(value)?,
+                "parallelism" => cost.parallelism = parse_u32(value)?,
+                "salt" => {
+                    if value.len() != SALT_LEN * 2 || !value.is_ascii() {
+                        return Err(invalid("salt has the wrong length"));
+                    }
+                    let mut bytes = [0; SALT_LEN];
+                    for (idx, byte) in bytes.iter_mut().enumerate() {
+                        *byte = u8::from_str_radix(&value[idx * 2..idx * 2 + 2], 16)
+                            .map_err(|_| invalid("salt is not hex"))?;
+                    }
+                    salt = Some(bytes);
+                }
+                _ => return Err(invalid("unknown key")),
+            }
+        }
+
+        let salt = salt.ok_or_else(|| invalid("missing salt"))?;
+        if !cost.is_bounded() {
+            return Err(invalid("key derivation cost is too high"));
+        }
+        Ok(Self { cost, salt })
+    }
+}
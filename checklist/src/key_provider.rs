@@ -0,0 +1,195 @@
+//! Sources of encryption key material.
+//!
+//! A [`KeyProvider`] produces secret bytes. Those bytes can be used directly as the database key
+//! ([`raw_encryption_config`]) or treated as a passphrase from which the key is derived
+//! ([`derived_encryption_config`]).
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use bytes::Bytes;
+use zeroize::Zeroizing;
+
+use crate::{kdf::KdfHeader, Cipher, EncryptionConfig, Error, KdfCost, Result};
+
+/// Something which can produce secret key material on demand.
+pub trait KeyProvider: Send + Sync {
+    /// Produce the secret key material.
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>>;
+}
+
+impl<T: KeyProvider + ?Sized> KeyProvider for Box<T> {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        (**self).key()
+    }
+}
+
+impl<T: KeyProvider + ?Sized> KeyProvider for std::sync::Arc<T> {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        (**self).key()
+    }
+}
+
+/// Build an encryption config which uses the provider's output directly as the database key.
+pub fn raw_encryption_config(provider: &dyn KeyProvider) -> Result<EncryptionConfig> {
+    let key = provider.key()?;
+    Ok(EncryptionConfig {
+        cipher: Cipher::Aes256Cbc,
+        encryption_key: Bytes::from_owner(key),
+    })
+}
+
+/// Build an encryption config which treats the provider's output as a passphrase.
+///
+/// See [`passphrase_encryption_config`][crate::passphrase_encryption_config].
+pub fn derived_encryption_config(
+    provider: &dyn KeyProvider,
+    db_path: impl AsRef<Path>,
+    cost: KdfCost,
+) -> Result<EncryptionConfig> {
+    let passphrase = provider.key()?;
+    KdfHeader::load_or_create(db_path, cost)?.encryption_config(&passphrase)
+}
+
+/// Strip a single trailing newline, as commonly produced by shells and editors.
+fn trim_trailing_newline(mut bytes: Zeroizing<Vec<u8>>) -> Zeroizing<Vec<u8>> {
+    if bytes.last() == Some(&b'\n') {
+        bytes.pop();
+        if bytes.last() == Some(&b'\r') {
+            bytes.pop();
+        }
+    }
+    bytes
+}
+
+/// Read the key from a file.
+///
+/// The file's contents are used verbatim; it can contain arbitrary bytes.
+#[derive(Debug, Clone)]
+pub struct FileKeyProvider {
+    pub path: PathBuf,
+}
+
+impl FileKeyProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        std::fs::read(&self.path)
+            .map(Zeroizing::new)
+            .map_err(Error::io("reading encryption key from file"))
+    }
+}
+
+/// Read the key from an environment variable.
+#[derive(Debug, Clone)]
+pub struct EnvKeyProvider {
+    pub var: OsString,
+}
+
+impl EnvKeyProvider {
+    pub fn new(var: impl Into<OsString>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl KeyProvider for EnvKeyProvider {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        let value = std::env::var_os(&self.var).ok_or_else(|| {
+            Error::key_provider(format!(
+                "environment variable {} is not set",
+                self.var.to_string_lossy()
+            ))
+        })?;
+        Ok(Zeroizing::new(value.into_encoded_bytes()))
+    }
+}
+
+/// Prompt for the key on the controlling terminal, without echoing it.
+#[derive(Debug, Clone)]
+pub struct PromptKeyProvider {
+    pub prompt: String,
+}
+
+impl PromptKeyProvider {
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+        }
+    }
+}
+
+impl Default for PromptKeyProvider {
+    fn default() -> Self {
+        Self::new("Encryption key: ")
+    }
+}
+
+impl KeyProvider for PromptKeyProvider {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        rpassword::prompt_password(&self.prompt)
+            .map(|key| Zeroizing::new(key.into_bytes()))
+            .map_err(Error::io("prompting for encryption key"))
+    }
+}
+
+/// Run an external command and use its standard output as the key.
+///
+/// This integrates with password managers, e.g. `pass show checklist`. A single trailing newline
+/// is stripped from the output.
+#[derive(Debug, Clone)]
+pub struct CommandKeyProvider {
+    pub program: OsString,
+    pub args: Vec<OsString>,
+}
+
+impl CommandKeyProvider {
+    pub fn new(
+        program: impl Into<OsString>,
+        args: impl IntoIterator<Item = impl Into<OsString>>,
+    ) -> Self {
+        Self {
+            program: program.into(),
+            args: args.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl KeyProvider for CommandKeyProvider {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        let output = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .output()
+            .map_err(Error::io("running encryption key command"))?;
+        let stdout = Zeroizing::new(output.stdout);
+
+        if !output.status.success() {
+            return Err(Error::key_provider(format!(
+                "encryption key command exited with {}",
+                output.status
+            )));
+        }
+
+        Ok(trim_trailing_newline(stdout))
+    }
+}
+
+/// A fixed key, for tests.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Clone)]
+pub struct StaticKeyProvider(pub Vec<u8>);
+
+#[cfg(any(test, feature = "test-util"))]
+impl KeyProvider for StaticKeyProvider {
+    fn key(&self) -> Result<Zeroizing<Vec<u8>>> {
+        Ok(Zeroizing::new(self.0.clone()))
+    }
+}
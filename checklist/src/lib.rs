@@ -1,7 +1,22 @@
-use std::path::Path;
-
-use libsql::{params, Connection, Database};
+mod backup;
+mod db;
+pub mod formats;
+pub mod kdf;
+pub mod key_provider;
+mod lookup;
+mod options;
+pub mod sync;
+mod timestamp;
+mod uuids;
+
+use libsql::params;
 pub use libsql::{Cipher, EncryptionConfig};
+pub use uuid::Uuid;
+
+pub use db::{Db, SCHEMA_VERSION};
+pub use kdf::{passphrase_encryption_config, KdfCost};
+pub use key_provider::KeyProvider;
+pub use options::{DbOptions, EncryptionMode};
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
@@ -11,62 +26,97 @@ pub enum Error {
         #[source]
         inner: libsql::Error,
     },
+    #[error("{context}: {inner}")]
+    Io {
+        context: &'static str,
+        #[source]
+        inner: std::io::Error,
+    },
+    #[error("deriving key from passphrase")]
+    Kdf(#[source] argon2::Error),
+    #[error("generating random bytes")]
+    Random(#[source] getrandom::Error),
+    #[error("obtaining encryption key: {0}")]
+    KeyProvider(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("invalid kdf header: {0}")]
+    InvalidKdfHeader(String),
+    #[error(
+        "the key header {} is missing, so the key cannot be derived; restore it from a backup",
+        .0.display()
+    )]
+    MissingKdfHeader(std::path::PathBuf),
+    #[error("database uses encryption mode {found}, but was opened with {expected}")]
+    EncryptionMismatch {
+        expected: EncryptionMode,
+        found: EncryptionMode,
+    },
+    #[error("database is encrypted, but was opened without encryption")]
+    UnexpectedlyEncrypted,
+    #[error("database records an unknown encryption mode: {0}")]
+    UnknownEncryptionMode(String),
+    #[error("refusing to overwrite existing file: {}", .0.display())]
+    DestinationExists(std::path::PathBuf),
+    #[error(
+        "unsupported schema version {}; expected {expected}",
+        .found.as_deref().unwrap_or("<none>")
+    )]
+    SchemaVersionMismatch {
+        expected: u32,
+        found: Option<String>,
+    },
     #[error("this item is not present in the db; it may have been deleted")]
     MissingItem,
+    #[error("this checklist is not present in the db; it may have been deleted")]
+    MissingChecklist,
+    #[error("invalid timestamp in database: {0}")]
+    InvalidTimestamp(String),
+    #[error("invalid uuid: {0}")]
+    InvalidUuid(String),
+    #[error("invalid document at {path}: {reason}")]
+    InvalidDocument { path: String, reason: String },
+    #[error("invalid changeset: {0}")]
+    InvalidChangeset(String),
+    #[error("this record has changed since it was loaded; it is now at version {current}")]
+    Conflict { current: u64 },
+    #[error("{query:?} could mean any of: {}", .candidates.join(", "))]
+    Ambiguous {
+        query: String,
+        /// Descriptions of the records which match equally well
+        candidates: Vec<String>,
+    },
 }
 
 impl Error {
     pub(crate) fn libsql(context: &'static str) -> impl FnOnce(libsql::Error) -> Self {
         move |inner| Self::Libsql { context, inner }
     }
-}
-
-pub type Result<T, E = Error> = std::result::Result<T, E>;
-
-pub struct Db {
-    inner: Database,
-}
-
-impl Db {
-    pub async fn new(path: impl AsRef<Path>, encryption_config: EncryptionConfig) -> Result<Self> {
-        let inner = libsql::Builder::new_local(path)
-            .encryption_config(encryption_config)
-            .build()
-            .await
-            .map_err(Error::libsql("building local db connection"))?;
-
-        let db = Self { inner };
-        db.ensure_schema().await?;
 
-        Ok(db)
+    /// Construct an error from within a [`KeyProvider`] implementation.
+    pub fn key_provider(inner: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::KeyProvider(inner.into())
     }
 
-    pub(crate) fn conn(&self) -> Result<Connection> {
-        self.inner
-            .connect()
-            .map_err(Error::libsql("establishing connection to db"))
+    pub(crate) fn io(context: &'static str) -> impl FnOnce(std::io::Error) -> Self {
+        move |inner| Self::Io { context, inner }
     }
 
-    async fn ensure_schema(&self) -> Result<()> {
-        const SCHEMA: &str = include_str!("schema.sql");
-        let conn = self.conn()?;
-
-        for command in SCHEMA.split("\n\n") {
-            conn.execute(command, ())
-                .await
-                .map_err(Error::libsql("executing schema"))?;
+    pub(crate) fn invalid_document(path: impl Into<String>, reason: impl ToString) -> Self {
+        Self::InvalidDocument {
+            path: path.into(),
+            reason: reason.to_string(),
         }
-
-        Ok(())
     }
 }
 
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
 #[derive(
     Debug,
     Clone,
     Copy,
     PartialEq,
     Eq,
+    Hash,
     PartialOrd,
     Ord,
     derive_more::Constructor,
@@ -76,21 +126,28 @@ impl Db {
     derive_more::Display,
     derive_more::FromStr,
 )]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct ChecklistId(i64);
 
 pub struct Checklist {
     pub id: ChecklistId,
+    /// Globally unique id, which unlike `id` is preserved by exports, imports and sync
+    pub uuid: Uuid,
     pub name: String,
+    /// Incremented by every change to the checklist, for detecting concurrent changes
+    pub version: u64,
 }
 
 impl Checklist {
     pub async fn new(db: &Db, name: &str) -> Result<Self> {
         let conn = db.conn()?;
 
+        let uuid = uuids::new();
         let mut rows = conn
             .query(
-                "INSERT INTO checklists(name) VALUES (?1) RETURNING id",
-                [name],
+                "INSERT INTO checklists(uuid, name) VALUES (?1, ?2) RETURNING id",
+                [uuids::to_sql(&uuid), name.to_owned()],
             )
             .await
             .map_err(Error::libsql("creating checklist"))?;
@@ -106,7 +163,9 @@ impl Checklist {
 
         Ok(Self {
             id,
+            uuid,
             name: name.to_owned(),
+            version: 1,
         })
     }
 
@@ -114,7 +173,10 @@ impl Checklist {
         let conn = db.conn()?;
 
         let mut rows = conn
-            .query("SELECT name FROM checklists WHERE id = ?1", [*id])
+            .query(
+                "SELECT id, uuid, name, version FROM checklists WHERE id = ?1",
+                [*id],
+            )
             .await
             .map_err(Error::libsql("getting checklist by id"))?;
         let row = rows
@@ -122,17 +184,26 @@ impl Checklist {
             .await
             .map_err(Error::libsql("getting result row for loading checklist"))?;
 
-        row.map(|row| {
-            let name = row.get_str(0).map_err(Error::libsql(
-                "getting name from result row while getting checklist by id",
-            ))?;
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// Load a checklist by its globally unique id.
+    pub async fn load_by_uuid(db: &Db, uuid: Uuid) -> Result<Option<Self>> {
+        let conn = db.conn()?;
 
-            Ok(Self {
-                id,
-                name: name.to_owned(),
-            })
-        })
-        .transpose()
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, name, version FROM checklists WHERE uuid = ?1",
+                [uuids::to_sql(&uuid)],
+            )
+            .await
+            .map_err(Error::libsql("getting checklist by uuid"))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting result row for loading checklist"))?;
+
+        row.as_ref().map(Self::from_row).transpose()
     }
 
     pub async fn all(db: &Db) -> Result<Vec<Self>> {
@@ -140,7 +211,7 @@ impl Checklist {
         let mut checklists = Vec::new();
 
         let mut rows = conn
-            .query("SELECT id, name FROM checklists", ())
+            .query("SELECT id, uuid, name, version FROM checklists", ())
             .await
             .map_err(Error::libsql("listing all checklists"))?;
 
@@ -149,22 +220,77 @@ impl Checklist {
             .await
             .map_err(Error::libsql("getting next row while listing checklists"))?
         {
-            let id = row.get::<i64>(0).map_err(Error::libsql(
-                "getting id from result row while listing all checklists",
-            ))?;
-            let id = ChecklistId::new(id);
-            let name = row
-                .get_str(1)
-                .map_err(Error::libsql(
-                    "getting name from result row while listing all checklists",
-                ))?
-                .to_owned();
-            checklists.push(Self { id, name });
+            checklists.push(Self::from_row(&row)?);
         }
 
         Ok(checklists)
     }
 
+    /// Find the checklist whose name best matches `name`.
+    ///
+    /// Names are matched exactly, then ignoring case, then by prefix, then by substring, and then
+    /// by containing the characters of `name` in order. Fails with [`Error::MissingChecklist`] if
+    /// none match, and with [`Error::Ambiguous`] if several match equally well.
+    pub async fn find(db: &Db, name: &str) -> Result<Self> {
+        lookup::best(
+            Self::all(db).await?,
+            name,
+            |checklist| &checklist.name,
+            |checklist| format!("{} ({})", checklist.name, checklist.id),
+            Error::MissingChecklist,
+        )
+    }
+
+    /// Load the checklist changed most recently, here or by sync, counting changes to its items.
+    pub async fn last_changed(db: &Db) -> Result<Option<Self>> {
+        let conn = db.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, name, version FROM checklists
+                ORDER BY coalesce((
+                    SELECT max(clock) FROM sync_clocks
+                    WHERE (tbl = 'checklists' AND sync_clocks.uuid = checklists.uuid)
+                        OR (tbl = 'items' AND sync_clocks.uuid IN (
+                            SELECT uuid FROM items WHERE checklist = checklists.id
+                        ))
+                ), 0) DESC, id DESC
+                LIMIT 1",
+                (),
+            )
+            .await
+            .map_err(Error::libsql("getting last changed checklist"))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting result row for loading checklist"))?;
+
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// Read a row of `id, uuid, name, version`.
+    fn from_row(row: &libsql::Row) -> Result<Self> {
+        let id = row.get::<i64>(0).map_err(Error::libsql(
+            "getting id from result row while loading checklist",
+        ))?;
+        let uuid = row.get_str(1).map_err(Error::libsql(
+            "getting uuid from result row while loading checklist",
+        ))?;
+        let name = row.get_str(2).map_err(Error::libsql(
+            "getting name from result row while loading checklist",
+        ))?;
+        let version = row.get::<u64>(3).map_err(Error::libsql(
+            "getting version from result row while loading checklist",
+        ))?;
+
+        Ok(Self {
+            id: ChecklistId::new(id),
+            uuid: uuids::from_sql(uuid)?,
+            name: name.to_owned(),
+            version,
+        })
+    }
+
     pub async fn delete(db: &Db, id: ChecklistId) -> Result<()> {
         let conn = db.conn()?;
 
@@ -175,13 +301,128 @@ impl Checklist {
         Ok(())
     }
 
+    /// Delete a checklist, provided that it is still at the `expected` version.
+    ///
+    /// Fails with [`Error::Conflict`] if the checklist has changed since.
+    pub async fn delete_if_version(db: &Db, id: ChecklistId, expected: u64) -> Result<()> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .query(
+                "DELETE FROM checklists WHERE id = ?1 AND version = ?2 RETURNING version",
+                params!(*id, expected),
+            )
+            .await
+            .map_err(Error::libsql("deleting checklist"))?;
+        versioned(&conn, rows, "checklists", *id, Error::MissingChecklist).await?;
+
+        Ok(())
+    }
+
+    /// Rename this checklist.
+    pub async fn rename(&self, db: &Db, name: &str) -> Result<()> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .execute(
+                "UPDATE checklists SET name = ?1 WHERE id = ?2",
+                params!(name, *self.id),
+            )
+            .await
+            .map_err(Error::libsql("renaming checklist"))?;
+
+        if rows == 0 {
+            Err(Error::MissingChecklist)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Rename this checklist, provided that it is still at the `expected` version.
+    ///
+    /// Returns the new version. Fails with [`Error::Conflict`] if the checklist has changed since.
+    pub async fn rename_if_version(&self, db: &Db, name: &str, expected: u64) -> Result<u64> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .query(
+                "UPDATE checklists SET name = ?1, version = version + 1 WHERE id = ?2 AND version = ?3
+                RETURNING version",
+                params!(name, *self.id, expected),
+            )
+            .await
+            .map_err(Error::libsql("renaming checklist"))?;
+        versioned(&conn, rows, "checklists", *self.id, Error::MissingChecklist).await
+    }
+
+    /// Write this checklist and its items as a Markdown task list.
+    ///
+    /// See [`formats::markdown`] for the format.
+    pub async fn to_markdown(&self, db: &Db) -> Result<String> {
+        let record = formats::ChecklistRecord::load(db, self.id)
+            .await?
+            .ok_or(Error::MissingChecklist)?;
+        Ok(formats::markdown::to_string(&record))
+    }
+
+    /// Make this checklist match an edited copy of its Markdown task list, in a single transaction.
+    ///
+    /// `original` is the task list as written by [`Checklist::to_markdown`], and `edited` is that
+    /// list after changes. Items without a uuid comment are added, items which are no longer listed
+    /// are deleted, and the rest take the text, check mark and order of the edited list. The heading
+    /// renames the checklist.
+    ///
+    /// Fails with [`Error::Conflict`] if the checklist or its items have changed since `original`
+    /// was written.
+    pub async fn apply_markdown(
+        &self,
+        db: &Db,
+        original: &str,
+        edited: &str,
+    ) -> Result<formats::EditSummary> {
+        let record = formats::markdown::from_str(edited)?;
+
+        let conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .await
+            .map_err(Error::libsql("beginning markdown edit transaction"))?;
+        let summary = record.apply_edit(&tx, self.id, original).await?;
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing markdown edit transaction"))?;
+        Ok(summary)
+    }
+
+    /// Create a new checklist and its items from a Markdown task list, in a single transaction.
+    ///
+    /// See [`formats::markdown`] for the format.
+    pub async fn from_markdown(db: &Db, markdown: &str) -> Result<Self> {
+        let record = formats::markdown::from_str(markdown)?;
+
+        let conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .await
+            .map_err(Error::libsql("beginning markdown import transaction"))?;
+        let (id, _) = record
+            .insert(&tx, formats::ImportMode::Merge, formats::KeptIds::default())
+            .await?;
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing markdown import transaction"))?;
+
+        Self::load(db, id).await?.ok_or(Error::MissingChecklist)
+    }
+
     pub async fn items(&self, db: &Db) -> Result<Vec<Item>> {
         let conn = db.conn()?;
         let mut items = Vec::new();
 
         let mut rows = conn
             .query(
-                "SELECT id, item FROM items WHERE checklist = ?1",
+                "SELECT id, uuid, checklist, item, version FROM items WHERE checklist = ?1
+                ORDER BY position, id",
                 [*self.id],
             )
             .await
@@ -190,21 +431,7 @@ impl Checklist {
         while let Some(row) = rows.next().await.map_err(Error::libsql(
             "getting next row while listing items for a checklist",
         ))? {
-            let id = row.get::<i64>(0).map_err(Error::libsql(
-                "getting id from result row while listing checklist items",
-            ))?;
-            let id = ItemId::new(id);
-            let item = row
-                .get_str(1)
-                .map_err(Error::libsql(
-                    "getting item from result row while listing checklist items",
-                ))?
-                .to_owned();
-            items.push(Item {
-                id,
-                checklist: self.id,
-                item,
-            });
+            items.push(Item::from_row(&row)?);
         }
 
         Ok(items)
@@ -217,6 +444,7 @@ impl Checklist {
     Copy,
     PartialEq,
     Eq,
+    Hash,
     PartialOrd,
     Ord,
     derive_more::Constructor,
@@ -226,22 +454,39 @@ impl Checklist {
     derive_more::Display,
     derive_more::FromStr,
 )]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct ItemId(i64);
 
 pub struct Item {
     pub id: ItemId,
+    /// Globally unique id, which unlike `id` is preserved by exports, imports and sync
+    pub uuid: Uuid,
     pub checklist: ChecklistId,
     pub item: String,
+    /// Incremented by every change to the item, for detecting concurrent changes
+    pub version: u64,
 }
 
 impl Item {
     pub async fn new(db: &Db, checklist: ChecklistId, item: String) -> Result<Self> {
         let conn = db.conn()?;
 
+        let uuid = uuids::new();
         let mut rows = conn
             .query(
-                "INSERT INTO items(checklist, item) VALUES (?1, ?2) RETURNING id",
-                params!(*checklist, item.clone()),
+                "INSERT INTO items(uuid, checklist, item, created_at, position)
+                VALUES (
+                    ?1, ?2, ?3, ?4,
+                    (SELECT coalesce(max(position), 0) + 1 FROM items WHERE checklist = ?2)
+                )
+                RETURNING id",
+                params!(
+                    uuids::to_sql(&uuid),
+                    *checklist,
+                    item.clone(),
+                    timestamp::now()
+                ),
             )
             .await
             .map_err(Error::libsql("creating item"))?;
@@ -257,8 +502,10 @@ impl Item {
 
         Ok(Self {
             id,
+            uuid,
             checklist,
             item,
+            version: 1,
         })
     }
 
@@ -266,35 +513,150 @@ impl Item {
         let conn = db.conn()?;
 
         let mut rows = conn
-            .query("SELECT checklist, item FROM items WHERE id = ?1", [*id])
+            .query(
+                "SELECT id, uuid, checklist, item, version FROM items WHERE id = ?1",
+                [*id],
+            )
             .await
             .map_err(Error::libsql("getting item by id"))?;
         let row = rows
             .next()
             .await
-            .map_err(Error::libsql("getting result row for loading checklist"))?;
+            .map_err(Error::libsql("getting result row for loading item"))?;
+
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// Load an item by its globally unique id.
+    pub async fn load_by_uuid(db: &Db, uuid: Uuid) -> Result<Option<Self>> {
+        let conn = db.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, checklist, item, version FROM items WHERE uuid = ?1",
+                [uuids::to_sql(&uuid)],
+            )
+            .await
+            .map_err(Error::libsql("getting item by uuid"))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting result row for loading item"))?;
+
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// Find the items in any checklist whose text contains `query`, ignoring ASCII case.
+    pub async fn search(db: &Db, query: &str) -> Result<Vec<Self>> {
+        let conn = db.conn()?;
+        let mut items = Vec::new();
 
-        row.map(|row| {
-            let checklist = row
-                .get::<i64>(0)
-                .map_err(Error::libsql(
-                    "getting checklist from result row while getting item by id",
-                ))?
-                .into();
-            let item = row
-                .get_str(1)
-                .map_err(Error::libsql(
-                    "getting name from result row while getting item by id",
-                ))?
-                .to_owned();
-
-            Ok(Self {
-                id,
-                checklist,
-                item,
-            })
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, checklist, item, version FROM items
+                WHERE instr(lower(item), lower(?1)) > 0
+                ORDER BY checklist, position, id",
+                [query],
+            )
+            .await
+            .map_err(Error::libsql("searching items"))?;
+
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next row while searching items"))?
+        {
+            items.push(Self::from_row(&row)?);
+        }
+
+        Ok(items)
+    }
+
+    /// Find the item whose text best matches `name`, in `checklist` or else in any checklist.
+    ///
+    /// Matches as [`Checklist::find`] does, failing with [`Error::MissingItem`] if none match.
+    pub async fn find(db: &Db, checklist: Option<ChecklistId>, name: &str) -> Result<Self> {
+        let conn = db.conn()?;
+        let mut items = Vec::new();
+
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, checklist, item, version FROM items
+                WHERE ?1 IS NULL OR checklist = ?1
+                ORDER BY checklist, position, id",
+                [checklist.map(|id| *id)],
+            )
+            .await
+            .map_err(Error::libsql("listing items to find one"))?;
+
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next row while finding an item"))?
+        {
+            items.push(Self::from_row(&row)?);
+        }
+
+        lookup::best(
+            items,
+            name,
+            |item| &item.item,
+            |item| format!("{} ({})", item.item, item.id),
+            Error::MissingItem,
+        )
+    }
+
+    /// Load the item changed most recently, here or by sync, in `checklist` or else in any
+    /// checklist.
+    pub async fn last_changed(db: &Db, checklist: Option<ChecklistId>) -> Result<Option<Self>> {
+        let conn = db.conn()?;
+
+        let mut rows = conn
+            .query(
+                "SELECT id, uuid, checklist, item, version FROM items
+                WHERE ?1 IS NULL OR checklist = ?1
+                ORDER BY coalesce((
+                    SELECT max(clock) FROM sync_clocks
+                    WHERE tbl = 'items' AND sync_clocks.uuid = items.uuid
+                ), 0) DESC, id DESC
+                LIMIT 1",
+                [checklist.map(|id| *id)],
+            )
+            .await
+            .map_err(Error::libsql("getting last changed item"))?;
+        let row = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting result row for loading item"))?;
+
+        row.as_ref().map(Self::from_row).transpose()
+    }
+
+    /// Read a row of `id, uuid, checklist, item, version`.
+    fn from_row(row: &libsql::Row) -> Result<Self> {
+        let id = row.get::<i64>(0).map_err(Error::libsql(
+            "getting id from result row while loading item",
+        ))?;
+        let uuid = row.get_str(1).map_err(Error::libsql(
+            "getting uuid from result row while loading item",
+        ))?;
+        let checklist = row.get::<i64>(2).map_err(Error::libsql(
+            "getting checklist from result row while loading item",
+        ))?;
+        let item = row.get_str(3).map_err(Error::libsql(
+            "getting item from result row while loading item",
+        ))?;
+        let version = row.get::<u64>(4).map_err(Error::libsql(
+            "getting version from result row while loading item",
+        ))?;
+
+        Ok(Self {
+            id: ItemId::new(id),
+            uuid: uuids::from_sql(uuid)?,
+            checklist: ChecklistId::new(checklist),
+            item: item.to_owned(),
+            version,
         })
-        .transpose()
     }
 
     pub async fn delete(db: &Db, id: ItemId) -> Result<()> {
@@ -307,6 +669,146 @@ impl Item {
         Ok(())
     }
 
+    /// Delete an item, provided that it is still at the `expected` version.
+    ///
+    /// Fails with [`Error::Conflict`] if the item has changed since.
+    pub async fn delete_if_version(db: &Db, id: ItemId, expected: u64) -> Result<()> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .query(
+                "DELETE FROM items WHERE id = ?1 AND version = ?2 RETURNING version",
+                params!(*id, expected),
+            )
+            .await
+            .map_err(Error::libsql("deleting item"))?;
+        versioned(&conn, rows, "items", *id, Error::MissingItem).await?;
+
+        Ok(())
+    }
+
+    /// Change the text of this item.
+    pub async fn rename(&self, db: &Db, item: &str) -> Result<()> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .execute(
+                "UPDATE items SET item = ?1 WHERE id = ?2",
+                params!(item, *self.id),
+            )
+            .await
+            .map_err(Error::libsql("renaming item"))?;
+
+        if rows == 0 {
+            Err(Error::MissingItem)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Change the text of this item, provided that it is still at the `expected` version.
+    ///
+    /// Returns the new version. Fails with [`Error::Conflict`] if the item has changed since.
+    pub async fn rename_if_version(&self, db: &Db, item: &str, expected: u64) -> Result<u64> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .query(
+                "UPDATE items SET item = ?1, version = version + 1 WHERE id = ?2 AND version = ?3
+                RETURNING version",
+                params!(item, *self.id, expected),
+            )
+            .await
+            .map_err(Error::libsql("renaming item"))?;
+        versioned(&conn, rows, "items", *self.id, Error::MissingItem).await
+    }
+
+    /// Move this item to `index` among the items of its checklist, or to the end if `index` is past
+    /// the last item.
+    pub async fn move_to(&self, db: &Db, index: usize) -> Result<()> {
+        let conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .await
+            .map_err(Error::libsql("beginning item move transaction"))?;
+        self.reorder(&tx, index).await?;
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing item move transaction"))
+    }
+
+    /// Move this item to `index` among the items of its checklist, provided that it is still at the
+    /// `expected` version.
+    ///
+    /// Returns the new version, which is unchanged if the item was already at `index`. Fails with
+    /// [`Error::Conflict`] if the item has changed since.
+    pub async fn move_to_if_version(&self, db: &Db, index: usize, expected: u64) -> Result<u64> {
+        let conn = db.conn()?;
+        let tx = conn
+            .transaction()
+            .await
+            .map_err(Error::libsql("beginning item move transaction"))?;
+
+        let rows = tx
+            .query(
+                "SELECT version FROM items WHERE id = ?1 AND version = ?2",
+                params!(*self.id, expected),
+            )
+            .await
+            .map_err(Error::libsql("checking version of item to move"))?;
+        versioned(&tx, rows, "items", *self.id, Error::MissingItem).await?;
+        self.reorder(&tx, index).await?;
+        let rows = tx
+            .query("SELECT version FROM items WHERE id = ?1", [*self.id])
+            .await
+            .map_err(Error::libsql("getting version of moved item"))?;
+        let version = versioned(&tx, rows, "items", *self.id, Error::MissingItem).await?;
+
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing item move transaction"))?;
+        Ok(version)
+    }
+
+    /// Renumber the items of this item's checklist so that this item is at `index`.
+    async fn reorder(&self, tx: &libsql::Transaction, index: usize) -> Result<()> {
+        let mut ids = Vec::new();
+        let mut rows = tx
+            .query(
+                "SELECT id FROM items WHERE checklist = ?1 ORDER BY position, id",
+                [*self.checklist],
+            )
+            .await
+            .map_err(Error::libsql("selecting items to reorder"))?;
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next row while reordering items"))?
+        {
+            ids.push(
+                row.get::<i64>(0)
+                    .map_err(Error::libsql("getting id of item to reorder"))?,
+            );
+        }
+
+        let current = ids
+            .iter()
+            .position(|id| *id == *self.id)
+            .ok_or(Error::MissingItem)?;
+        ids.remove(current);
+        ids.insert(index.min(ids.len()), *self.id);
+        // only items whose position changes are written, and so stamped for sync
+        for (position, id) in ids.into_iter().enumerate() {
+            tx.execute(
+                "UPDATE items SET position = ?1 WHERE id = ?2 AND position IS NOT ?1",
+                params!(position as i64 + 1, id),
+            )
+            .await
+            .map_err(Error::libsql("updating item position"))?;
+        }
+        Ok(())
+    }
+
     pub async fn is_set(&self, db: &Db) -> Result<bool> {
         let conn = db.conn()?;
 
@@ -324,13 +826,14 @@ impl Item {
             .map_err(Error::libsql("getting checked status from result row"))
     }
 
+    /// Set the check status of this item, recording when it was completed.
     pub async fn set_checked(&self, db: &Db, checked: bool) -> Result<()> {
         let conn = db.conn()?;
 
         let rows = conn
             .execute(
-                "UPDATE items SET checked = ?1 WHERE id = ?2",
-                params!(checked, *self.id),
+                "UPDATE items SET checked = ?1, completed_at = ?2 WHERE id = ?3",
+                params!(checked, checked.then(timestamp::now), *self.id),
             )
             .await
             .map_err(Error::libsql("updating checked status for item"))?;
@@ -341,4 +844,67 @@ impl Item {
             Ok(())
         }
     }
+
+    /// Set the check status of this item, provided that it is still at the `expected` version.
+    ///
+    /// Returns the new version. Fails with [`Error::Conflict`] if the item has changed since.
+    pub async fn set_checked_if_version(
+        &self,
+        db: &Db,
+        checked: bool,
+        expected: u64,
+    ) -> Result<u64> {
+        let conn = db.conn()?;
+
+        let rows = conn
+            .query(
+                "UPDATE items SET checked = ?1, completed_at = ?2, version = version + 1
+                WHERE id = ?3 AND version = ?4
+                RETURNING version",
+                params!(checked, checked.then(timestamp::now), *self.id, expected),
+            )
+            .await
+            .map_err(Error::libsql("updating checked status for item"))?;
+        versioned(&conn, rows, "items", *self.id, Error::MissingItem).await
+    }
+}
+
+/// Finish a mutation which is conditional on the version of the row `id` in `table`.
+///
+/// `rows` are the results of the mutation, which returns the version of the row afterwards. If it
+/// matched nothing, this fails with [`Error::Conflict`] if the row is at another version, or with
+/// `missing` if the row does not exist.
+async fn versioned(
+    conn: &libsql::Connection,
+    mut rows: libsql::Rows,
+    table: &str,
+    id: i64,
+    missing: Error,
+) -> Result<u64> {
+    if let Some(row) = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for versioned change"))?
+    {
+        return row
+            .get::<u64>(0)
+            .map_err(Error::libsql("getting version from result row"));
+    }
+
+    let mut rows = conn
+        .query(&format!("SELECT version FROM {table} WHERE id = ?1"), [id])
+        .await
+        .map_err(Error::libsql("getting current version"))?;
+    match rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for current version"))?
+    {
+        Some(row) => Err(Error::Conflict {
+            current: row
+                .get::<u64>(0)
+                .map_err(Error::libsql("getting current version from result row"))?,
+        }),
+        None => Err(missing),
+    }
 }
@@ -0,0 +1,71 @@
+//! Finding checklists and items by name, for people who remember names better than ids.
+
+use crate::{Error, Result};
+
+/// How closely a name matches what was asked for, closest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Closeness {
+    Exact,
+    IgnoringCase,
+    Prefix,
+    Contains,
+    /// The characters asked for appear in the name in order, but perhaps not together
+    Fuzzy,
+}
+
+fn closeness(name: &str, query: &str) -> Option<Closeness> {
+    if name == query {
+        return Some(Closeness::Exact);
+    }
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    if name == query {
+        Some(Closeness::IgnoringCase)
+    } else if name.starts_with(&query) {
+        Some(Closeness::Prefix)
+    } else if name.contains(&query) {
+        Some(Closeness::Contains)
+    } else {
+        let mut chars = name.chars();
+        query
+            .chars()
+            .all(|wanted| chars.any(|c| c == wanted))
+            .then_some(Closeness::Fuzzy)
+    }
+}
+
+/// Pick the record whose name matches `query` more closely than any other.
+///
+/// Fails with `missing` if no name matches, and with [`Error::Ambiguous`] if several match
+/// equally closely, describing each of them with `describe`.
+pub(crate) fn best<T>(
+    records: Vec<T>,
+    query: &str,
+    name: impl Fn(&T) -> &str,
+    describe: impl Fn(&T) -> String,
+    missing: Error,
+) -> Result<T> {
+    let mut closest = None;
+    let mut best = Vec::new();
+    for record in records {
+        let Some(this) = closeness(name(&record), query) else {
+            continue;
+        };
+        if closest.is_none_or(|closest| this < closest) {
+            closest = Some(this);
+            best.clear();
+        }
+        if closest == Some(this) {
+            best.push(record);
+        }
+    }
+
+    match best.len() {
+        0 => Err(missing),
+        1 => Ok(best.remove(0)),
+        _ => Err(Error::Ambiguous {
+            query: query.to_owned(),
+            candidates: best.iter().map(describe).collect(),
+        }),
+    }
+}
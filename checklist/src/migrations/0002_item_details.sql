@@ -0,0 +1,5 @@
+ALTER TABLE items ADD COLUMN priority TEXT;
+
+ALTER TABLE items ADD COLUMN created_at TEXT;
+
+ALTER TABLE items ADD COLUMN completed_at TEXT;
@@ -0,0 +1,7 @@
+ALTER TABLE checklists ADD COLUMN uuid TEXT;
+
+ALTER TABLE items ADD COLUMN uuid TEXT;
+
+CREATE UNIQUE INDEX checklists_uuid ON checklists (uuid);
+
+CREATE UNIQUE INDEX items_uuid ON items (uuid);
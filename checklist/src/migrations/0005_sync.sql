@@ -0,0 +1,98 @@
+CREATE TABLE sync_state (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    site TEXT NOT NULL,
+    clock INTEGER NOT NULL,
+    applying INTEGER NOT NULL DEFAULT FALSE
+) STRICT;
+
+CREATE TABLE sync_clocks (
+    tbl TEXT NOT NULL,
+    uuid TEXT NOT NULL,
+    field TEXT NOT NULL,
+    clock INTEGER NOT NULL,
+    site TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    PRIMARY KEY (tbl, uuid, field)
+) STRICT;
+
+CREATE TABLE sync_tombstones (
+    tbl TEXT NOT NULL,
+    uuid TEXT NOT NULL,
+    clock INTEGER NOT NULL,
+    site TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    PRIMARY KEY (tbl, uuid)
+) STRICT;
+
+CREATE TRIGGER checklists_sync_insert AFTER INSERT ON checklists
+WHEN (SELECT NOT applying FROM sync_state) AND new.uuid IS NOT NULL
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    DELETE FROM sync_tombstones WHERE tbl = 'checklists' AND uuid = new.uuid;
+    INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+    SELECT 'checklists', new.uuid, 'name', clock, site, clock FROM sync_state;
+END;
+
+CREATE TRIGGER checklists_sync_update AFTER UPDATE ON checklists
+WHEN (SELECT NOT applying FROM sync_state) AND new.uuid IS NOT NULL AND old.name IS NOT new.name
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+    SELECT 'checklists', new.uuid, 'name', clock, site, clock FROM sync_state;
+END;
+
+CREATE TRIGGER checklists_sync_delete AFTER DELETE ON checklists
+WHEN (SELECT NOT applying FROM sync_state) AND old.uuid IS NOT NULL
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    DELETE FROM sync_clocks WHERE tbl = 'checklists' AND uuid = old.uuid;
+    INSERT OR REPLACE INTO sync_tombstones(tbl, uuid, clock, site, seq)
+    SELECT 'checklists', old.uuid, clock, site, clock FROM sync_state;
+END;
+
+CREATE TRIGGER items_sync_insert AFTER INSERT ON items
+WHEN (SELECT NOT applying FROM sync_state) AND new.uuid IS NOT NULL
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    DELETE FROM sync_tombstones WHERE tbl = 'items' AND uuid = new.uuid;
+    INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+    SELECT 'items', new.uuid, field.column1, clock, site, clock
+    FROM sync_state, (
+        VALUES ('checklist'), ('item'), ('checked'), ('priority'), ('created_at'), ('completed_at'),
+            ('due_at')
+    ) AS field;
+END;
+
+CREATE TRIGGER items_sync_update AFTER UPDATE ON items
+WHEN (SELECT NOT applying FROM sync_state) AND new.uuid IS NOT NULL AND (
+    old.checklist IS NOT new.checklist
+    OR old.item IS NOT new.item
+    OR old.checked IS NOT new.checked
+    OR old.priority IS NOT new.priority
+    OR old.created_at IS NOT new.created_at
+    OR old.completed_at IS NOT new.completed_at
+    OR old.due_at IS NOT new.due_at
+)
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+    SELECT 'items', new.uuid, field, clock, site, clock
+    FROM sync_state, (
+        SELECT 'checklist' AS field WHERE old.checklist IS NOT new.checklist
+        UNION ALL SELECT 'item' WHERE old.item IS NOT new.item
+        UNION ALL SELECT 'checked' WHERE old.checked IS NOT new.checked
+        UNION ALL SELECT 'priority' WHERE old.priority IS NOT new.priority
+        UNION ALL SELECT 'created_at' WHERE old.created_at IS NOT new.created_at
+        UNION ALL SELECT 'completed_at' WHERE old.completed_at IS NOT new.completed_at
+        UNION ALL SELECT 'due_at' WHERE old.due_at IS NOT new.due_at
+    );
+END;
+
+CREATE TRIGGER items_sync_delete AFTER DELETE ON items
+WHEN (SELECT NOT applying FROM sync_state) AND old.uuid IS NOT NULL
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    DELETE FROM sync_clocks WHERE tbl = 'items' AND uuid = old.uuid;
+    INSERT OR REPLACE INTO sync_tombstones(tbl, uuid, clock, site, seq)
+    SELECT 'items', old.uuid, clock, site, clock FROM sync_state;
+END;
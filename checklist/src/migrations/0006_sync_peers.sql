@@ -0,0 +1,5 @@
+CREATE TABLE sync_peers (
+    site TEXT PRIMARY KEY,
+    received INTEGER NOT NULL DEFAULT 0,
+    acknowledged INTEGER NOT NULL DEFAULT 0
+) STRICT;
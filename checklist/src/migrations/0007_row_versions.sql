@@ -0,0 +1,15 @@
+ALTER TABLE checklists ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+ALTER TABLE items ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+
+CREATE TRIGGER checklists_version AFTER UPDATE ON checklists
+WHEN old.version = new.version
+BEGIN
+    UPDATE checklists SET version = version + 1 WHERE id = new.id;
+END;
+
+CREATE TRIGGER items_version AFTER UPDATE ON items
+WHEN old.version = new.version
+BEGIN
+    UPDATE items SET version = version + 1 WHERE id = new.id;
+END;
@@ -0,0 +1,57 @@
+ALTER TABLE items ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+DROP TRIGGER items_version;
+
+UPDATE items SET position = id;
+
+CREATE TRIGGER items_version AFTER UPDATE ON items
+WHEN old.version = new.version
+BEGIN
+    UPDATE items SET version = version + 1 WHERE id = new.id;
+END;
+
+DROP TRIGGER items_sync_insert;
+
+CREATE TRIGGER items_sync_insert AFTER INSERT ON items
+WHEN (SELECT NOT applying FROM sync_state) AND new.uuid IS NOT NULL
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    DELETE FROM sync_tombstones WHERE tbl = 'items' AND uuid = new.uuid;
+    INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+    SELECT 'items', new.uuid, field.column1, clock, site, clock
+    FROM sync_state, (
+        VALUES ('checklist'), ('item'), ('checked'), ('priority'), ('created_at'), ('completed_at'),
+            ('due_at'), ('position')
+    ) AS field;
+END;
+
+DROP TRIGGER items_sync_update;
+
+CREATE TRIGGER items_sync_update AFTER UPDATE ON items
+WHEN (SELECT NOT applying FROM sync_state) AND new.uuid IS NOT NULL AND (
+    old.checklist IS NOT new.checklist
+    OR old.item IS NOT new.item
+    OR old.checked IS NOT new.checked
+    OR old.priority IS NOT new.priority
+    OR old.created_at IS NOT new.created_at
+    OR old.completed_at IS NOT new.completed_at
+    OR old.due_at IS NOT new.due_at
+    OR old.position IS NOT new.position
+)
+BEGIN
+    UPDATE sync_state SET clock = clock + 1;
+    INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+    SELECT 'items', new.uuid, field, clock, site, clock
+    FROM sync_state, (
+        SELECT 'checklist' AS field WHERE old.checklist IS NOT new.checklist
+        UNION ALL SELECT 'item' WHERE old.item IS NOT new.item
+        UNION ALL SELECT 'checked' WHERE old.checked IS NOT new.checked
+        UNION ALL SELECT 'priority' WHERE old.priority IS NOT new.priority
+        UNION ALL SELECT 'created_at' WHERE old.created_at IS NOT new.created_at
+        UNION ALL SELECT 'completed_at' WHERE old.completed_at IS NOT new.completed_at
+        UNION ALL SELECT 'due_at' WHERE old.due_at IS NOT new.due_at
+        UNION ALL SELECT 'position' WHERE old.position IS NOT new.position
+    );
+END;
+
+CREATE INDEX items_position ON items (checklist, position);
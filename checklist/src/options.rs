@@ -0,0 +1,63 @@
+use crate::{Cipher, EncryptionConfig};
+
+/// How a database is protected at rest.
+///
+/// This is recorded in the database when it is created, so that reopening it in a different mode
+/// produces a clear error instead of an opaque SQLite failure.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, derive_more::Display, derive_more::FromStr,
+)]
+pub enum EncryptionMode {
+    /// Stored in plaintext; suitable only for throwaway and test databases
+    #[display("none")]
+    None,
+    /// AES 256 Bit CBC, no HMAC
+    #[default]
+    #[display("aes256cbc")]
+    Aes256Cbc,
+}
+
+impl EncryptionMode {
+    /// The cipher which implements this mode, if any.
+    pub fn cipher(self) -> Option<Cipher> {
+        match self {
+            Self::None => None,
+            Self::Aes256Cbc => Some(Cipher::Aes256Cbc),
+        }
+    }
+
+    /// The mode implemented by a particular encryption config.
+    pub fn of(encryption: Option<&EncryptionConfig>) -> Self {
+        match encryption.map(|config| &config.cipher) {
+            None => Self::None,
+            Some(Cipher::Aes256Cbc) => Self::Aes256Cbc,
+        }
+    }
+}
+
+/// Options controlling how a [`Db`][crate::Db] is opened.
+#[derive(Debug, Clone, Default)]
+pub struct DbOptions {
+    /// Encryption for data at rest.
+    ///
+    /// When `None`, the database is stored in plaintext.
+    pub encryption: Option<EncryptionConfig>,
+}
+
+impl DbOptions {
+    /// Options for an encrypted database.
+    pub fn encrypted(encryption_config: EncryptionConfig) -> Self {
+        Self {
+            encryption: Some(encryption_config),
+        }
+    }
+
+    /// Options for a plaintext database.
+    pub fn unencrypted() -> Self {
+        Self { encryption: None }
+    }
+
+    pub fn encryption_mode(&self) -> EncryptionMode {
+        EncryptionMode::of(self.encryption.as_ref())
+    }
+}
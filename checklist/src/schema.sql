@@ -12,3 +12,8 @@ CREATE TABLE IF NOT EXISTS items (
 ) STRICT;
 
 CREATE INDEX IF NOT EXISTS checklist_items ON items (checklist);
+
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+) STRICT;
@@ -0,0 +1,1027 @@
+//! Multi-device sync.
+//!
+//! Every database has a random site id, and a Lamport clock which advances with each change. Triggers
+//! stamp each field of each checklist and item with the clock and site of its last write, and record
+//! deletions as tombstones. Databases are synced by exchanging [`Changeset`]s, which carry these stamps:
+//!
+//! - Each field takes the value with the greatest [`Stamp`], so the last writer wins field by field.
+//!   Concurrent edits to different fields of the same item are both kept.
+//! - A checklist or item exists while one of its fields is stamped later than its tombstone, if any.
+//!   Deleting a checklist deletes its items.
+//!
+//! Applying a changeset merges it into the database: applying it twice changes nothing, and databases
+//! which have applied each other's changesets, in any order, have the same contents. Records are
+//! identified by uuid; integer ids remain local to each database.
+//!
+//! Changesets can be incremental. Each change is also recorded with the local *version*, the value of
+//! the clock when it was made or merged here, and [`Db::changes_since`] collects the records changed
+//! after a given version. Every database remembers, for each peer, the latest of the peer's versions it
+//! has received, and the latest of its own versions which the peer has acknowledged receiving; the
+//! latter is where [`Db::changes_for`] starts. For transport, changesets are encoded in a compact binary
+//! form which may be encrypted; see [`Changeset::to_bytes`].
+
+mod wire;
+
+use std::collections::HashMap;
+
+use libsql::{params, params_from_iter, Connection, Transaction};
+
+use crate::{uuids, Db, Error, Result, Uuid};
+
+/// When a field was last written, or a record deleted.
+///
+/// Stamps are ordered by clock, then by site, so that concurrent writes are resolved the same way
+/// everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stamp {
+    /// Lamport clock of the writing site at the time of the write
+    pub clock: u64,
+    /// Site which made the write
+    pub site: Uuid,
+}
+
+/// The value of a single field.
+///
+/// Values are ordered so that they can break ties between identical stamps, which only occur for
+/// fields written before sync was introduced.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Text(String),
+}
+
+/// A synced table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Table {
+    Checklists,
+    Items,
+}
+
+impl Table {
+    const ALL: [Self; 2] = [Self::Checklists, Self::Items];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Checklists => "checklists",
+            Self::Items => "items",
+        }
+    }
+
+    /// Synced fields, in the order in which [`Table::select`] produces them.
+    ///
+    /// An item's `checklist` is exchanged as the checklist's uuid.
+    fn fields(self) -> &'static [&'static str] {
+        match self {
+            Self::Checklists => &["name"],
+            Self::Items => &[
+                "checklist",
+                "item",
+                "checked",
+                "priority",
+                "created_at",
+                "completed_at",
+                "due_at",
+                "position",
+            ],
+        }
+    }
+
+    /// Query producing `uuid` followed by each field, for every row.
+    fn select(self) -> &'static str {
+        match self {
+            Self::Checklists => "SELECT uuid, name FROM checklists",
+            Self::Items => {
+                "SELECT items.uuid, checklists.uuid, item, checked, priority, created_at, completed_at,
+                due_at, position
+                FROM items JOIN checklists ON checklists.id = items.checklist"
+            }
+        }
+    }
+}
+
+/// A field of a record, and when it was written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FieldChange {
+    pub field: String,
+    pub value: Value,
+    pub stamp: Stamp,
+}
+
+/// The state of a single checklist or item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RowChange {
+    pub table: Table,
+    pub uuid: Uuid,
+    pub fields: Vec<FieldChange>,
+    /// When the record was deleted, if it was
+    pub deleted: Option<Stamp>,
+}
+
+/// Changes to checklists and items, as exchanged between databases.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Changeset {
+    /// Site of the database which produced this changeset
+    pub site: Uuid,
+    /// Version after which the changes were made; 0 if this changeset holds every record
+    pub since: u64,
+    /// Version of the producing database when this changeset was produced
+    pub version: u64,
+    /// For each peer of the producing database, the latest of the peer's versions it has received
+    pub received: Vec<(Uuid, u64)>,
+    pub rows: Vec<RowChange>,
+}
+
+impl Changeset {
+    /// Encode this changeset in its binary form, encrypted when a key is given.
+    ///
+    /// The key may be a passphrase: the encryption key is derived from it with Argon2id, using a fresh
+    /// salt for every changeset. Databases which sync must therefore share a key, but databases whose
+    /// keys are derived from a passphrase need not share a salt.
+    pub fn to_bytes(&self, key: Option<&[u8]>) -> Result<Vec<u8>> {
+        wire::encode(self, key)
+    }
+
+    /// Decode a changeset from its binary form.
+    ///
+    /// Fails with [`Error::InvalidChangeset`] if the changeset is encrypted but no key is given, or
+    /// the other way around.
+    pub fn from_bytes(bytes: &[u8], key: Option<&[u8]>) -> Result<Self> {
+        wire::decode(bytes, key)
+    }
+}
+
+/// Concurrent changes to the same record, resolved while applying a changeset.
+///
+/// A change is concurrent if the database which made one had not yet received the other. Conflicts are
+/// resolved by the usual rules; they are reported so that the losing change can be reviewed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub table: Table,
+    pub uuid: Uuid,
+    pub kind: ConflictKind,
+    /// Whether the incoming change won
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both databases wrote the same field
+    Field {
+        field: String,
+        local: Value,
+        incoming: Value,
+    },
+    /// This database deleted the record, while the other edited it
+    DeletedHere,
+    /// The other database deleted the record, while this one edited it
+    DeletedThere,
+}
+
+/// What applying a changeset changed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SyncSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Records which could not be applied, such as items of a checklist which does not exist here
+    pub skipped: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl SyncSummary {
+    /// Number of records which were inserted, updated or deleted.
+    pub fn changed(&self) -> usize {
+        self.inserted + self.updated + self.deleted
+    }
+}
+
+/// What syncing two databases changed in each.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SyncReport {
+    /// Changes to this database
+    pub pulled: SyncSummary,
+    /// Changes to the other database
+    pub pushed: SyncSummary,
+}
+
+/// What a database knows about another database it has synced with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Peer {
+    pub site: Uuid,
+    /// Latest of the peer's versions which has been received from it
+    pub received: u64,
+    /// Latest of this database's versions which the peer has acknowledged receiving
+    pub acknowledged: u64,
+}
+
+impl Db {
+    /// The random id which identifies this database's writes.
+    pub async fn site(&self) -> Result<Uuid> {
+        let conn = self.conn()?;
+        let (site, _) = read_state(&conn).await?;
+        Ok(site)
+    }
+
+    /// The current version of this database, which advances with each change.
+    pub async fn sync_version(&self) -> Result<u64> {
+        let conn = self.conn()?;
+        let (_, clock) = read_state(&conn).await?;
+        Ok(clock)
+    }
+
+    /// Every database this one has exchanged changesets with.
+    pub async fn peers(&self) -> Result<Vec<Peer>> {
+        let conn = self.conn()?;
+        let mut rows = conn
+            .query(
+                "SELECT site, received, acknowledged FROM sync_peers ORDER BY site",
+                (),
+            )
+            .await
+            .map_err(Error::libsql("selecting sync peers"))?;
+        let mut peers = Vec::new();
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next sync peer"))?
+        {
+            let site = row
+                .get_str(0)
+                .map_err(Error::libsql("getting site of sync peer"))?;
+            peers.push(Peer {
+                site: uuids::from_sql(site)?,
+                received: row
+                    .get::<u64>(1)
+                    .map_err(Error::libsql("getting received version of sync peer"))?,
+                acknowledged: row
+                    .get::<u64>(2)
+                    .map_err(Error::libsql("getting acknowledged version of sync peer"))?,
+            });
+        }
+        Ok(peers)
+    }
+
+    /// Collect every checklist and item changed after `since`, including deletions.
+    ///
+    /// Changed records are included whole. With `since` 0, the changeset holds every record.
+    pub async fn changes_since(&self, since: u64) -> Result<Changeset> {
+        let conn = self.conn()?;
+        let (site, version) = read_state(&conn).await?;
+        let received = self
+            .peers()
+            .await?
+            .into_iter()
+            .map(|peer| (peer.site, peer.received))
+            .collect();
+
+        let mut rows = Vec::new();
+        for table in Table::ALL {
+            let mut stamps = read_stamps(&conn, table).await?;
+            let mut deleted = read_tombstones(&conn, table, since).await?;
+
+            // records which predate sync have no stamps, so they only appear in full changesets
+            let mut result = conn
+                .query(
+                    &format!(
+                        "{} WHERE ?1 = 0 OR {}.uuid IN (
+                            SELECT uuid FROM sync_clocks WHERE tbl = ?2 AND seq > ?1
+                        )",
+                        table.select(),
+                        table.name()
+                    ),
+                    params!(since, table.name()),
+                )
+                .await
+                .map_err(Error::libsql("selecting rows for changeset"))?;
+            while let Some(row) = result
+                .next()
+                .await
+                .map_err(Error::libsql("getting next row for changeset"))?
+            {
+                let uuid = uuids::from_sql(
+                    row.get_str(0)
+                        .map_err(Error::libsql("getting uuid for changeset"))?,
+                )?;
+                let mut fields = Vec::with_capacity(table.fields().len());
+                for (idx, field) in table.fields().iter().enumerate() {
+                    let value = row
+                        .get_value(idx as i32 + 1)
+                        .map_err(Error::libsql("getting field for changeset"))?;
+                    fields.push(FieldChange {
+                        field: (*field).to_owned(),
+                        value: Value::from_sql(value)?,
+                        stamp: stamps.remove(&(uuid, *field)).unwrap_or_default(),
+                    });
+                }
+                rows.push(RowChange {
+                    table,
+                    uuid,
+                    fields,
+                    deleted: deleted.remove(&uuid),
+                });
+            }
+
+            rows.extend(deleted.into_iter().map(|(uuid, stamp)| RowChange {
+                table,
+                uuid,
+                fields: Vec::new(),
+                deleted: Some(stamp),
+            }));
+        }
+        rows.sort_by_key(|row| (row.table, row.uuid));
+
+        Ok(Changeset {
+            site,
+            since,
+            version,
+            received,
+            rows,
+        })
+    }
+
+    /// Collect the changes which `peer` has not yet acknowledged receiving.
+    pub async fn changes_for(&self, peer: Uuid) -> Result<Changeset> {
+        let acknowledged = self
+            .peers()
+            .await?
+            .into_iter()
+            .find(|known| known.site == peer)
+            .map(|known| known.acknowledged)
+            .unwrap_or_default();
+        self.changes_since(acknowledged).await
+    }
+
+    /// Merge a changeset from another database into this one, in a single transaction.
+    ///
+    /// This also records how far this database has received the other's changes, and how far the other
+    /// has received this database's.
+    pub async fn apply_changeset(&self, changeset: &Changeset) -> Result<SyncSummary> {
+        let conn = self.conn()?;
+        let tx = conn
+            .transaction()
+            .await
+            .map_err(Error::libsql("beginning sync transaction"))?;
+
+        // the merged rows are stamped as they arrived, so they must not be stamped again
+        set_applying(&tx, true).await?;
+
+        let (site, clock) = read_state(&tx).await?;
+        let incoming = changeset
+            .rows
+            .iter()
+            .flat_map(|row| {
+                row.fields
+                    .iter()
+                    .map(|field| field.stamp)
+                    .chain(row.deleted)
+            })
+            .map(|stamp| stamp.clock)
+            .max()
+            .unwrap_or_default();
+        let seq = clock.max(incoming) + 1;
+        tx.execute("UPDATE sync_state SET clock = ?1", [seq])
+            .await
+            .map_err(Error::libsql("advancing sync clock"))?;
+
+        let apply = Apply {
+            seq,
+            acknowledged: changeset
+                .received
+                .iter()
+                .find(|(peer, _)| *peer == site)
+                .map(|(_, version)| *version)
+                .unwrap_or_default(),
+        };
+        let mut summary = SyncSummary::default();
+        for table in Table::ALL {
+            let mut rows = changeset
+                .rows
+                .iter()
+                .filter(|row| row.table == table)
+                .collect::<Vec<_>>();
+            rows.sort_by_key(|row| row.uuid);
+            for row in rows {
+                apply.row(&tx, row, &mut summary).await?;
+            }
+        }
+
+        if changeset.site != site {
+            // a changeset which starts after the last one received leaves a gap, so it is not counted
+            // as received; its changes will arrive again
+            tx.execute(
+                "INSERT INTO sync_peers(site) VALUES (?1) ON CONFLICT DO NOTHING",
+                [uuids::to_sql(&changeset.site)],
+            )
+            .await
+            .map_err(Error::libsql("recording sync peer"))?;
+            tx.execute(
+                "UPDATE sync_peers SET
+                received = CASE WHEN ?2 <= received THEN max(received, ?3) ELSE received END,
+                acknowledged = max(acknowledged, ?4)
+                WHERE site = ?1",
+                params!(
+                    uuids::to_sql(&changeset.site),
+                    changeset.since,
+                    changeset.version,
+                    apply.acknowledged
+                ),
+            )
+            .await
+            .map_err(Error::libsql("recording sync progress of peer"))?;
+        }
+
+        set_applying(&tx, false).await?;
+        tx.commit()
+            .await
+            .map_err(Error::libsql("committing sync transaction"))?;
+
+        Ok(summary)
+    }
+
+    /// Sync this database with another, so that both have the same contents.
+    pub async fn sync_with(&self, other: &Db) -> Result<SyncReport> {
+        let ours = self.changes_for(other.site().await?).await?;
+        let theirs = other.changes_for(self.site().await?).await?;
+
+        Ok(SyncReport {
+            pulled: self.apply_changeset(&theirs).await?,
+            pushed: other.apply_changeset(&ours).await?,
+        })
+    }
+}
+
+impl Value {
+    fn from_sql(value: libsql::Value) -> Result<Self> {
+        match value {
+            libsql::Value::Null => Ok(Self::Null),
+            libsql::Value::Integer(value) => Ok(Self::Integer(value)),
+            libsql::Value::Text(value) => Ok(Self::Text(value)),
+            _ => Err(Error::InvalidChangeset(
+                "field has an unsupported type".to_owned(),
+            )),
+        }
+    }
+
+    fn to_sql(&self) -> libsql::Value {
+        match self {
+            Self::Null => libsql::Value::Null,
+            Self::Integer(value) => libsql::Value::Integer(*value),
+            Self::Text(value) => libsql::Value::Text(value.clone()),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => f.write_str("nothing"),
+            Self::Integer(value) => write!(f, "{value}"),
+            Self::Text(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let record = match self.table {
+            Table::Checklists => "checklist",
+            Table::Items => "item",
+        };
+        write!(f, "{record} {}: ", self.uuid)?;
+        match (&self.kind, self.accepted) {
+            (
+                ConflictKind::Field {
+                    field,
+                    local,
+                    incoming,
+                },
+                accepted,
+            ) => {
+                let kept = if accepted { incoming } else { local };
+                write!(
+                    f,
+                    "`{field}` was set to {local} here and to {incoming} there; kept {kept}"
+                )
+            }
+            (ConflictKind::DeletedHere, true) => {
+                f.write_str("deleted here but edited there; restored it")
+            }
+            (ConflictKind::DeletedHere, false) => {
+                f.write_str("deleted here but edited there; kept it deleted")
+            }
+            (ConflictKind::DeletedThere, true) => {
+                f.write_str("edited here but deleted there; deleted it")
+            }
+            (ConflictKind::DeletedThere, false) => {
+                f.write_str("edited here but deleted there; kept it")
+            }
+        }
+    }
+}
+
+/// Assign this database a site id, if it has none yet.
+pub(crate) async fn init(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sync_state(id, site, clock) VALUES (0, ?1, 0) ON CONFLICT DO NOTHING",
+        [uuids::to_sql(&uuids::new())],
+    )
+    .await
+    .map_err(Error::libsql("recording sync site"))?;
+    Ok(())
+}
+
+/// Suspend or resume the triggers which stamp changes.
+pub(crate) async fn set_applying(conn: &Connection, applying: bool) -> Result<()> {
+    conn.execute("UPDATE sync_state SET applying = ?1", [applying])
+        .await
+        .map_err(Error::libsql("updating sync state"))?;
+    Ok(())
+}
+
+async fn read_state(conn: &Connection) -> Result<(Uuid, u64)> {
+    let mut rows = conn
+        .query("SELECT site, clock FROM sync_state", ())
+        .await
+        .map_err(Error::libsql("reading sync state"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for sync state"))?
+        .expect("sync state is initialized when the database is opened");
+    let site = row
+        .get_str(0)
+        .map_err(Error::libsql("getting site from sync state"))?;
+    let clock = row
+        .get::<u64>(1)
+        .map_err(Error::libsql("getting clock from sync state"))?;
+    Ok((uuids::from_sql(site)?, clock))
+}
+
+async fn read_stamps(
+    conn: &Connection,
+    table: Table,
+) -> Result<HashMap<(Uuid, &'static str), Stamp>> {
+    let mut stamps = HashMap::new();
+    let mut rows = conn
+        .query(
+            "SELECT uuid, field, clock, site FROM sync_clocks WHERE tbl = ?1",
+            [table.name()],
+        )
+        .await
+        .map_err(Error::libsql("reading field stamps"))?;
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting next field stamp"))?
+    {
+        let uuid = row
+            .get_str(0)
+            .map_err(Error::libsql("getting uuid of field stamp"))?;
+        let field = row
+            .get_str(1)
+            .map_err(Error::libsql("getting field of field stamp"))?;
+        let Some(field) = table.fields().iter().find(|known| **known == field) else {
+            continue;
+        };
+        stamps.insert((uuids::from_sql(uuid)?, *field), stamp_from_row(&row, 2)?);
+    }
+    Ok(stamps)
+}
+
+/// Read the tombstones recorded after `since`.
+async fn read_tombstones(
+    conn: &Connection,
+    table: Table,
+    since: u64,
+) -> Result<HashMap<Uuid, Stamp>> {
+    let mut tombstones = HashMap::new();
+    let mut rows = conn
+        .query(
+            "SELECT uuid, clock, site FROM sync_tombstones WHERE tbl = ?1 AND seq > ?2",
+            params!(table.name(), since),
+        )
+        .await
+        .map_err(Error::libsql("reading tombstones"))?;
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting next tombstone"))?
+    {
+        let uuid = row
+            .get_str(0)
+            .map_err(Error::libsql("getting uuid of tombstone"))?;
+        tombstones.insert(uuids::from_sql(uuid)?, stamp_from_row(&row, 1)?);
+    }
+    Ok(tombstones)
+}
+
+/// Read a stamp from the `clock` and `site` columns starting at `idx`.
+fn stamp_from_row(row: &libsql::Row, idx: i32) -> Result<Stamp> {
+    let clock = row
+        .get::<u64>(idx)
+        .map_err(Error::libsql("getting clock of stamp"))?;
+    let site = row
+        .get_str(idx + 1)
+        .map_err(Error::libsql("getting site of stamp"))?;
+    Ok(Stamp {
+        clock,
+        site: uuids::from_sql(site)?,
+    })
+}
+
+/// A field as it is stored here.
+struct LocalField {
+    stamp: Stamp,
+    /// Version at which the field was written or merged here; 0 if it predates sync
+    seq: u64,
+    value: Value,
+}
+
+/// Applies the rows of a single changeset.
+struct Apply {
+    /// Version at which the changes are merged
+    seq: u64,
+    /// Latest version of this database which the other had received
+    acknowledged: u64,
+}
+
+impl Apply {
+    /// Merge the state of a single record into the database.
+    async fn row(
+        &self,
+        tx: &Transaction,
+        row: &RowChange,
+        summary: &mut SyncSummary,
+    ) -> Result<()> {
+        let table = row.table;
+        let uuid = uuids::to_sql(&row.uuid);
+        let unseen = |seq: u64| seq > self.acknowledged;
+
+        // the local state of the record
+        let tombstone = local_tombstone(tx, table, &uuid).await?;
+        let local = local_fields(tx, table, &uuid).await?;
+        let exists = local.is_some();
+        let local = local.unwrap_or_default();
+        let edited_here = local.values().any(|field| unseen(field.seq));
+
+        let mut fields = local
+            .iter()
+            .map(|(field, local)| (*field, (local.stamp, local.value.clone())))
+            .collect::<HashMap<_, _>>();
+        let mut changed = Vec::new();
+        for change in &row.fields {
+            let Some(field) = table.fields().iter().find(|known| **known == change.field) else {
+                // written by a newer version of this library
+                continue;
+            };
+            let incoming = (change.stamp, &change.value);
+            let newer = match local.get(field) {
+                None => true,
+                Some(local) => {
+                    let newer = incoming > (local.stamp, &local.value);
+                    if unseen(local.seq)
+                        && local.stamp != change.stamp
+                        && local.value != change.value
+                    {
+                        summary.conflicts.push(Conflict {
+                            table,
+                            uuid: row.uuid,
+                            kind: ConflictKind::Field {
+                                field: (*field).to_owned(),
+                                local: local.value.clone(),
+                                incoming: change.value.clone(),
+                            },
+                            accepted: newer,
+                        });
+                    }
+                    newer
+                }
+            };
+            if newer {
+                fields.insert(field, (change.stamp, change.value.clone()));
+                changed.push(*field);
+            }
+        }
+
+        let local_deleted = tombstone.map(|(stamp, _)| stamp);
+        let deleted = local_deleted.max(row.deleted);
+        let latest = fields.values().map(|(stamp, _)| *stamp).max();
+        let alive = match (latest, deleted) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(latest), Some(deleted)) => latest > deleted,
+        };
+
+        if exists && edited_here && row.deleted > local_deleted {
+            summary.conflicts.push(Conflict {
+                table,
+                uuid: row.uuid,
+                kind: ConflictKind::DeletedThere,
+                accepted: !alive,
+            });
+        }
+        if let Some((_, seq)) = tombstone.filter(|_| !exists && !row.fields.is_empty()) {
+            if unseen(seq)
+                && row
+                    .fields
+                    .iter()
+                    .any(|field| Some(field.stamp) > local_deleted)
+            {
+                summary.conflicts.push(Conflict {
+                    table,
+                    uuid: row.uuid,
+                    kind: ConflictKind::DeletedHere,
+                    accepted: alive,
+                });
+            }
+        }
+
+        if let Some(stamp) = deleted.filter(|_| deleted > local_deleted) {
+            tx.execute(
+                "INSERT OR REPLACE INTO sync_tombstones(tbl, uuid, clock, site, seq)
+                VALUES (?1, ?2, ?3, ?4, ?5)",
+                params!(
+                    table.name(),
+                    uuid.as_str(),
+                    stamp.clock,
+                    uuids::to_sql(&stamp.site),
+                    self.seq
+                ),
+            )
+            .await
+            .map_err(Error::libsql("recording tombstone"))?;
+        }
+
+        if !alive {
+            if exists {
+                delete_row(tx, table, &uuid, deleted.unwrap_or_default(), self.seq).await?;
+                summary.deleted += 1;
+            }
+            return Ok(());
+        }
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        if exists {
+            let mut values = vec![libsql::Value::Text(uuid.clone())];
+            for field in &changed {
+                let value = sql_value(tx, table, field, &fields[field].1).await?;
+                let Some(value) = value else {
+                    // the row is applied whole or not at all, so it can be applied later
+                    summary.skipped += 1;
+                    return Ok(());
+                };
+                values.push(value);
+            }
+            let assignments = changed
+                .iter()
+                .enumerate()
+                .map(|(idx, field)| format!("{field} = ?{}", idx + 2))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tx.execute(
+                &format!("UPDATE {} SET {assignments} WHERE uuid = ?1", table.name()),
+                params_from_iter(values),
+            )
+            .await
+            .map_err(Error::libsql("updating synced row"))?;
+            summary.updated += 1;
+        } else {
+            let mut values = vec![libsql::Value::Text(uuid.clone())];
+            for field in table.fields() {
+                let value = match fields.get(field) {
+                    Some((_, value)) => sql_value(tx, table, field, value).await?,
+                    None => None,
+                };
+                let Some(value) = value else {
+                    // a record must arrive whole before it can be inserted
+                    summary.skipped += 1;
+                    return Ok(());
+                };
+                values.push(value);
+            }
+            let placeholders = (1..=values.len())
+                .map(|idx| format!("?{idx}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tx.execute(
+                &format!(
+                    "INSERT INTO {}(uuid, {}) VALUES ({placeholders})",
+                    table.name(),
+                    table.fields().join(", ")
+                ),
+                params_from_iter(values),
+            )
+            .await
+            .map_err(Error::libsql("inserting synced row"))?;
+            summary.inserted += 1;
+        }
+
+        for field in changed {
+            let (stamp, _) = &fields[field];
+            tx.execute(
+                "INSERT OR REPLACE INTO sync_clocks(tbl, uuid, field, clock, site, seq)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params!(
+                    table.name(),
+                    uuid.as_str(),
+                    field,
+                    stamp.clock,
+                    uuids::to_sql(&stamp.site),
+                    self.seq
+                ),
+            )
+            .await
+            .map_err(Error::libsql("recording field stamp"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The tombstone of a record and the version at which it was recorded, if the record was deleted.
+async fn local_tombstone(
+    tx: &Transaction,
+    table: Table,
+    uuid: &str,
+) -> Result<Option<(Stamp, u64)>> {
+    let mut rows = tx
+        .query(
+            "SELECT clock, site, seq FROM sync_tombstones WHERE tbl = ?1 AND uuid = ?2",
+            [table.name(), uuid],
+        )
+        .await
+        .map_err(Error::libsql("reading tombstone"))?;
+    let row = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for tombstone"))?;
+    row.map(|row| {
+        let seq = row
+            .get::<u64>(2)
+            .map_err(Error::libsql("getting version of tombstone"))?;
+        Ok((stamp_from_row(&row, 0)?, seq))
+    })
+    .transpose()
+}
+
+/// The fields of a record, if the record exists.
+///
+/// Fields which have never been stamped have the default stamp.
+async fn local_fields(
+    tx: &Transaction,
+    table: Table,
+    uuid: &str,
+) -> Result<Option<HashMap<&'static str, LocalField>>> {
+    let mut rows = tx
+        .query(
+            &format!("{} WHERE {}.uuid = ?1", table.select(), table.name()),
+            [uuid],
+        )
+        .await
+        .map_err(Error::libsql("reading local row"))?;
+    let Some(row) = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting result row for local row"))?
+    else {
+        return Ok(None);
+    };
+
+    let mut fields = HashMap::new();
+    for (idx, field) in table.fields().iter().enumerate() {
+        let value = row
+            .get_value(idx as i32 + 1)
+            .map_err(Error::libsql("getting local field"))?;
+        fields.insert(
+            *field,
+            LocalField {
+                stamp: Stamp::default(),
+                seq: 0,
+                value: Value::from_sql(value)?,
+            },
+        );
+    }
+
+    let mut rows = tx
+        .query(
+            "SELECT field, clock, site, seq FROM sync_clocks WHERE tbl = ?1 AND uuid = ?2",
+            [table.name(), uuid],
+        )
+        .await
+        .map_err(Error::libsql("reading local field stamps"))?;
+    while let Some(row) = rows
+        .next()
+        .await
+        .map_err(Error::libsql("getting next local field stamp"))?
+    {
+        let field = row
+            .get_str(0)
+            .map_err(Error::libsql("getting field of local field stamp"))?;
+        if let Some(local) = fields.get_mut(field) {
+            local.stamp = stamp_from_row(&row, 1)?;
+            local.seq = row
+                .get::<u64>(3)
+                .map_err(Error::libsql("getting version of local field stamp"))?;
+        }
+    }
+
+    Ok(Some(fields))
+}
+
+/// Convert a field's value to the form stored in the database.
+///
+/// Returns `None` for an item whose checklist does not exist here.
+async fn sql_value(
+    tx: &Transaction,
+    table: Table,
+    field: &str,
+    value: &Value,
+) -> Result<Option<libsql::Value>> {
+    if (table, field) != (Table::Items, "checklist") {
+        return Ok(Some(value.to_sql()));
+    }
+
+    let Value::Text(checklist) = value else {
+        return Err(Error::InvalidChangeset(
+            "an item's checklist must be a uuid".to_owned(),
+        ));
+    };
+    let mut rows = tx
+        .query(
+            "SELECT id FROM checklists WHERE uuid = ?1",
+            [checklist.as_str()],
+        )
+        .await
+        .map_err(Error::libsql("finding checklist of synced item"))?;
+    let row = rows.next().await.map_err(Error::libsql(
+        "getting result row for checklist of synced item",
+    ))?;
+    row.map(|row| {
+        row.get::<i64>(0)
+            .map(libsql::Value::Integer)
+            .map_err(Error::libsql("getting id of checklist of synced item"))
+    })
+    .transpose()
+}
+
+/// Delete a record which a tombstone has overtaken, along with the items of a checklist.
+async fn delete_row(
+    tx: &Transaction,
+    table: Table,
+    uuid: &str,
+    deleted: Stamp,
+    seq: u64,
+) -> Result<()> {
+    if table == Table::Checklists {
+        // deleting the checklist cascades to its items, which are deleted as of the same time
+        tx.execute(
+            "INSERT INTO sync_tombstones(tbl, uuid, clock, site, seq)
+            SELECT 'items', items.uuid, ?1, ?2, ?3
+            FROM items JOIN checklists ON checklists.id = items.checklist
+            WHERE checklists.uuid = ?4
+            ON CONFLICT(tbl, uuid) DO UPDATE SET clock = excluded.clock, site = excluded.site,
+                seq = excluded.seq
+            WHERE (excluded.clock, excluded.site) > (clock, site)",
+            params!(deleted.clock, uuids::to_sql(&deleted.site), seq, uuid),
+        )
+        .await
+        .map_err(Error::libsql(
+            "recording tombstones of deleted checklist's items",
+        ))?;
+        tx.execute(
+            "DELETE FROM sync_clocks WHERE tbl = 'items' AND uuid IN (
+                SELECT items.uuid FROM items JOIN checklists ON checklists.id = items.checklist
+                WHERE checklists.uuid = ?1
+            )",
+            [uuid],
+        )
+        .await
+        .map_err(Error::libsql(
+            "forgetting field stamps of deleted checklist's items",
+        ))?;
+    }
+
+    tx.execute(
+        &format!("DELETE FROM {} WHERE uuid = ?1", table.name()),
+        [uuid],
+    )
+    .await
+    .map_err(Error::libsql("deleting synced row"))?;
+    tx.execute(
+        "DELETE FROM sync_clocks WHERE tbl = ?1 AND uuid = ?2",
+        [table.name(), uuid],
+    )
+    .await
+    .map_err(Error::libsql("forgetting field stamps of deleted row"))?;
+
+    Ok(())
+}
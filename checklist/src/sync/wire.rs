@@ -0,0 +1,337 @@
+//! The binary form of changesets.
+//!
+//! A changeset starts with a header:
+//!
+//! - the magic bytes `CLCS`, then the format version, currently 1;
+//! - a byte which is 0 for a plaintext changeset, or 1 for an encrypted one;
+//! - for an encrypted changeset, the Argon2id memory cost, iterations and parallelism as little-endian
+//!   `u32`s, the 16-byte salt, and the 24-byte XChaCha20-Poly1305 nonce.
+//!
+//! The header cannot be authenticated until a key has been derived from it, so changesets whose costs
+//! exceed [`KdfCost::MAX`] are rejected before deriving.
+//!
+//! The body follows, encrypted with the header as associated data when the changeset is encrypted.
+//! Integers in the body are LEB128 varints, signed ones zigzag-encoded; strings are a varint length
+//! followed by UTF-8; uuids are 16 raw bytes. The body holds the site, `since` and `version`, the
+//! received versions of peers, a table of the sites which appear in stamps, and then the rows. Stamps
+//! refer to sites by their index in that table.
+
+use std::collections::HashMap;
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+
+use super::{Changeset, FieldChange, RowChange, Stamp, Table, Value};
+use crate::{
+    kdf::{KdfCost, KdfHeader},
+    Error, Result, Uuid,
+};
+
+const MAGIC: &[u8; 4] = b"CLCS";
+const FORMAT_VERSION: u8 = 1;
+const PLAINTEXT: u8 = 0;
+const ENCRYPTED: u8 = 1;
+const NONCE_LEN: usize = 24;
+
+const ROW_ITEM: u8 = 1 << 0;
+const ROW_DELETED: u8 = 1 << 1;
+
+const VALUE_NULL: u8 = 0;
+const VALUE_INTEGER: u8 = 1;
+const VALUE_TEXT: u8 = 2;
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidChangeset(reason.to_owned())
+}
+
+pub(super) fn encode(changeset: &Changeset, key: Option<&[u8]>) -> Result<Vec<u8>> {
+    let mut header = Vec::from(*MAGIC);
+    header.push(FORMAT_VERSION);
+    let body = encode_body(changeset);
+
+    let Some(key) = key else {
+        header.push(PLAINTEXT);
+        header.extend(body);
+        return Ok(header);
+    };
+
+    let kdf = KdfHeader::generate(KdfCost::default())?;
+    let mut nonce = [0; NONCE_LEN];
+    getrandom::getrandom(&mut nonce).map_err(Error::Random)?;
+    header.push(ENCRYPTED);
+    for cost in [
+        kdf.cost.memory_kib,
+        kdf.cost.iterations,
+        kdf.cost.parallelism,
+    ] {
+        header.extend(cost.to_le_bytes());
+    }
+    header.extend(kdf.salt);
+    header.extend(nonce);
+
+    let cipher = XChaCha20Poly1305::new_from_slice(&kdf.derive_key(key)?)
+        .expect("derived keys have the length of a XChaCha20-Poly1305 key");
+    let payload = Payload {
+        msg: &body,
+        aad: &header,
+    };
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), payload)
+        .map_err(|_| invalid("changeset is too large to encrypt"))?;
+    header.extend(ciphertext);
+    Ok(header)
+}
+
+pub(super) fn decode(bytes: &[u8], key: Option<&[u8]>) -> Result<Changeset> {
+    let mut reader = Reader(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a changeset"));
+    }
+    if reader.byte()? != FORMAT_VERSION {
+        return Err(invalid("unsupported format version"));
+    }
+
+    match (reader.byte()?, key) {
+        (PLAINTEXT, None) => decode_body(reader),
+        (PLAINTEXT, Some(_)) => Err(invalid("changeset is not encrypted")),
+        (ENCRYPTED, None) => Err(invalid("changeset is encrypted, but no key was given")),
+        (ENCRYPTED, Some(key)) => {
+            let mut cost = [0; 3];
+            for cost in &mut cost {
+                let bytes = reader.take(4)?.try_into().expect("took 4 bytes");
+                *cost = u32::from_le_bytes(bytes);
+            }
+            let [memory_kib, iterations, parallelism] = cost;
+            let cost = KdfCost {
+                memory_kib,
+                iterations,
+                parallelism,
+            };
+            if !cost.is_bounded() {
+                return Err(invalid("key derivation cost is too high"));
+            }
+            let kdf = KdfHeader {
+                cost,
+                salt: reader.take(16)?.try_into().expect("took 16 bytes"),
+            };
+            let nonce = reader.take(NONCE_LEN)?;
+            let header = &bytes[..bytes.len() - reader.0.len()];
+
+            let cipher = XChaCha20Poly1305::new_from_slice(&kdf.derive_key(key)?)
+                .expect("derived keys have the length of a XChaCha20-Poly1305 key");
+            let payload = Payload {
+                msg: reader.0,
+                aad: header,
+            };
+            let body = cipher
+                .decrypt(XNonce::from_slice(nonce), payload)
+                .map_err(|_| invalid("cannot decrypt changeset; the key may be wrong"))?;
+            decode_body(Reader(&body))
+        }
+        _ => Err(invalid("unknown encryption mode")),
+    }
+}
+
+fn encode_body(changeset: &Changeset) -> Vec<u8> {
+    let mut sites = Vec::new();
+    let mut site_indices = HashMap::new();
+    let stamps = changeset.rows.iter().flat_map(|row| {
+        row.fields
+            .iter()
+            .map(|field| field.stamp)
+            .chain(row.deleted)
+    });
+    for stamp in stamps {
+        site_indices.entry(stamp.site).or_insert_with(|| {
+            sites.push(stamp.site);
+            sites.len() as u64 - 1
+        });
+    }
+
+    let mut writer = Writer::default();
+    writer.uuid(&changeset.site);
+    writer.varint(changeset.since);
+    writer.varint(changeset.version);
+    writer.varint(changeset.received.len() as u64);
+    for (site, version) in &changeset.received {
+        writer.uuid(site);
+        writer.varint(*version);
+    }
+    writer.varint(sites.len() as u64);
+    for site in &sites {
+        writer.uuid(site);
+    }
+
+    let stamp = |writer: &mut Writer, stamp: &Stamp| {
+        writer.varint(stamp.clock);
+        writer.varint(site_indices[&stamp.site]);
+    };
+    writer.varint(changeset.rows.len() as u64);
+    for row in &changeset.rows {
+        let mut flags = 0;
+        if row.table == Table::Items {
+            flags |= ROW_ITEM;
+        }
+        if row.deleted.is_some() {
+            flags |= ROW_DELETED;
+        }
+        writer.0.push(flags);
+        writer.uuid(&row.uuid);
+        if let Some(deleted) = &row.deleted {
+            stamp(&mut writer, deleted);
+        }
+        writer.varint(row.fields.len() as u64);
+        for field in &row.fields {
+            writer.str(&field.field);
+            stamp(&mut writer, &field.stamp);
+            match &field.value {
+                Value::Null => writer.0.push(VALUE_NULL),
+                Value::Integer(value) => {
+                    writer.0.push(VALUE_INTEGER);
+                    writer.varint(((value << 1) ^ (value >> 63)) as u64);
+                }
+                Value::Text(value) => {
+                    writer.0.push(VALUE_TEXT);
+                    writer.str(value);
+                }
+            }
+        }
+    }
+
+    writer.0
+}
+
+fn decode_body(mut reader: Reader) -> Result<Changeset> {
+    let site = reader.uuid()?;
+    let since = reader.varint()?;
+    let version = reader.varint()?;
+    let received = (0..reader.varint()?)
+        .map(|_| Ok((reader.uuid()?, reader.varint()?)))
+        .collect::<Result<_>>()?;
+    let sites = (0..reader.varint()?)
+        .map(|_| reader.uuid())
+        .collect::<Result<Vec<_>>>()?;
+
+    let stamp = |reader: &mut Reader| {
+        let clock = reader.varint()?;
+        let site = usize::try_from(reader.varint()?)
+            .ok()
+            .and_then(|idx| sites.get(idx))
+            .ok_or_else(|| invalid("stamp refers to an unknown site"))?;
+        Ok::<_, Error>(Stamp { clock, site: *site })
+    };
+    let mut rows = Vec::new();
+    for _ in 0..reader.varint()? {
+        let flags = reader.byte()?;
+        let table = match flags & ROW_ITEM {
+            0 => Table::Checklists,
+            _ => Table::Items,
+        };
+        let uuid = reader.uuid()?;
+        let deleted = match flags & ROW_DELETED {
+            0 => None,
+            _ => Some(stamp(&mut reader)?),
+        };
+        let mut fields = Vec::new();
+        for _ in 0..reader.varint()? {
+            let field = reader.str()?;
+            let stamp = stamp(&mut reader)?;
+            let value = match reader.byte()? {
+                VALUE_NULL => Value::Null,
+                VALUE_INTEGER => {
+                    let value = reader.varint()?;
+                    Value::Integer((value >> 1) as i64 ^ -((value & 1) as i64))
+                }
+                VALUE_TEXT => Value::Text(reader.str()?),
+                _ => return Err(invalid("unknown value type")),
+            };
+            fields.push(FieldChange {
+                field,
+                value,
+                stamp,
+            });
+        }
+        rows.push(RowChange {
+            table,
+            uuid,
+            fields,
+            deleted,
+        });
+    }
+
+    if !reader.0.is_empty() {
+        return Err(invalid("unexpected bytes after the last row"));
+    }
+
+    Ok(Changeset {
+        site,
+        since,
+        version,
+        received,
+        rows,
+    })
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    fn uuid(&mut self, uuid: &Uuid) {
+        self.0.extend(uuid.as_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.varint(value.len() as u64);
+        self.0.extend(value.as_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid("changeset is truncated"));
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint is too long"))
+    }
+
+    fn uuid(&mut self) -> Result<Uuid> {
+        let bytes = self.take(16)?.try_into().expect("took 16 bytes");
+        Ok(Uuid::from_bytes(bytes))
+    }
+
+    fn str(&mut self) -> Result<String> {
+        let len = usize::try_from(self.varint()?).map_err(|_| invalid("string is too long"))?;
+        String::from_utf8(self.take(len)?.to_owned())
+            .map_err(|_| invalid("string is not valid UTF-8"))
+    }
+}
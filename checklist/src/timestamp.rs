@@ -0,0 +1,22 @@
+//! Timestamps as stored in the database.
+//!
+//! Timestamps are stored as RFC 3339 text in UTC with millisecond precision, so that they sort
+//! lexically.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::{Error, Result};
+
+pub(crate) fn to_sql(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+pub(crate) fn from_sql(text: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|timestamp| timestamp.to_utc())
+        .map_err(|_| Error::InvalidTimestamp(text.to_owned()))
+}
+
+pub(crate) fn now() -> String {
+    to_sql(&Utc::now())
+}
@@ -0,0 +1,64 @@
+//! Globally unique ids as stored in the database.
+//!
+//! Integer ids are only unique within a single database, so every checklist and item also has a
+//! UUIDv7, which identifies it across exports, imports and copies. Uuids are stored as hyphenated
+//! lowercase text.
+
+use libsql::{params, Connection};
+use uuid::Uuid;
+
+use crate::{Error, Result};
+
+pub(crate) fn to_sql(uuid: &Uuid) -> String {
+    uuid.hyphenated().to_string()
+}
+
+pub(crate) fn from_sql(text: &str) -> Result<Uuid> {
+    Uuid::try_parse(text).map_err(|_| Error::InvalidUuid(text.to_owned()))
+}
+
+pub(crate) fn new() -> Uuid {
+    Uuid::now_v7()
+}
+
+/// Assign a uuid to every checklist and item which lacks one.
+///
+/// Rows only lack uuids when they were written by a version of the schema without them, either before
+/// a migration or in a copy from an older database.
+pub(crate) async fn fill_missing(conn: &Connection) -> Result<()> {
+    let tx = conn
+        .transaction()
+        .await
+        .map_err(Error::libsql("beginning uuid assignment transaction"))?;
+
+    for table in ["checklists", "items"] {
+        let mut ids = Vec::new();
+        let mut rows = tx
+            .query(&format!("SELECT id FROM {table} WHERE uuid IS NULL"), ())
+            .await
+            .map_err(Error::libsql("listing rows without uuids"))?;
+        while let Some(row) = rows
+            .next()
+            .await
+            .map_err(Error::libsql("getting next row without a uuid"))?
+        {
+            ids.push(
+                row.get::<i64>(0)
+                    .map_err(Error::libsql("getting id of row without a uuid"))?,
+            );
+        }
+
+        for id in ids {
+            tx.execute(
+                &format!("UPDATE {table} SET uuid = ?1 WHERE id = ?2"),
+                params!(to_sql(&new()), id),
+            )
+            .await
+            .map_err(Error::libsql("assigning uuid"))?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(Error::libsql("committing uuid assignment transaction"))
+}
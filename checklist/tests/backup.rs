@@ -0,0 +1,76 @@
+mod common;
+
+use std::time::Duration;
+
+use checklist::{kdf::KdfHeader, Checklist, Db, DbOptions, KdfCost};
+use common::TempDir;
+
+async fn names(db: &Db) -> Vec<String> {
+    let checklists = Checklist::all(db).await.unwrap();
+    checklists
+        .into_iter()
+        .map(|checklist| checklist.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn restoring_a_backup_restores_its_key_header() {
+    let dir = TempDir::new();
+    let path = dir.path().join("db.sqlite3");
+    let backup = dir.path().join("backup.sqlite3");
+    let cost = KdfCost {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+    let header = KdfHeader::generate(cost).unwrap();
+    header.store(&path).unwrap();
+    let key = header.encryption_config(b"passphrase").unwrap();
+
+    let db = Db::new(&path, key.clone()).await.unwrap();
+    Checklist::new(&db, "groceries").await.unwrap();
+    db.backup_to(&backup).await.unwrap();
+    assert_eq!(KdfHeader::load(&backup).unwrap(), Some(header.clone()));
+    assert!(db.backup_to(&backup).await.is_err());
+
+    // the header goes missing along with changes made after the backup
+    Checklist::new(&db, "chores").await.unwrap();
+    KdfHeader::remove(&path).unwrap();
+    db.restore_from(&backup).await.unwrap();
+    assert_eq!(names(&db).await, ["groceries"]);
+    assert_eq!(KdfHeader::load(&path).unwrap(), Some(header));
+    drop(db);
+
+    let db = Db::open(&path, DbOptions::encrypted(key)).await.unwrap();
+    assert_eq!(names(&db).await, ["groceries"]);
+    assert!(db.restore_from(dir.path().join("missing")).await.is_err());
+}
+
+#[tokio::test]
+async fn rotating_backups_keep_the_newest() {
+    let dir = TempDir::new();
+    let db = Db::open(dir.path().join("db.sqlite3"), DbOptions::unencrypted())
+        .await
+        .unwrap();
+
+    let mut written = Vec::new();
+    for name in ["groceries", "chores", "garden"] {
+        Checklist::new(&db, name).await.unwrap();
+        written.push(db.backup_rotating(2).await.unwrap());
+        // backups are named by the millisecond
+        std::thread::sleep(Duration::from_millis(2));
+    }
+    let mut backups = db.rotating_backups().unwrap();
+    backups.sort();
+    assert_eq!(backups, written[1..]);
+
+    // even when none are to be kept, the new backup is
+    let newest = db.backup_rotating(0).await.unwrap();
+    assert_eq!(
+        db.rotating_backups().unwrap(),
+        std::slice::from_ref(&newest)
+    );
+
+    db.restore_from(&newest).await.unwrap();
+    assert_eq!(names(&db).await, ["groceries", "chores", "garden"]);
+}
@@ -0,0 +1,49 @@
+// each test crate uses only some of these helpers
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use checklist::{Db, DbOptions, Uuid};
+
+/// A fresh database in the temporary directory, removed when dropped.
+pub struct TempDb {
+    pub db: Db,
+    path: PathBuf,
+}
+
+impl TempDb {
+    pub async fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("checklist-test-{}.sqlite3", Uuid::now_v7()));
+        let db = Db::open(&path, DbOptions::unencrypted())
+            .await
+            .expect("opening database");
+        Self { db, path }
+    }
+}
+
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A fresh directory in the temporary directory, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("checklist-test-{}", Uuid::now_v7()));
+        std::fs::create_dir_all(&path).expect("creating temporary directory");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
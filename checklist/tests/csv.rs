@@ -0,0 +1,90 @@
+#![cfg(feature = "csv")]
+
+use checklist::{
+    formats::{csv, ChecklistRecord, Document, ItemRecord},
+    ChecklistId, ItemId, Uuid,
+};
+use chrono::{DateTime, Utc};
+
+fn utc(timestamp: &str) -> Option<DateTime<Utc>> {
+    Some(timestamp.parse().expect("valid timestamp"))
+}
+
+fn item(text: &str) -> ItemRecord {
+    ItemRecord {
+        item: text.to_owned(),
+        ..ItemRecord::default()
+    }
+}
+
+fn checklist(name: &str, items: Vec<ItemRecord>) -> ChecklistRecord {
+    ChecklistRecord {
+        name: name.to_owned(),
+        items,
+        ..ChecklistRecord::default()
+    }
+}
+
+#[test]
+fn awkward_text_round_trips() {
+    let document = Document {
+        checklists: vec![
+            ChecklistRecord {
+                id: Some(ChecklistId::new(1)),
+                uuid: Some(Uuid::now_v7()),
+                ..checklist(
+                    "groceries, \"weekly\"",
+                    vec![
+                        ItemRecord {
+                            id: Some(ItemId::new(1)),
+                            uuid: Some(Uuid::now_v7()),
+                            checked: true,
+                            priority: Some('A'),
+                            created_at: utc("2025-01-29T10:00:00.123Z"),
+                            completed_at: utc("2025-01-31T08:02:00Z"),
+                            due_at: utc("2025-02-01T00:00:00Z"),
+                            ..item("eggs, free range")
+                        },
+                        item("say \"cheese\"\nthen buy it"),
+                        item("crème fraîche 🥛"),
+                        item("  padded  "),
+                    ],
+                )
+            },
+            checklist("empty", Vec::new()),
+            checklist("日本語", vec![item("\"")]),
+        ],
+    };
+
+    let mut written = Vec::new();
+    csv::write(&document, &mut written).unwrap();
+    assert_eq!(csv::read(written.as_slice()).unwrap(), document);
+}
+
+#[test]
+fn rows_are_grouped_by_checklist_name() {
+    let document = csv::read(
+        "item,checked,checklist\n\
+        milk,x,groceries\n\
+        sweep,,chores\n\
+        \"eggs, brown\",yes,groceries\n\
+        mop,0,\"chores\"\n"
+            .as_bytes(),
+    )
+    .unwrap();
+
+    let checked = |text: &str, checked| ItemRecord {
+        checked,
+        ..item(text)
+    };
+    assert_eq!(
+        document.checklists,
+        [
+            checklist(
+                "groceries",
+                vec![checked("milk", true), checked("eggs, brown", true)]
+            ),
+            checklist("chores", vec![item("sweep"), item("mop")]),
+        ]
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use checklist::{formats::EditSummary, Checklist, Error, Item};
+use common::TempDb;
+
+#[tokio::test]
+async fn edited_markdown_is_applied_as_a_diff() {
+    let db = TempDb::new().await;
+    let db = &db.db;
+    let groceries = Checklist::new(db, "groceries").await.unwrap();
+    let mut items = Vec::new();
+    for name in ["milk", "eggs", "bread", "butter"] {
+        items.push(Item::new(db, groceries.id, name.to_owned()).await.unwrap());
+    }
+
+    let original = groceries.to_markdown(db).await.unwrap();
+    let lines: Vec<&str> = original.lines().collect();
+    let (milk, bread, butter) = (lines[2], lines[4], lines[5]);
+    // eggs is removed, butter moved to the top and also copied, and jam added
+    let edited = format!(
+        "# shopping\n\n{butter}\n{}\n{}\n- [ ] jam\n{butter}\n",
+        milk.replace("] milk", "] oat milk"),
+        bread.replace("[ ]", "[x]"),
+    );
+    let summary = groceries
+        .apply_markdown(db, &original, &edited)
+        .await
+        .unwrap();
+    assert_eq!(
+        summary,
+        EditSummary {
+            renamed: true,
+            added: 2,
+            removed: 1,
+            edited: 1,
+            toggled: 1,
+            moved: 1,
+        }
+    );
+
+    let shopping = Checklist::load(db, groceries.id).await.unwrap().unwrap();
+    assert_eq!(shopping.name, "shopping");
+    let edited_items = shopping.items(db).await.unwrap();
+    let names: Vec<_> = edited_items.iter().map(|item| item.item.as_str()).collect();
+    assert_eq!(names, ["butter", "oat milk", "bread", "jam", "butter"]);
+    assert_eq!(edited_items[0].uuid, items[3].uuid);
+    assert_eq!(edited_items[1].uuid, items[0].uuid);
+    assert_ne!(edited_items[4].uuid, items[3].uuid);
+    assert!(edited_items[2].is_set(db).await.unwrap());
+    assert!(Item::load(db, items[1].id).await.unwrap().is_none());
+
+    // an unchanged list changes nothing
+    let current = shopping.to_markdown(db).await.unwrap();
+    let summary = shopping
+        .apply_markdown(db, &current, &current)
+        .await
+        .unwrap();
+    assert_eq!(summary, EditSummary::default());
+}
+
+#[tokio::test]
+async fn edits_of_a_changed_checklist_conflict() {
+    let db = TempDb::new().await;
+    let db = &db.db;
+    let groceries = Checklist::new(db, "groceries").await.unwrap();
+    let milk = Item::new(db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+
+    let original = groceries.to_markdown(db).await.unwrap();
+    milk.set_checked(db, true).await.unwrap();
+    let edited = format!("{original}- [ ] eggs\n");
+    assert!(matches!(
+        groceries.apply_markdown(db, &original, &edited).await,
+        Err(Error::Conflict { .. })
+    ));
+    assert_eq!(groceries.items(db).await.unwrap().len(), 1);
+
+    // nor is anything applied from a list which is not a checklist
+    let current = groceries.to_markdown(db).await.unwrap();
+    assert!(matches!(
+        groceries.apply_markdown(db, &current, "- [ ] eggs\n").await,
+        Err(Error::InvalidDocument { .. })
+    ));
+}
@@ -0,0 +1,33 @@
+use checklist::formats::{ics, ChecklistRecord, Document, ItemRecord};
+
+fn item(text: &str, priority: char) -> ItemRecord {
+    ItemRecord {
+        item: text.to_owned(),
+        priority: Some(priority),
+        ..ItemRecord::default()
+    }
+}
+
+#[test]
+fn priorities_are_written_as_ranks_and_others_are_left_out() {
+    let document = Document {
+        checklists: vec![ChecklistRecord {
+            name: "house".to_owned(),
+            items: vec![
+                item("first", 'A'),
+                item("ninth", 'I'),
+                item("last", 'Z'),
+                item("lowercase", 'a'),
+                item("digit", '1'),
+            ],
+            ..ChecklistRecord::default()
+        }],
+    };
+
+    let ics = ics::to_string(&document);
+    let priorities = ics
+        .lines()
+        .filter_map(|line| line.strip_prefix("PRIORITY:"))
+        .collect::<Vec<_>>();
+    assert_eq!(priorities, ["1", "9", "9"], "{ics}");
+}
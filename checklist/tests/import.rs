@@ -0,0 +1,226 @@
+mod common;
+
+use checklist::{
+    formats::{ChecklistRecord, Document, ImportMode, ItemRecord},
+    Checklist, ChecklistId, Db, Error, Item, ItemId, Uuid,
+};
+use common::TempDb;
+
+fn item(text: &str) -> ItemRecord {
+    ItemRecord {
+        item: text.to_owned(),
+        ..ItemRecord::default()
+    }
+}
+
+fn checklist(name: &str, items: Vec<ItemRecord>) -> ChecklistRecord {
+    ChecklistRecord {
+        name: name.to_owned(),
+        items,
+        ..ChecklistRecord::default()
+    }
+}
+
+/// Each checklist's uuid and name, with each of its items' uuids and texts.
+async fn contents(db: &Db) -> Vec<(Uuid, String, Vec<(Uuid, String)>)> {
+    let document = Document::export(db).await.unwrap();
+    document
+        .checklists
+        .into_iter()
+        .map(|checklist| {
+            let items = checklist
+                .items
+                .into_iter()
+                .map(|item| (item.uuid.unwrap(), item.item))
+                .collect();
+            (checklist.uuid.unwrap(), checklist.name, items)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn invalid_documents_are_rejected_before_anything_is_imported() {
+    let db = TempDb::new().await;
+    let db = &db.db;
+    let groceries = Checklist::new(db, "groceries").await.unwrap();
+    Item::new(db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+    let before = contents(db).await;
+    let existing = Document::export(db).await.unwrap();
+    let uuid = Some(Uuid::now_v7());
+
+    let cases = [
+        (
+            vec![checklist("chores", Vec::new()), checklist(" ", Vec::new())],
+            ImportMode::Merge,
+            "checklists[1].name",
+        ),
+        (
+            vec![checklist("chores", vec![item("sweep"), item("\n")])],
+            ImportMode::Update,
+            "checklists[0].items[1].item",
+        ),
+        (
+            vec![checklist(
+                "chores",
+                vec![ItemRecord {
+                    priority: Some('a'),
+                    ..item("sweep")
+                }],
+            )],
+            ImportMode::Replace,
+            "checklists[0].items[0].priority",
+        ),
+        (
+            vec![
+                existing.checklists[0].clone(),
+                existing.checklists[0].clone(),
+            ],
+            ImportMode::Replace,
+            "checklists[1].id",
+        ),
+        (
+            vec![ChecklistRecord {
+                uuid,
+                ..checklist(
+                    "chores",
+                    vec![ItemRecord {
+                        uuid,
+                        ..item("sweep")
+                    }],
+                )
+            }],
+            ImportMode::Update,
+            "checklists[0].items[0].uuid",
+        ),
+        (
+            vec![
+                checklist("chores", existing.checklists[0].items.clone()),
+                checklist("errands", existing.checklists[0].items.clone()),
+            ],
+            ImportMode::Replace,
+            "checklists[1].items[0].id",
+        ),
+    ];
+    for (checklists, mode, expected) in cases {
+        let document = Document { checklists };
+        let result = document.import(db, mode).await;
+        assert!(
+            matches!(&result, Err(Error::InvalidDocument { path, .. }) if path == expected),
+            "{mode}: {result:?}"
+        );
+        assert_eq!(contents(db).await, before);
+    }
+
+    // duplicates are copies when merging, so they are not an error
+    let summary = Document {
+        checklists: vec![
+            existing.checklists[0].clone(),
+            existing.checklists[0].clone(),
+        ],
+    }
+    .import(db, ImportMode::Merge)
+    .await
+    .unwrap();
+    assert_eq!((summary.checklists, summary.items), (2, 2));
+}
+
+#[tokio::test]
+async fn each_mode_treats_existing_uuids_its_own_way() {
+    for mode in [ImportMode::Merge, ImportMode::Update, ImportMode::Replace] {
+        let db = TempDb::new().await;
+        let db = &db.db;
+        let groceries = Checklist::new(db, "groceries").await.unwrap();
+        for name in ["milk", "eggs"] {
+            Item::new(db, groceries.id, name.to_owned()).await.unwrap();
+        }
+        Checklist::new(db, "chores").await.unwrap();
+        let before = contents(db).await;
+
+        // an edited export of the first checklist, with one more item
+        let mut document = Document::export(db).await.unwrap();
+        document.checklists.truncate(1);
+        let edited = &mut document.checklists[0];
+        edited.name = "shopping".to_owned();
+        edited.items[0].item = "oat milk".to_owned();
+        edited.items.push(item("bread"));
+        document.import(db, mode).await.unwrap();
+
+        let after = contents(db).await;
+        let (uuid, _, items) = &before[0];
+        let (milk, eggs) = (items[0].0, items[1].0);
+        let names = |checklist: &(Uuid, String, Vec<(Uuid, String)>)| {
+            let items: Vec<_> = checklist.2.iter().map(|(_, item)| item.clone()).collect();
+            (checklist.1.clone(), items)
+        };
+        let shopping = (
+            "shopping".to_owned(),
+            vec!["oat milk".to_owned(), "eggs".to_owned(), "bread".to_owned()],
+        );
+        match mode {
+            ImportMode::Merge => {
+                // the copy gets fresh uuids, and the original is untouched
+                assert_eq!(after[..2], before[..]);
+                assert_eq!(names(&after[2]), shopping);
+                assert_ne!(after[2].0, *uuid);
+                assert!(after[2]
+                    .2
+                    .iter()
+                    .all(|(item, _)| ![milk, eggs].contains(item)));
+            }
+            ImportMode::Update => {
+                assert_eq!(after.len(), 2);
+                assert_eq!(after[0].0, *uuid);
+                assert_eq!(names(&after[0]), shopping);
+                assert_eq!((after[0].2[0].0, after[0].2[1].0), (milk, eggs));
+                assert_eq!(after[1], before[1]);
+            }
+            ImportMode::Replace => {
+                assert_eq!(after.len(), 1);
+                assert_eq!(after[0].0, *uuid);
+                assert_eq!(names(&after[0]), shopping);
+                assert_eq!((after[0].2[0].0, after[0].2[1].0), (milk, eggs));
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn records_which_keep_their_ids_do_not_overwrite_those_which_do_not() {
+    for mode in [ImportMode::Replace, ImportMode::Update] {
+        let db = TempDb::new().await;
+        let db = &db.db;
+        let document = Document {
+            checklists: vec![
+                checklist("a", vec![item("milk")]),
+                ChecklistRecord {
+                    id: Some(ChecklistId::new(1)),
+                    ..checklist(
+                        "b",
+                        vec![ItemRecord {
+                            id: Some(ItemId::new(1)),
+                            ..item("eggs")
+                        }],
+                    )
+                },
+            ],
+        };
+        let summary = document.import(db, mode).await.unwrap();
+        assert_eq!((summary.checklists, summary.items), (2, 2));
+
+        let imported = Document::export(db).await.unwrap();
+        let names: Vec<_> = imported
+            .checklists
+            .iter()
+            .map(|checklist| (checklist.id.unwrap(), checklist.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [(ChecklistId::new(1), "b"), (ChecklistId::new(2), "a")],
+            "{mode}"
+        );
+        assert_eq!(imported.checklists[0].items[0].id, Some(ItemId::new(1)));
+        assert_eq!(imported.checklists[1].items[0].item, "milk");
+    }
+}
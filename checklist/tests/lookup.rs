@@ -0,0 +1,96 @@
+mod common;
+
+use checklist::{Checklist, Error, Item};
+use common::TempDb;
+
+#[tokio::test]
+async fn names_are_found_by_the_closest_match() {
+    let db = TempDb::new().await;
+    let db = &db.db;
+    let groceries = Checklist::new(db, "groceries").await.unwrap();
+    let garden = Checklist::new(db, "Garden").await.unwrap();
+    let gear = Checklist::new(db, "gear").await.unwrap();
+
+    let find = |name| Checklist::find(db, name);
+    assert_eq!(find("garden").await.unwrap().id, garden.id);
+    assert_eq!(find("groc").await.unwrap().id, groceries.id);
+    assert_eq!(find("ocer").await.unwrap().id, groceries.id);
+    assert_eq!(find("grcs").await.unwrap().id, groceries.id);
+    // an exact name wins over names which it begins
+    Checklist::new(db, "gear for camping").await.unwrap();
+    assert_eq!(find("gear").await.unwrap().id, gear.id);
+
+    match find("g").await {
+        Err(Error::Ambiguous { query, candidates }) => {
+            assert_eq!(query, "g");
+            assert_eq!(candidates.len(), 4);
+            assert!(candidates.contains(&format!("groceries ({})", groceries.id)));
+        }
+        _ => panic!("expected an ambiguous name"),
+    }
+    assert!(matches!(
+        find("shopping").await,
+        Err(Error::MissingChecklist)
+    ));
+}
+
+#[tokio::test]
+async fn items_are_found_in_one_checklist_or_any() {
+    let db = TempDb::new().await;
+    let db = &db.db;
+    let groceries = Checklist::new(db, "groceries").await.unwrap();
+    let chores = Checklist::new(db, "chores").await.unwrap();
+    let milk = Item::new(db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+    Item::new(db, chores.id, "buy milk".to_owned())
+        .await
+        .unwrap();
+    Item::new(db, chores.id, "sweep".to_owned()).await.unwrap();
+
+    assert_eq!(Item::find(db, None, "milk").await.unwrap().id, milk.id);
+    assert_eq!(
+        Item::find(db, Some(chores.id), "milk").await.unwrap().item,
+        "buy milk"
+    );
+    assert!(matches!(
+        Item::find(db, Some(groceries.id), "sweep").await,
+        Err(Error::MissingItem)
+    ));
+    assert!(matches!(
+        Item::find(db, None, "i").await,
+        Err(Error::Ambiguous { .. })
+    ));
+}
+
+#[tokio::test]
+async fn last_changed_follows_changes_to_items() {
+    let db = TempDb::new().await;
+    let db = &db.db;
+    assert!(Checklist::last_changed(db).await.unwrap().is_none());
+
+    let groceries = Checklist::new(db, "groceries").await.unwrap();
+    let chores = Checklist::new(db, "chores").await.unwrap();
+    let milk = Item::new(db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+    let sweep = Item::new(db, chores.id, "sweep".to_owned()).await.unwrap();
+    assert_eq!(
+        Checklist::last_changed(db).await.unwrap().unwrap().id,
+        chores.id
+    );
+    assert_eq!(
+        Item::last_changed(db, None).await.unwrap().unwrap().id,
+        sweep.id
+    );
+
+    milk.set_checked(db, true).await.unwrap();
+    let last = Checklist::last_changed(db).await.unwrap().unwrap();
+    assert_eq!(last.id, groceries.id);
+    assert_eq!(
+        Item::last_changed(db, None).await.unwrap().unwrap().id,
+        milk.id
+    );
+    let last = Item::last_changed(db, Some(chores.id)).await.unwrap();
+    assert_eq!(last.unwrap().id, sweep.id);
+}
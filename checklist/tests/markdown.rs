@@ -0,0 +1,70 @@
+use checklist::{
+    formats::{markdown, ChecklistRecord, ItemRecord},
+    Uuid,
+};
+use chrono::{DateTime, Utc};
+
+fn utc(timestamp: &str) -> Option<DateTime<Utc>> {
+    Some(timestamp.parse().expect("valid timestamp"))
+}
+
+#[test]
+fn item_attributes_round_trip() {
+    let checklist = ChecklistRecord {
+        uuid: Some(Uuid::now_v7()),
+        name: "house\nand garden".to_owned(),
+        items: vec![
+            ItemRecord {
+                uuid: Some(Uuid::now_v7()),
+                item: "call the plumber".to_owned(),
+                priority: Some('A'),
+                created_at: utc("2025-01-30T09:15:00Z"),
+                due_at: utc("2025-02-01T00:00:00Z"),
+                ..ItemRecord::default()
+            },
+            ItemRecord {
+                item: "buy a new washer".to_owned(),
+                checked: true,
+                created_at: utc("2025-01-29T08:00:00.123456Z"),
+                completed_at: utc("2025-01-31T17:00:00Z"),
+                ..ItemRecord::default()
+            },
+            ItemRecord {
+                item: r"comment <!-- uuid:not-a-uuid --> \ and all".to_owned(),
+                ..ItemRecord::default()
+            },
+        ],
+        ..ChecklistRecord::default()
+    };
+
+    let written = markdown::to_string(&checklist);
+    assert!(
+        written.contains("] call the plumber <!-- uuid:"),
+        "{written}"
+    );
+    assert!(
+        written.contains(" priority:A created:2025-01-30T09:15:00Z due:2025-02-01T00:00:00Z -->"),
+        "{written}"
+    );
+    assert_eq!(markdown::from_str(&written).unwrap(), checklist);
+}
+
+#[test]
+fn comments_which_are_not_attributes_are_text() {
+    let checklist = markdown::from_str(
+        "# chores\n- [ ] sweep <!-- the porch -->\n- [ ] mop <!-- priority:AA -->\n",
+    )
+    .unwrap();
+    let items: Vec<_> = checklist
+        .items
+        .iter()
+        .map(|item| (item.item.as_str(), item.priority))
+        .collect();
+    assert_eq!(
+        items,
+        [
+            ("sweep <!-- the porch -->", None),
+            ("mop <!-- priority:AA -->", None)
+        ]
+    );
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use checklist::{formats::Document, Checklist, Db, Item};
+use common::TempDb;
+
+async fn item_names(db: &Db, checklist: &Checklist) -> Vec<String> {
+    let items = checklist.items(db).await.expect("loading items");
+    items.into_iter().map(|item| item.item).collect()
+}
+
+#[tokio::test]
+async fn moved_items_keep_their_order_through_export_and_sync() {
+    let a = TempDb::new().await;
+    let b = TempDb::new().await;
+
+    let groceries = Checklist::new(&a.db, "groceries").await.unwrap();
+    let mut items = Vec::new();
+    for name in ["milk", "eggs", "bread"] {
+        items.push(
+            Item::new(&a.db, groceries.id, name.to_owned())
+                .await
+                .unwrap(),
+        );
+    }
+    items[2].move_to(&a.db, 0).await.unwrap();
+    items[0].move_to(&a.db, 10).await.unwrap();
+
+    let expected = ["bread", "eggs", "milk"];
+    assert_eq!(item_names(&a.db, &groceries).await, expected);
+    let document = Document::export(&a.db).await.unwrap();
+    let exported: Vec<_> = document.checklists[0]
+        .items
+        .iter()
+        .map(|item| item.item.as_str())
+        .collect();
+    assert_eq!(exported, expected);
+
+    a.db.sync_with(&b.db).await.unwrap();
+    let b_groceries = Checklist::load_by_uuid(&b.db, groceries.uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(item_names(&b.db, &b_groceries).await, expected);
+
+    // a move on the other side is synced back
+    let b_eggs = Item::load_by_uuid(&b.db, items[1].uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    b_eggs.move_to(&b.db, 0).await.unwrap();
+    b.db.sync_with(&a.db).await.unwrap();
+    assert_eq!(
+        item_names(&a.db, &groceries).await,
+        ["eggs", "bread", "milk"]
+    );
+}
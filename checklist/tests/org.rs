@@ -0,0 +1,117 @@
+use checklist::{
+    formats::{org, Document},
+    Uuid,
+};
+use chrono::{DateTime, Utc};
+
+fn utc(timestamp: &str) -> Option<DateTime<Utc>> {
+    Some(timestamp.parse().expect("valid timestamp"))
+}
+
+#[test]
+fn canonical_fixture_round_trips_unchanged() {
+    let fixture = include_str!("fixtures/canonical.org");
+    let document = org::from_str(fixture).expect("parsing fixture");
+    assert_eq!(org::to_string(&document), fixture);
+}
+
+#[test]
+fn mixed_fixture_round_trips() {
+    let fixture = include_str!("fixtures/mixed.org");
+    let document = org::from_str(fixture).expect("parsing fixture");
+    let reparsed = org::from_str(&org::to_string(&document)).expect("parsing export");
+    assert_eq!(reparsed, document);
+}
+
+#[test]
+fn mixed_fixture_maps_headlines_checkboxes_and_planning() {
+    let document: Document =
+        org::from_str(include_str!("fixtures/mixed.org")).expect("parsing fixture");
+
+    let names = document
+        .checklists
+        .iter()
+        .map(|checklist| checklist.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["inbox", "Packing list", "Errands"]);
+
+    let packing = &document.checklists[1].items;
+    let packing = packing
+        .iter()
+        .map(|item| (item.item.as_str(), item.checked))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        packing,
+        [
+            ("passport", false),
+            ("tickets", true),
+            ("chargers", false),
+            ("sunscreen", true),
+        ]
+    );
+
+    let errands = &document.checklists[2].items;
+    assert_eq!(errands[0].item, "post office");
+    assert!(!errands[0].checked);
+    assert_eq!(errands[0].due_at, utc("2025-03-01T10:00:00Z"));
+
+    assert_eq!(errands[1].item, "bank");
+    assert!(errands[1].checked);
+    assert_eq!(errands[1].priority, Some('B'));
+    assert_eq!(errands[1].completed_at, utc("2025-02-28T16:45:00Z"));
+    // a deadline takes precedence over a scheduled date
+    assert_eq!(errands[1].due_at, utc("2025-03-03T00:00:00Z"));
+    assert_eq!(errands[1].created_at, utc("2025-02-20T11:00:00Z"));
+}
+
+#[test]
+fn canonical_fixture_maps_ids_to_uuids() {
+    let document = org::from_str(include_str!("fixtures/canonical.org")).expect("parsing fixture");
+
+    let house = &document.checklists[0];
+    assert_eq!(
+        house.uuid,
+        Some(Uuid::from_u128(0x0194b1c2_5e40_7a31_9c2e_3f1d2a4b5c6d))
+    );
+    assert_eq!(
+        house.items[0].uuid,
+        Some(Uuid::from_u128(0x0194b1c2_5e41_7b02_8d3f_4a5b6c7d8e9f))
+    );
+    assert_eq!(house.items[1].uuid, None);
+    assert_eq!(document.checklists[1].uuid, None);
+}
+
+#[test]
+fn escaped_fixture_round_trips_unchanged() {
+    let fixture = include_str!("fixtures/escaped.org");
+    let document = org::from_str(fixture).expect("parsing fixture");
+    assert_eq!(org::to_string(&document), fixture);
+}
+
+#[test]
+fn escaped_fixture_keeps_keywords_and_priorities_in_text() {
+    let document = org::from_str(include_str!("fixtures/escaped.org")).expect("parsing fixture");
+
+    let names = document
+        .checklists
+        .iter()
+        .map(|checklist| checklist.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["TODO", "DONE and dusted", "[#A] team"]);
+
+    let items = document
+        .checklists
+        .iter()
+        .flat_map(|checklist| &checklist.items)
+        .map(|item| (item.item.as_str(), item.priority))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        items,
+        [
+            ("[#A] is not a priority", None),
+            ("[#C] follows a priority", Some('B')),
+            ("\\server\\share", None),
+            ("TODO list", None),
+        ]
+    );
+}
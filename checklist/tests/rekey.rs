@@ -0,0 +1,134 @@
+mod common;
+
+use checklist::{
+    kdf::KdfHeader, passphrase_encryption_config, Checklist, Db, DbOptions, EncryptionConfig,
+    Error, KdfCost,
+};
+use common::TempDir;
+
+/// A key derived cheaply from `passphrase`, so that the tests run quickly.
+fn key(passphrase: &str) -> EncryptionConfig {
+    let cost = KdfCost {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+    KdfHeader::generate(cost)
+        .unwrap()
+        .encryption_config(passphrase.as_bytes())
+        .unwrap()
+}
+
+async fn names(db: &Db) -> Vec<String> {
+    let checklists = Checklist::all(db).await.unwrap();
+    checklists
+        .into_iter()
+        .map(|checklist| checklist.name)
+        .collect()
+}
+
+#[tokio::test]
+async fn rekeyed_databases_open_only_with_the_new_key() {
+    let dir = TempDir::new();
+    let path = dir.path().join("db.sqlite3");
+    let (old, new) = (key("old"), key("new"));
+
+    let db = Db::new(&path, old.clone()).await.unwrap();
+    Checklist::new(&db, "groceries").await.unwrap();
+    Checklist::new(&db, "chores").await.unwrap();
+    db.rekey(new.clone()).await.unwrap();
+    // the open database carries on with the new key
+    Checklist::new(&db, "garden").await.unwrap();
+    drop(db);
+
+    assert!(Db::new(&path, old).await.is_err());
+    let db = Db::new(&path, new).await.unwrap();
+    assert_eq!(names(&db).await, ["groceries", "chores", "garden"]);
+}
+
+#[tokio::test]
+async fn a_failed_rekey_leaves_the_original_readable() {
+    let dir = TempDir::new();
+    let path = dir.path().join("db.sqlite3");
+    let old = key("old");
+
+    let db = Db::new(&path, old.clone()).await.unwrap();
+    Checklist::new(&db, "groceries").await.unwrap();
+    // a directory where the copy would be written makes the rekey fail
+    std::fs::create_dir_all(dir.path().join("db.sqlite3.tmp/occupied")).unwrap();
+    assert!(db.rekey(key("new")).await.is_err());
+
+    Checklist::new(&db, "chores").await.unwrap();
+    drop(db);
+    let db = Db::open(&path, DbOptions::encrypted(old)).await.unwrap();
+    assert_eq!(names(&db).await, ["groceries", "chores"]);
+}
+
+#[tokio::test]
+async fn rekeying_replaces_or_removes_the_key_header() {
+    let dir = TempDir::new();
+    let path = dir.path().join("db.sqlite3");
+    let cost = KdfCost {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    let config = passphrase_encryption_config(&path, b"old", cost).unwrap();
+    let old_header = KdfHeader::load(&path).unwrap().unwrap();
+    let db = Db::new(&path, config).await.unwrap();
+    Checklist::new(&db, "groceries").await.unwrap();
+
+    let new_cost = KdfCost {
+        iterations: 2,
+        ..cost
+    };
+    db.rekey_with_passphrase(b"new", new_cost).await.unwrap();
+    let new_header = KdfHeader::load(&path).unwrap().unwrap();
+    assert_ne!(new_header.salt, old_header.salt);
+    assert_eq!(new_header.cost, new_cost);
+    assert!(!dir.path().join("db.sqlite3.kdf.tmp").exists());
+    drop(db);
+    let config = passphrase_encryption_config(&path, b"new", cost).unwrap();
+    let db = Db::new(&path, config).await.unwrap();
+    assert_eq!(names(&db).await, ["groceries"]);
+
+    // a raw key is not derived from the header, so the header goes
+    db.rekey(key("raw")).await.unwrap();
+    assert_eq!(KdfHeader::load(&path).unwrap(), None);
+    drop(db);
+
+    // nor is a header made up for the database, since no key derived from it could open it
+    assert!(matches!(
+        passphrase_encryption_config(&path, b"new", cost),
+        Err(Error::MissingKdfHeader(_))
+    ));
+    assert_eq!(KdfHeader::load(&path).unwrap(), None);
+}
+
+#[test]
+fn costly_key_headers_are_rejected() {
+    let dir = TempDir::new();
+    let path = dir.path().join("db.sqlite3");
+    let header = KdfHeader::generate(KdfCost::default()).unwrap();
+    header.store(&path).unwrap();
+    let stored = std::fs::read_to_string(KdfHeader::path_for(&path)).unwrap();
+    for (field, cost) in [("memory_kib", 19456), ("iterations", 2), ("parallelism", 1)] {
+        let costly = stored.replace(
+            &format!("{field}={cost}\n"),
+            &format!("{field}={}\n", u32::MAX),
+        );
+        assert_ne!(costly, stored);
+        std::fs::write(KdfHeader::path_for(&path), costly).unwrap();
+        assert!(matches!(
+            KdfHeader::load(&path),
+            Err(Error::InvalidKdfHeader(_))
+        ));
+    }
+
+    let costly = KdfCost {
+        memory_kib: KdfCost::MAX.memory_kib + 1,
+        ..KdfCost::default()
+    };
+    assert!(KdfHeader::generate(costly).is_err());
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use checklist::{Checklist, Db, DbOptions};
+use common::TempDir;
+
+#[test]
+fn concurrent_openers_migrate_a_new_database_once() {
+    let dir = TempDir::new();
+    let path = dir.path().join("db.sqlite3");
+
+    let openers = (0..4)
+        .map(|_| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .build()
+                    .expect("building runtime");
+                runtime.block_on(Db::open(&path, DbOptions::unencrypted()))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let dbs = openers
+        .into_iter()
+        .map(|opener| {
+            opener
+                .join()
+                .expect("opener panicked")
+                .expect("opening database")
+        })
+        .collect::<Vec<_>>();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("building runtime");
+    runtime
+        .block_on(Checklist::new(&dbs[0], "groceries"))
+        .expect("writing to migrated database");
+}
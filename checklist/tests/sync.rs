@@ -0,0 +1,221 @@
+mod common;
+
+use checklist::{
+    sync::{Changeset, Conflict, ConflictKind, FieldChange, RowChange, Stamp, Table, Value},
+    Checklist, Db, Error, Item, Uuid,
+};
+use common::TempDb;
+
+async fn item_names(db: &Db, checklist: Uuid) -> Vec<(String, bool)> {
+    let checklist = Checklist::load_by_uuid(db, checklist)
+        .await
+        .expect("loading checklist")
+        .expect("checklist exists");
+    let mut names = Vec::new();
+    for item in checklist.items(db).await.expect("loading items") {
+        let checked = item.is_set(db).await.expect("loading item status");
+        names.push((item.item, checked));
+    }
+    names
+}
+
+#[tokio::test]
+async fn concurrent_edits_converge_and_resync_is_a_no_op() {
+    let a = TempDb::new().await;
+    let b = TempDb::new().await;
+
+    let groceries = Checklist::new(&a.db, "groceries").await.unwrap();
+    let milk = Item::new(&a.db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+    let eggs = Item::new(&a.db, groceries.id, "eggs".to_owned())
+        .await
+        .unwrap();
+    a.db.sync_with(&b.db).await.unwrap();
+
+    // edit both sides independently
+    Item::delete(&a.db, eggs.id).await.unwrap();
+    let b_milk = Item::load_by_uuid(&b.db, milk.uuid).await.unwrap().unwrap();
+    b_milk.set_checked(&b.db, true).await.unwrap();
+    let b_groceries = Checklist::load_by_uuid(&b.db, groceries.uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    Item::new(&b.db, b_groceries.id, "bread".to_owned())
+        .await
+        .unwrap();
+
+    let report = b.db.sync_with(&a.db).await.unwrap();
+    assert_eq!(report.pulled.deleted, 1);
+    assert_eq!(report.pushed.inserted, 1);
+    assert_eq!(report.pushed.updated, 1);
+
+    let expected = [("milk".to_owned(), true), ("bread".to_owned(), false)];
+    assert_eq!(item_names(&a.db, groceries.uuid).await, expected);
+    assert_eq!(item_names(&b.db, groceries.uuid).await, expected);
+    assert_eq!(
+        a.db.changes_since(0).await.unwrap().rows,
+        b.db.changes_since(0).await.unwrap().rows
+    );
+
+    let report = a.db.sync_with(&b.db).await.unwrap();
+    assert_eq!(report.pulled.changed() + report.pushed.changed(), 0);
+}
+
+#[tokio::test]
+async fn deleting_a_checklist_removes_items_added_elsewhere() {
+    let a = TempDb::new().await;
+    let b = TempDb::new().await;
+
+    let work = Checklist::new(&a.db, "work").await.unwrap();
+    a.db.sync_with(&b.db).await.unwrap();
+
+    Checklist::delete(&a.db, work.id).await.unwrap();
+    let b_work = Checklist::load_by_uuid(&b.db, work.uuid)
+        .await
+        .unwrap()
+        .unwrap();
+    Item::new(&b.db, b_work.id, "report".to_owned())
+        .await
+        .unwrap();
+
+    a.db.sync_with(&b.db).await.unwrap();
+    a.db.sync_with(&b.db).await.unwrap();
+
+    for db in [&a.db, &b.db] {
+        assert!(Checklist::all(db).await.unwrap().is_empty());
+    }
+    assert_eq!(
+        a.db.changes_since(0).await.unwrap().rows,
+        b.db.changes_since(0).await.unwrap().rows
+    );
+}
+
+#[tokio::test]
+async fn encrypted_changesets_carry_incremental_changes_and_report_conflicts() {
+    let a = TempDb::new().await;
+    let b = TempDb::new().await;
+    let key = Some(&b"correct horse"[..]);
+
+    let groceries = Checklist::new(&a.db, "groceries").await.unwrap();
+    let milk = Item::new(&a.db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+    let bytes =
+        a.db.changes_for(b.db.site().await.unwrap())
+            .await
+            .unwrap()
+            .to_bytes(key)
+            .unwrap();
+    assert!(Changeset::from_bytes(&bytes, None).is_err());
+    assert!(Changeset::from_bytes(&bytes, Some(b"wrong")).is_err());
+    // the header is checked before deriving a key, which with these costs would never finish
+    for offset in [6, 10, 14] {
+        let mut costly = bytes.clone();
+        costly[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            Changeset::from_bytes(&costly, key),
+            Err(Error::InvalidChangeset(_))
+        ));
+    }
+    let changeset = Changeset::from_bytes(&bytes, key).unwrap();
+    assert_eq!(b.db.apply_changeset(&changeset).await.unwrap().inserted, 2);
+
+    let version = a.db.sync_version().await.unwrap();
+    assert!(a.db.changes_since(version).await.unwrap().rows.is_empty());
+
+    // check the item on one side, and check then uncheck it on the other
+    milk.set_checked(&a.db, true).await.unwrap();
+    let b_milk = Item::load_by_uuid(&b.db, milk.uuid).await.unwrap().unwrap();
+    b_milk.set_checked(&b.db, true).await.unwrap();
+    b_milk.set_checked(&b.db, false).await.unwrap();
+    let changes = a.db.changes_since(version).await.unwrap();
+    assert_eq!(changes.rows.len(), 1);
+
+    let bytes =
+        b.db.changes_for(a.db.site().await.unwrap())
+            .await
+            .unwrap()
+            .to_bytes(key)
+            .unwrap();
+    let summary =
+        a.db.apply_changeset(&Changeset::from_bytes(&bytes, key).unwrap())
+            .await
+            .unwrap();
+    assert_eq!(summary.updated, 1);
+    assert_eq!(
+        summary.conflicts[0],
+        Conflict {
+            table: Table::Items,
+            uuid: milk.uuid,
+            kind: ConflictKind::Field {
+                field: "checked".to_owned(),
+                local: Value::Integer(1),
+                incoming: Value::Integer(0),
+            },
+            accepted: true,
+        }
+    );
+    // checking also set the completion time
+    assert!(matches!(
+        &summary.conflicts[1].kind,
+        ConflictKind::Field { field, incoming: Value::Null, .. } if field == "completed_at"
+    ));
+    assert!(!milk.is_set(&a.db).await.unwrap());
+
+    let peers = a.db.peers().await.unwrap();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].site, b.db.site().await.unwrap());
+    assert_eq!(peers[0].acknowledged, changeset.version);
+}
+
+#[tokio::test]
+async fn rows_which_cannot_be_applied_whole_are_left_alone() {
+    let a = TempDb::new().await;
+    let b = TempDb::new().await;
+
+    let groceries = Checklist::new(&a.db, "groceries").await.unwrap();
+    let milk = Item::new(&a.db, groceries.id, "milk".to_owned())
+        .await
+        .unwrap();
+    a.db.sync_with(&b.db).await.unwrap();
+
+    // rename the item and move it to a checklist the other side has not received
+    let stamp = Stamp {
+        clock: a.db.sync_version().await.unwrap() + 1,
+        site: a.db.site().await.unwrap(),
+    };
+    let moved = Changeset {
+        site: stamp.site,
+        rows: vec![RowChange {
+            table: Table::Items,
+            uuid: milk.uuid,
+            fields: vec![
+                FieldChange {
+                    field: "item".to_owned(),
+                    value: Value::Text("oat milk".to_owned()),
+                    stamp,
+                },
+                FieldChange {
+                    field: "checklist".to_owned(),
+                    value: Value::Text(Uuid::from_u128(1).to_string()),
+                    stamp,
+                },
+            ],
+            deleted: None,
+        }],
+        ..Changeset::default()
+    };
+    let summary = b.db.apply_changeset(&moved).await.unwrap();
+    assert_eq!((summary.skipped, summary.updated), (1, 0));
+    assert_eq!(
+        item_names(&b.db, groceries.uuid).await,
+        [("milk".to_owned(), false)]
+    );
+
+    // nor was the renaming stamped, so nothing diverges from the other side
+    assert_eq!(
+        a.db.changes_since(0).await.unwrap().rows,
+        b.db.changes_since(0).await.unwrap().rows
+    );
+}
@@ -0,0 +1,84 @@
+use checklist::formats::{todotxt, ChecklistRecord, Document, ItemRecord};
+use chrono::{DateTime, Utc};
+
+fn utc(timestamp: &str) -> Option<DateTime<Utc>> {
+    Some(timestamp.parse().expect("valid timestamp"))
+}
+
+fn item(text: &str) -> ItemRecord {
+    ItemRecord {
+        item: text.to_owned(),
+        ..ItemRecord::default()
+    }
+}
+
+fn checklist(name: &str, items: Vec<ItemRecord>) -> ChecklistRecord {
+    ChecklistRecord {
+        name: name.to_owned(),
+        items,
+        ..ChecklistRecord::default()
+    }
+}
+
+#[test]
+fn ambiguous_text_and_names_round_trip() {
+    let document = Document {
+        checklists: vec![
+            checklist(
+                "Project Name",
+                vec![
+                    item("x marks the spot"),
+                    item("(A) is not a priority"),
+                    item("2024-01-01 is not a date"),
+                    item(r"\ leads with a backslash"),
+                    ItemRecord {
+                        checked: true,
+                        completed_at: utc("2025-01-31T00:00:00Z"),
+                        ..item("2024-01-01 is still not a date")
+                    },
+                    ItemRecord {
+                        priority: Some('B'),
+                        created_at: utc("2025-01-29T00:00:00Z"),
+                        ..item("x after a priority")
+                    },
+                ],
+            ),
+            checklist("snake_case 100%", vec![item("milk")]),
+            checklist("tabs\tand émigrés", vec![item("eggs")]),
+            checklist("empty", Vec::new()),
+        ],
+    };
+
+    let todo = todotxt::to_string(&document);
+    assert!(todo.contains(" +Project_Name"), "{todo}");
+    assert!(todo.contains("+empty\n"), "{todo}");
+    assert_eq!(todotxt::from_str(&todo).expect("parsing export"), document);
+}
+
+#[test]
+fn creation_dates_of_tasks_completed_at_an_unknown_time_round_trip() {
+    let document = Document {
+        checklists: vec![checklist(
+            "chores",
+            vec![ItemRecord {
+                checked: true,
+                created_at: utc("2025-01-29T00:00:00Z"),
+                ..item("water the plants")
+            }],
+        )],
+    };
+
+    let todo = todotxt::to_string(&document);
+    assert_eq!(todo, "x water the plants +chores created:2025-01-29\n");
+    assert_eq!(todotxt::from_str(&todo).expect("parsing export"), document);
+}
+
+#[test]
+fn lines_without_escapes_are_read_as_todo_txt() {
+    let document = todotxt::from_str("x 2025-01-31 buy milk +my_groceries\n+errands\n").unwrap();
+    assert_eq!(document.checklists[0].name, "my groceries");
+    assert!(document.checklists[0].items[0].checked);
+    assert_eq!(document.checklists[1], checklist("errands", Vec::new()));
+
+    assert!(todotxt::from_str("x +errands").is_err());
+}
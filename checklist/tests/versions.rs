@@ -0,0 +1,50 @@
+mod common;
+
+use checklist::{Checklist, Error, Item};
+use common::TempDb;
+
+#[tokio::test]
+async fn stale_versions_are_rejected() {
+    let temp = TempDb::new().await;
+    let db = &temp.db;
+
+    let checklist = Checklist::new(db, "groceries").await.unwrap();
+    let item = Item::new(db, checklist.id, "milk".to_owned())
+        .await
+        .unwrap();
+    assert_eq!(item.version, 1);
+
+    // another process checks the item with the version it loaded
+    let other = Item::load(db, item.id).await.unwrap().unwrap();
+    let version = other
+        .set_checked_if_version(db, true, other.version)
+        .await
+        .unwrap();
+    assert_eq!(version, 2);
+
+    assert!(matches!(
+        item.rename_if_version(db, "oat milk", item.version).await,
+        Err(Error::Conflict { current: 2 })
+    ));
+    assert!(matches!(
+        Item::delete_if_version(db, item.id, item.version).await,
+        Err(Error::Conflict { current: 2 })
+    ));
+
+    // unconditional changes also advance the version
+    item.rename(db, "oat milk").await.unwrap();
+    let item = Item::load(db, item.id).await.unwrap().unwrap();
+    assert_eq!((item.item.as_str(), item.version), ("oat milk", 3));
+
+    let version = checklist
+        .rename_if_version(db, "shopping", checklist.version)
+        .await
+        .unwrap();
+    Checklist::delete_if_version(db, checklist.id, version)
+        .await
+        .unwrap();
+    assert!(matches!(
+        Item::delete_if_version(db, item.id, item.version).await,
+        Err(Error::MissingItem)
+    ));
+}
@@ -0,0 +1,28 @@
+//! Noticing changes to the database, whichever process makes them.
+
+use std::{sync::Arc, time::Duration};
+
+use checklist::Db;
+use tokio::sync::watch;
+
+/// How often to check the database for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Watch the database's version, which advances with every change made by this or any other process.
+///
+/// The database is polled until every receiver has been dropped.
+pub(crate) async fn watch(db: Arc<Db>) -> checklist::Result<watch::Receiver<u64>> {
+    let (tx, rx) = watch::channel(db.sync_version().await?);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        while !tx.is_closed() {
+            interval.tick().await;
+            // a failed poll is retried on the next tick
+            if let Ok(version) = db.sync_version().await {
+                tx.send_if_modified(|current| std::mem::replace(current, version) != version);
+            }
+        }
+    });
+    Ok(rx)
+}
@@ -1,14 +1,50 @@
-use std::{os::unix::ffi::OsStrExt, path::PathBuf};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use anyhow::{Context, Result};
-use checklist::{ChecklistId, ItemId};
-use clap::{Args, Parser, Subcommand};
+use anyhow::{bail, Context, Result};
+use checklist::{
+    formats::ImportMode,
+    key_provider::{FileKeyProvider, PromptKeyProvider},
+    ChecklistId, Db, DbOptions, EncryptionConfig, EncryptionMode, ItemId, KdfCost, KeyProvider,
+    Uuid,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Deserializer};
+use zeroize::Zeroizing;
+
+use crate::{
+    config::Profile,
+    key_source::{self, KeySource, LiteralKey, NewPassphrase},
+};
 
 #[derive(Debug, Parser)]
+#[command(after_long_help = EXIT_STATUS)]
 pub struct Cli {
     #[command(subcommand)]
     pub noun: Noun,
 
+    /// How to print checklists, items and the outcomes of commands
+    ///
+    /// "text" is for people, and colored when printed to a terminal. The others are for scripts:
+    /// checklists are objects with `id`, `uuid`, `name` and `version`, and items are objects with
+    /// `id`, `uuid`, `checklist`, `item`, `checked` and `version`. "json" prints one value, which
+    /// is an array for commands which show many records; "jsonl" prints one object per line; "csv"
+    /// and "tsv" print a header row and then one row per record. Commands which create, toggle or
+    /// remove a record print it.
+    ///
+    /// Default: text
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Profile in the configuration file from which to take defaults for these options
+    ///
+    /// Default: the `default-profile` of the configuration file, if any
+    #[arg(long, env = "CHECKLIST_PROFILE")]
+    pub profile: Option<String>,
+
     /// Path to the database
     ///
     /// Default: "$XDG_DATA_HOME" if set or "$HOME/.local/share", then "checklist/db.sqlite3"
@@ -23,12 +59,113 @@ pub struct Cli {
 
     /// Encryption key for data at rest
     ///
-    /// Default: "$USER@$NAME"
-    #[arg(short, long, conflicts_with = "encryption_key_file")]
+    /// Other processes can read the command line, so prefer `--key-source` or `--passphrase`.
+    #[arg(short, long, conflicts_with_all = ["encryption_key_file", "passphrase"])]
     encryption_key: Option<String>,
+
+    /// Prompt for a passphrase from which to derive the encryption key for data at rest
+    ///
+    /// The key is derived with Argon2id, using a random salt stored alongside the database. To read
+    /// the passphrase from a file, variable or command instead, use `--key-source` with
+    /// `--derive-key`.
+    #[arg(short = 'P', long, conflicts_with = "encryption_key_file")]
+    passphrase: bool,
+
+    /// Where to obtain the encryption key for data at rest
+    ///
+    /// One of `file:<path>`, `env:<var>`, `prompt`, or `command:<shell command>`. An encrypted
+    /// database needs a key from this or another key option; `insecure-default` gives the guessable
+    /// key "$USER@$NAME" which older versions used when none was given, and only opens existing
+    /// databases, so that they can be rekeyed.
+    #[arg(short = 'k', long, conflicts_with_all = ["encryption_key_file", "encryption_key", "passphrase"])]
+    key_source: Option<KeySource>,
+
+    /// Treat the output of the key source as a passphrase and derive the key from it
+    #[arg(long, requires = "key_source")]
+    derive_key: bool,
+
+    /// Cipher used to encrypt data at rest: "aes256cbc", or "none" for a plaintext database
+    ///
+    /// Plaintext databases are intended only for throwaway and test data.
+    ///
+    /// Default: aes256cbc
+    #[arg(long)]
+    cipher: Option<EncryptionMode>,
+
+    #[command(flatten)]
+    pub kdf_cost: KdfCostArgs,
+
+    /// The profile's checklist, for item commands which do not name one
+    #[arg(skip)]
+    checklist: Option<Ref<ChecklistId>>,
+}
+
+// Cost parameters for passphrase key derivation. These only apply when the database's key header is
+// first created. This is not a doc comment, since clap would use it as the help text of `Cli`.
+#[derive(Debug, Args)]
+pub struct KdfCostArgs {
+    /// Argon2id memory cost in KiB
+    #[arg(long)]
+    kdf_memory_kib: Option<u32>,
+
+    /// Argon2id iteration count
+    #[arg(long)]
+    kdf_iterations: Option<u32>,
+
+    /// Argon2id degree of parallelism
+    #[arg(long)]
+    kdf_parallelism: Option<u32>,
+}
+
+impl KdfCostArgs {
+    pub(crate) fn cost(&self) -> KdfCost {
+        let default = KdfCost::default();
+        KdfCost {
+            memory_kib: self.kdf_memory_kib.unwrap_or(default.memory_kib),
+            iterations: self.kdf_iterations.unwrap_or(default.iterations),
+            parallelism: self.kdf_parallelism.unwrap_or(default.parallelism),
+        }
+    }
 }
 
 impl Cli {
+    /// Take the settings of `profile` for the options which were not given.
+    ///
+    /// The key source of the profile is only used when no key options were given at all.
+    pub(crate) fn apply(&mut self, profile: Profile) -> Result<()> {
+        if self.path.is_none() {
+            self.path = profile.path().transpose()?;
+        }
+        self.format = self.format.or(profile.format);
+        self.cipher = self.cipher.or(profile.cipher);
+        if !self.key_given() {
+            self.key_source = profile.key_source;
+            self.derive_key = profile.derive_key.unwrap_or(false);
+        }
+        self.checklist = profile.checklist;
+        Ok(())
+    }
+
+    fn key_given(&self) -> bool {
+        self.encryption_key_file.is_some()
+            || self.encryption_key.is_some()
+            || self.passphrase
+            || self.key_source.is_some()
+    }
+
+    pub(crate) fn format(&self) -> OutputFormat {
+        self.format.unwrap_or_default()
+    }
+
+    fn cipher(&self) -> EncryptionMode {
+        self.cipher.unwrap_or_default()
+    }
+
+    /// The checklist for item commands which do not name one, if there is one.
+    pub(crate) fn checklist(&self) -> Option<&Ref<ChecklistId>> {
+        self.checklist.as_ref()
+    }
+
     pub(crate) fn path(&self) -> Result<PathBuf> {
         if let Some(path) = &self.path {
             return Ok(path.clone());
@@ -39,21 +176,70 @@ impl Cli {
             .join("checklist/db.sqlite3"))
     }
 
-    pub(crate) fn encryption_key(&self) -> Result<Vec<u8>> {
+    /// The key with which changesets are encrypted, or `None` for a plaintext database.
+    ///
+    /// This is the key material itself, before any derivation, so that it is the same for every
+    /// database which shares a passphrase.
+    pub(crate) fn changeset_key(&self) -> Result<Option<Zeroizing<Vec<u8>>>> {
+        if self.cipher().cipher().is_none() {
+            return Ok(None);
+        }
+        let key = self
+            .key_provider()?
+            .key()
+            .context("obtaining changeset encryption key")?;
+        Ok(Some(key))
+    }
+
+    pub(crate) fn db_options(&self, path: &Path) -> Result<DbOptions> {
+        let Some(cipher) = self.cipher().cipher() else {
+            if self.key_given() {
+                bail!("encryption key options cannot be used with `--cipher none`");
+            }
+            return Ok(DbOptions::unencrypted());
+        };
+        if let Some(KeySource::InsecureDefault) = self.key_source {
+            if !path.exists() {
+                bail!("the insecure default key only opens existing databases; choose another key");
+            }
+            ceprintln!(
+                "<yellow,bold>warning:</> {} is encrypted with the guessable key \"$USER@$NAME\"; \
+                change it with `db rekey`",
+                path.display()
+            );
+        }
+
+        let mut encryption_config = self.encryption_config(path)?;
+        encryption_config.cipher = cipher;
+        Ok(DbOptions::encrypted(encryption_config))
+    }
+
+    fn encryption_config(&self, path: &Path) -> Result<EncryptionConfig> {
+        let derive = (self.derive_key || self.passphrase).then(|| (path, self.kdf_cost.cost()));
+        key_source::encryption_config(&*self.key_provider()?, derive)
+    }
+
+    fn key_provider(&self) -> Result<Box<dyn KeyProvider>> {
+        if let Some(source) = &self.key_source {
+            return Ok(source.provider());
+        }
+
         if let Some(path) = &self.encryption_key_file {
-            return std::fs::read(path).context("reading encryption key from file");
+            return Ok(Box::new(FileKeyProvider::new(path)));
         }
 
         if let Some(key) = &self.encryption_key {
-            return Ok(key.as_bytes().to_owned());
+            return Ok(Box::new(LiteralKey(key.as_bytes().to_owned())));
         }
 
-        let mut out = Vec::new();
-        out.extend_from_slice(std::env::var_os("USER").unwrap_or_default().as_bytes());
-        out.push(b'@');
-        out.extend_from_slice(std::env::var_os("NAME").unwrap_or_default().as_bytes());
+        if self.passphrase {
+            return Ok(Box::new(PromptKeyProvider::new("Passphrase: ")));
+        }
 
-        Ok(out)
+        bail!(
+            "no encryption key given; choose one with `--key-source` or `--passphrase`, or use \
+            `--cipher none` for a plaintext database"
+        )
     }
 }
 
@@ -64,6 +250,199 @@ pub enum Noun {
 
     /// Manage items
     Item(ItemVerbAction),
+
+    /// Manage the database itself
+    Db(DbVerbAction),
+
+    /// Write every checklist and item in an interchange format
+    Export(Export),
+
+    /// Read checklists and items from an interchange format
+    Import(Import),
+
+    /// Exchange changes with other databases
+    ///
+    /// `sync <other>` merges another database into this one and this one into it. Each field keeps its
+    /// most recent value, and deletions win over older edits. Syncing is idempotent, so copies kept on
+    /// a file share converge however often each side syncs.
+    Sync(SyncAction),
+
+    /// Serve checklists and items as a REST API and web UI over HTTP
+    ///
+    /// Every API request must carry `Authorization: Bearer <token>`, with the token from
+    /// `--token-file`. The web UI at the root of the server asks for the token, or takes it from a
+    /// `#token=<token>` suffix on its address.
+    Serve(Serve),
+
+    /// Answer JSON-RPC 2.0 requests on standard input, one per line, for editor integrations
+    ///
+    /// The database stays open between requests, and a `changed` notification is sent whenever it
+    /// changes.
+    Rpc,
+
+    /// Work through checklists in a full-screen terminal interface
+    ///
+    /// Keys follow vim: `j`/`k` to move, `h`/`l` to switch between checklists and items, space to
+    /// check an item, `a` to add, `e` to edit, `d` to delete, `J`/`K` to reorder, `f` to filter by
+    /// status, `/` to search and `q` to quit. Changes made elsewhere are shown as they happen.
+    Tui,
+
+    /// Run commands one after another against a database which stays open
+    ///
+    /// Commands are those of this program without the global options, such as `list new groceries`
+    /// or `item toggle 3`. After `use <checklist>`, item commands apply to that checklist when they
+    /// do not name one. Tab completes commands and the ids of checklists and items, by id or name.
+    Shell(Shell),
+
+    /// View and change the settings in the configuration file
+    ///
+    /// The file is "checklist/config.toml" in "$XDG_CONFIG_HOME", or else in "$HOME/.config". Its
+    /// settings are grouped into named profiles, each giving defaults for the global options of the
+    /// same names, and a `checklist` for item commands which do not name one. Options given on the
+    /// command line take precedence.
+    Config(ConfigAction),
+}
+
+const EXIT_STATUS: &str = "\
+Exit status:
+  0  success
+  1  any other error
+  2  invalid arguments, or a name which could mean several records
+  3  the checklist or item does not exist
+  4  the record changed while the command ran";
+
+/// Formats in which to print the results of commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Aligned and colored text, for people
+    #[default]
+    Text,
+
+    /// A single JSON value
+    Json,
+
+    /// One JSON object per line
+    Jsonl,
+
+    /// Comma-separated values, with a header row
+    Csv,
+
+    /// Tab-separated values, with a header row
+    Tsv,
+}
+
+/// Interchange formats for `export` and `import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum Format {
+    /// Versioned JSON document
+    #[default]
+    Json,
+
+    /// todo.txt, with checklists as `+project`s
+    Todotxt,
+
+    /// Emacs Org mode outline, with checklists as headlines
+    Org,
+
+    /// CSV with one row per item, for spreadsheets
+    Csv,
+
+    /// iCalendar, with checklists and items as VTODOs
+    Ics,
+}
+
+impl Format {
+    /// The import mode used when none is given.
+    pub fn default_import_mode(self) -> ImportMode {
+        match self {
+            // re-importing an export must not duplicate every to-do
+            Self::Ics => ImportMode::Update,
+            _ => ImportMode::default(),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Export {
+    /// Format in which to write
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: Format,
+
+    /// Path at which to write the export
+    ///
+    /// Default: standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct Import {
+    /// Path from which to read
+    ///
+    /// Default: standard input
+    pub input: Option<PathBuf>,
+
+    /// Format of the input
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: Format,
+
+    /// How to combine the input with existing data: "merge" adds everything with fresh ids; "replace"
+    /// deletes everything first and keeps the input's ids; "update" overwrites records with the same
+    /// uuids, or ids or checklist names where uuids are absent, and adds the rest
+    ///
+    /// Default: "update" for ics, whose UIDs identify the records exported, and "merge" otherwise
+    #[arg(short, long)]
+    pub mode: Option<ImportMode>,
+}
+
+/// A checklist or item, identified by its integer id, its uuid, its name, or `@last`
+///
+/// Numbers are always ids. Names need not be exact: see [`checklist::Checklist::find`].
+#[derive(Debug, Clone)]
+pub enum Ref<Id> {
+    Id(Id),
+    Uuid(Uuid),
+    Name(String),
+    /// The record changed most recently
+    Last,
+}
+
+impl<Id: FromStr> FromStr for Ref<Id> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "@last" {
+            return Ok(Self::Last);
+        }
+        if let Ok(uuid) = Uuid::try_parse(s) {
+            return Ok(Self::Uuid(uuid));
+        }
+        if let Ok(id) = s.parse() {
+            return Ok(Self::Id(id));
+        }
+        if s.trim().is_empty() {
+            return Err("expected an id, uuid, name or @last".to_owned());
+        }
+        Ok(Self::Name(s.to_owned()))
+    }
+}
+
+/// Deserialized from an integer id, or a string holding an id, uuid, name or `@last`.
+impl<'de, Id: FromStr + Deserialize<'de>> Deserialize<'de> for Ref<Id> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw<Id> {
+            Id(Id),
+            Text(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Id(id) => Ok(Self::Id(id)),
+            Raw::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
 }
 
 #[derive(Debug, Args)]
@@ -82,10 +461,27 @@ pub enum ListVerb {
 
     /// Delete a checklist
     Remove(RemoveChecklist),
+
+    /// Write a checklist as a Markdown task list
+    ExportMd(ExportMarkdown),
+
+    /// Create a checklist from a Markdown task list
+    ImportMd(ImportMarkdown),
+
+    /// Edit a checklist as a Markdown task list in "$VISUAL" or "$EDITOR"
+    ///
+    /// Lines may be added, removed, changed, checked with `[x]` and reordered, and the heading
+    /// renames the checklist. The changes are applied together when the editor exits, unless the
+    /// checklist has changed meanwhile, in which case the edited file is kept.
+    Edit(EditChecklist),
 }
 
 #[derive(Debug, Args)]
-pub struct ShowAllChecklists {}
+pub struct ShowAllChecklists {
+    /// Show each checklist's uuid alongside its id
+    #[arg(short, long)]
+    pub uuids: bool,
+}
 
 #[derive(Debug, Args)]
 pub struct NewChecklist {
@@ -95,8 +491,34 @@ pub struct NewChecklist {
 
 #[derive(Debug, Args)]
 pub struct RemoveChecklist {
-    /// Id of the checklist to remove
-    pub id: ChecklistId,
+    /// Id, uuid, name or `@last` of the checklist to remove
+    pub id: Ref<ChecklistId>,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportMarkdown {
+    /// Id, uuid, name or `@last` of the checklist to export
+    pub id: Ref<ChecklistId>,
+
+    /// Path at which to write the task list
+    ///
+    /// Default: standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct EditChecklist {
+    /// Id, uuid, name or `@last` of the checklist to edit
+    pub id: Ref<ChecklistId>,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportMarkdown {
+    /// Path from which to read the task list
+    ///
+    /// Default: standard input
+    pub file: Option<PathBuf>,
 }
 
 #[derive(Debug, Args)]
@@ -111,6 +533,7 @@ pub enum ItemVerb {
     ShowAll(ShowAllItems),
 
     /// Create a new item in a checklist
+    #[command(allow_missing_positional = true)]
     New(NewItem),
 
     /// Delete an item in a checklist
@@ -122,18 +545,26 @@ pub enum ItemVerb {
 
 #[derive(Debug, Args)]
 pub struct ShowAllItems {
-    /// Checklist Id for items to show
-    pub checklist_id: ChecklistId,
+    /// Id, uuid, name or `@last` of the checklist whose items to show
+    ///
+    /// Default: the checklist in use in the shell, or else the checklist of the profile
+    pub checklist_id: Option<Ref<ChecklistId>>,
 
     /// When set, omit the item header
     #[arg(short, long)]
     pub omit_header: bool,
+
+    /// Show each item's uuid alongside its id
+    #[arg(short, long)]
+    pub uuids: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct NewItem {
-    /// Checklist Id in which to put this item
-    pub checklist_id: ChecklistId,
+    /// Id, uuid, name or `@last` of the checklist in which to put this item
+    ///
+    /// Default: the checklist in use in the shell, or else the checklist of the profile
+    pub checklist_id: Option<Ref<ChecklistId>>,
 
     /// Name of this item
     pub name: String,
@@ -141,12 +572,323 @@ pub struct NewItem {
 
 #[derive(Debug, Args)]
 pub struct RemoveItem {
-    /// Id of the item to remove
-    pub id: ItemId,
+    /// Id, uuid, text or `@last` of the item to remove
+    ///
+    /// Text and `@last` are looked for in the checklist in use in the shell, or else the checklist of
+    /// the profile, if there is one.
+    pub id: Ref<ItemId>,
 }
 
 #[derive(Debug, Args)]
 pub struct ToggleItem {
-    /// Id of the item to toggle
-    pub id: ItemId,
+    /// Id, uuid, text or `@last` of the item to toggle
+    ///
+    /// Text and `@last` are looked for in the checklist in use in the shell, or else the checklist of
+    /// the profile, if there is one.
+    pub id: Ref<ItemId>,
+}
+
+#[derive(Debug, Args)]
+pub struct DbVerbAction {
+    #[command(subcommand)]
+    pub verb: DbVerb,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbVerb {
+    /// Re-encrypt the database with a new key
+    Rekey(RekeyDb),
+
+    /// Create the database by encrypting an existing plaintext SQLite database
+    ///
+    /// The database at `--path` must not yet exist; it is encrypted according to the usual key options.
+    Encrypt(EncryptDb),
+
+    /// Write a decrypted copy of the database
+    Decrypt(DecryptDb),
+
+    /// Back up the database while it remains in use
+    Backup(BackupDb),
+
+    /// Replace the database with the contents of a backup
+    Restore(RestoreDb),
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true, arg_required_else_help = true)]
+pub struct SyncAction {
+    #[command(subcommand)]
+    pub verb: Option<SyncVerb>,
+
+    /// Path to the other database, which must use the same encryption key as this one
+    pub other: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SyncVerb {
+    /// Write the changes made since a version as an encrypted changeset
+    Export(ExportChangeset),
+
+    /// Merge a changeset written by another database
+    Apply(ApplyChangeset),
+
+    /// Show this database's site and version, and how far each peer has synced
+    Status,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportChangeset {
+    /// Include only changes made after this version
+    ///
+    /// Default: everything, unless `--peer` is given
+    #[arg(long, conflicts_with = "peer")]
+    pub since: Option<u64>,
+
+    /// Include only changes which this peer, given by site, has not yet acknowledged
+    #[arg(long)]
+    pub peer: Option<Uuid>,
+
+    /// Path at which to write the changeset
+    ///
+    /// Default: standard output
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct ApplyChangeset {
+    /// Path from which to read the changeset
+    ///
+    /// Default: standard input
+    pub input: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct Serve {
+    /// Address on which to listen
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    pub listen: SocketAddr,
+
+    /// Path to the file holding the bearer token which clients must present
+    ///
+    /// A random token is written there if the file does not exist.
+    ///
+    /// Default: "$XDG_CONFIG_HOME" if set or "$HOME/.config", then "checklist/token"
+    #[arg(long)]
+    token_file: Option<PathBuf>,
+}
+
+impl Serve {
+    pub(crate) fn token_file(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.token_file {
+            return Ok(path.clone());
+        }
+
+        Ok(dirs::config_dir()
+            .context("config dir must exist on this system")?
+            .join("checklist/token"))
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct Shell {
+    /// Neither read nor write the command history
+    ///
+    /// History is kept next to the database, with a `.history` extension. It is not encrypted, so it
+    /// reveals the names of checklists and items typed at the prompt.
+    #[arg(long)]
+    pub no_history: bool,
+}
+
+/// Commands to run against the open database
+#[derive(Debug, Parser)]
+#[command(name = "checklist", no_binary_name = true)]
+pub struct ShellLine {
+    #[command(subcommand)]
+    pub command: ShellCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ShellCommand {
+    #[command(flatten)]
+    Noun(Noun),
+
+    /// Apply item commands to a checklist when they do not name one
+    Use(UseChecklist),
+
+    /// Leave the shell
+    #[command(alias = "quit")]
+    Exit,
+}
+
+#[derive(Debug, Args)]
+pub struct UseChecklist {
+    /// Id, uuid, name or `@last` of the checklist to use
+    ///
+    /// Default: stop using a checklist
+    pub checklist: Option<Ref<ChecklistId>>,
+}
+
+#[derive(Debug, Args)]
+pub struct BackupDb {
+    /// Path at which to write the backup; must not exist
+    ///
+    /// Default: a timestamped file next to the database, rotated according to `--keep`
+    pub output: Option<PathBuf>,
+
+    /// Number of rotating timestamped backups to keep next to the database
+    #[arg(long, default_value_t = 5, conflicts_with = "output")]
+    pub keep: usize,
+}
+
+#[derive(Debug, Args)]
+pub struct RestoreDb {
+    /// Path to the backup, which must use the same encryption key as the database
+    pub backup: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct EncryptDb {
+    /// Path to the plaintext database to import; it is not modified
+    pub plain: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct DecryptDb {
+    /// Path at which to write the plaintext copy; must not exist
+    pub output: PathBuf,
+
+    /// Confirm that writing the database contents unencrypted to disk is intended
+    #[arg(long)]
+    pub confirm_plaintext: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct RekeyDb {
+    /// Path to file containing the new encryption key
+    ///
+    /// This file can contain arbitrary bytes which comprise the key for the database
+    #[arg(short, long, required_unless_present_any = ["new_passphrase", "new_key_source"])]
+    pub new_key_file: Option<PathBuf>,
+
+    /// Prompt twice for a new passphrase from which to derive the encryption key
+    ///
+    /// A new key header, with a fresh salt and the costs given by the `--kdf-*` options, replaces
+    /// any existing one.
+    #[arg(long, conflicts_with = "new_key_file")]
+    pub new_passphrase: bool,
+
+    /// Where to obtain the new encryption key
+    ///
+    /// One of `file:<path>`, `env:<var>`, `prompt`, or `command:<shell command>`.
+    #[arg(long, conflicts_with_all = ["new_key_file", "new_passphrase"])]
+    pub new_key_source: Option<KeySource>,
+
+    /// Treat the output of the new key source as a passphrase and derive the key from it
+    #[arg(long, requires = "new_key_source")]
+    pub derive_new_key: bool,
+}
+
+impl RekeyDb {
+    /// Re-encrypt `db` with the new key, deriving it with `cost` and a fresh salt if it is a passphrase.
+    pub(crate) async fn rekey(&self, db: &Db, cost: KdfCost) -> Result<()> {
+        if let Some(KeySource::InsecureDefault) = self.new_key_source {
+            bail!("the insecure default key cannot be a new key");
+        }
+        let provider = self.key_provider();
+        if !(self.derive_new_key || self.new_passphrase) {
+            let config = key_source::encryption_config(&*provider, None)?;
+            return db.rekey(config).await.context("rekeying database");
+        }
+        let passphrase = provider.key().context("obtaining new passphrase")?;
+        db.rekey_with_passphrase(&passphrase, cost)
+            .await
+            .context("rekeying database")
+    }
+
+    fn key_provider(&self) -> Box<dyn KeyProvider> {
+        if let Some(source) = &self.new_key_source {
+            return source.provider();
+        }
+
+        if self.new_passphrase {
+            return Box::new(NewPassphrase);
+        }
+
+        let path = self
+            .new_key_file
+            .as_ref()
+            .expect("clap requires one of the new key options");
+        Box::new(FileKeyProvider::new(path))
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigAction {
+    #[command(subcommand)]
+    pub verb: ConfigVerb,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigVerb {
+    /// Print the path of the configuration file
+    Path,
+
+    /// Print the configuration file
+    Show,
+
+    /// Print a setting of the chosen profile
+    Get(GetSetting),
+
+    /// Change a setting of the chosen profile, creating the profile if need be
+    Set(SetSetting),
+
+    /// Remove a setting from the chosen profile
+    Unset(UnsetSetting),
+
+    /// Open the configuration file in "$VISUAL" or "$EDITOR", and check it once closed
+    Edit,
+}
+
+#[derive(Debug, Args)]
+pub struct GetSetting {
+    pub setting: Setting,
+}
+
+#[derive(Debug, Args)]
+pub struct SetSetting {
+    pub setting: Setting,
+
+    /// New value, as the global option of the same name takes it
+    pub value: String,
+}
+
+#[derive(Debug, Args)]
+pub struct UnsetSetting {
+    pub setting: Setting,
+}
+
+/// Settings of a profile in the configuration file, and `default-profile`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Setting {
+    /// Profile used when none is chosen; this belongs to the file rather than to a profile
+    DefaultProfile,
+
+    /// Path to the database; "~/" stands for the home directory
+    Path,
+
+    /// Where to obtain the encryption key for data at rest
+    KeySource,
+
+    /// Whether to derive the key from the output of the key source: "true" or "false"
+    DeriveKey,
+
+    /// Cipher used to encrypt data at rest
+    Cipher,
+
+    /// How to print checklists, items and the outcomes of commands
+    Format,
+
+    /// Checklist for item commands which do not name one
+    Checklist,
 }
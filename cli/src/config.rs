@@ -0,0 +1,237 @@
+//! Named profiles of settings, read from the configuration file.
+//!
+//! ```toml
+//! default-profile = "personal"
+//!
+//! [profiles.personal]
+//! checklist = "groceries"
+//!
+//! [profiles.work]
+//! path = "~/work/checklist.sqlite3"
+//! key-source = "command:pass show checklist"
+//! derive-key = true
+//! format = "json"
+//! ```
+//!
+//! `config set` and `config unset` change the file in place, keeping its comments and layout.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context as _, Result};
+use checklist::{ChecklistId, EncryptionMode};
+use serde::{de, Deserialize, Deserializer};
+use toml_edit::{DocumentMut, Item, Table};
+
+use crate::{
+    cli::{Cli, ConfigVerb, GetSetting, OutputFormat, Ref, SetSetting, Setting, UnsetSetting},
+    editor,
+    key_source::KeySource,
+};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// Defaults for the global options, and a checklist for item commands which do not name one
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Profile {
+    path: Option<PathBuf>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub key_source: Option<KeySource>,
+    pub derive_key: Option<bool>,
+    #[serde(default, deserialize_with = "parsed")]
+    pub cipher: Option<EncryptionMode>,
+    pub format: Option<OutputFormat>,
+    pub checklist: Option<Ref<ChecklistId>>,
+}
+
+impl Profile {
+    /// The path to the database, with a leading `~` standing for the home directory.
+    pub(crate) fn path(&self) -> Option<Result<PathBuf>> {
+        let path = self.path.as_ref()?;
+        let Ok(rest) = path.strip_prefix("~") else {
+            return Some(Ok(path.clone()));
+        };
+        let home = dirs::home_dir().context("home dir must exist on this system to expand `~`");
+        Some(home.map(|home| home.join(rest)))
+    }
+}
+
+/// Deserialized from a string, with the parser of the global option of the same name.
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map(Some).map_err(de::Error::custom)
+}
+
+/// The path of the configuration file.
+pub(crate) fn path() -> Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .context("config dir must exist on this system")?
+        .join("checklist/config.toml"))
+}
+
+/// Read the configuration file, which is empty if it does not exist.
+fn read(path: &Path) -> Result<String> {
+    match std::fs::read_to_string(path) {
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(String::new()),
+        result => result.with_context(|| format!("reading {}", path.display())),
+    }
+}
+
+fn parse(text: &str, path: &Path) -> Result<Config> {
+    toml::from_str(text).with_context(|| format!("invalid configuration in {}", path.display()))
+}
+
+/// Load the profile chosen with `--profile` or `$CHECKLIST_PROFILE`, or else the default profile.
+///
+/// Without either, every setting is left to the global options and their defaults.
+pub(crate) fn profile(cli: &Cli) -> Result<Profile> {
+    let path = path()?;
+    let mut config = parse(&read(&path)?, &path)?;
+    let Some(name) = cli.profile.as_ref().or(config.default_profile.as_ref()) else {
+        return Ok(Profile::default());
+    };
+    let name = name.clone();
+    config
+        .profiles
+        .remove(&name)
+        .with_context(|| format!("there is no profile {name:?} in {}", path.display()))
+}
+
+/// Run a `config` command.
+///
+/// These only read the file as far as they need to, so that a broken file can be repaired.
+pub(crate) fn run(cli: &Cli, verb: &ConfigVerb) -> Result<()> {
+    let path = path()?;
+    match verb {
+        ConfigVerb::Path => println!("{}", path.display()),
+        ConfigVerb::Show => print!("{}", read(&path)?),
+        ConfigVerb::Get(GetSetting { setting }) => {
+            let document = document(&path)?;
+            let value = match setting {
+                Setting::DefaultProfile => document.get(key(*setting)),
+                _ => {
+                    let name = chosen(cli, &document)?;
+                    document
+                        .get("profiles")
+                        .and_then(|profiles| profiles.get(name))
+                        .and_then(|profile| profile.get(key(*setting)))
+                }
+            };
+            let Some(value) = value else {
+                bail!("{} is not set", key(*setting));
+            };
+            match value.as_str() {
+                Some(text) => println!("{text}"),
+                None => println!("{}", value.to_string().trim()),
+            }
+        }
+        ConfigVerb::Set(SetSetting { setting, value }) => {
+            let mut document = document(&path)?;
+            let value = match setting {
+                Setting::DeriveKey => toml_edit::value(
+                    value
+                        .parse::<bool>()
+                        .context("derive-key must be \"true\" or \"false\"")?,
+                ),
+                _ => toml_edit::value(value),
+            };
+            *setting_mut(cli, &mut document, *setting)? = value;
+            write(&path, &document)?;
+        }
+        ConfigVerb::Unset(UnsetSetting { setting }) => {
+            let mut document = document(&path)?;
+            *setting_mut(cli, &mut document, *setting)? = Item::None;
+            write(&path, &document)?;
+        }
+        ConfigVerb::Edit => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).context("creating config directory")?;
+            }
+            editor::edit(&path)?;
+            parse(&read(&path)?, &path)?;
+        }
+    }
+    Ok(())
+}
+
+/// The key of a setting in the file, which is also its name on the command line.
+fn key(setting: Setting) -> &'static str {
+    match setting {
+        Setting::DefaultProfile => "default-profile",
+        Setting::Path => "path",
+        Setting::KeySource => "key-source",
+        Setting::DeriveKey => "derive-key",
+        Setting::Cipher => "cipher",
+        Setting::Format => "format",
+        Setting::Checklist => "checklist",
+    }
+}
+
+fn document(path: &Path) -> Result<DocumentMut> {
+    read(path)?
+        .parse()
+        .with_context(|| format!("invalid TOML in {}", path.display()))
+}
+
+/// The name of the profile chosen with `--profile` or `$CHECKLIST_PROFILE`, or else the default.
+fn chosen<'a>(cli: &'a Cli, document: &'a DocumentMut) -> Result<&'a str> {
+    cli.profile
+        .as_deref()
+        .or_else(|| document.get("default-profile")?.as_str())
+        .context("no profile is chosen; choose one with `--profile`, or set default-profile")
+}
+
+/// The place of a setting in the file, creating its profile if need be.
+fn setting_mut<'a>(
+    cli: &Cli,
+    document: &'a mut DocumentMut,
+    setting: Setting,
+) -> Result<&'a mut Item> {
+    if setting == Setting::DefaultProfile {
+        return Ok(document.entry(key(setting)).or_insert(Item::None));
+    }
+    let name = chosen(cli, document)?.to_owned();
+    let profiles = document
+        .entry("profiles")
+        .or_insert_with(|| {
+            // so that only the `[profiles.<name>]` headers are written
+            let mut profiles = Table::new();
+            profiles.set_implicit(true);
+            Item::Table(profiles)
+        })
+        .as_table_mut()
+        .context("profiles must be a table")?;
+    let profile = profiles
+        .entry(&name)
+        .or_insert_with(toml_edit::table)
+        .as_table_mut()
+        .with_context(|| format!("profile {name:?} must be a table"))?;
+    Ok(profile.entry(key(setting)).or_insert(Item::None))
+}
+
+/// Write the file, provided that it is still a valid configuration.
+fn write(path: &Path, document: &DocumentMut) -> Result<()> {
+    let text = document.to_string();
+    parse(&text, path)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).context("creating config directory")?;
+    }
+    std::fs::write(path, text).with_context(|| format!("writing {}", path.display()))
+}
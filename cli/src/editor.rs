@@ -0,0 +1,78 @@
+//! Opening files in the editor of the user's choice.
+
+use std::{
+    ffi::OsString,
+    fs::{File, OpenOptions},
+    io::{ErrorKind, Write as _},
+    os::unix::fs::OpenOptionsExt as _,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{bail, Context as _, Result};
+
+/// Open `path` in "$VISUAL" or "$EDITOR", or else `vi`, and wait for the editor to exit.
+///
+/// The editor is run by the shell, so it may carry arguments, as in `code --wait`.
+pub(crate) fn edit(path: &Path) -> Result<()> {
+    let mut script = std::env::var_os("VISUAL")
+        .filter(|editor| !editor.is_empty())
+        .or_else(|| std::env::var_os("EDITOR"))
+        .filter(|editor| !editor.is_empty())
+        .unwrap_or_else(|| OsString::from("vi"));
+    script.push(r#" "$1""#);
+
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("sh")
+        .arg(path)
+        .status()
+        .context("starting editor")?;
+    if !status.success() {
+        bail!("editor exited with {status}");
+    }
+    Ok(())
+}
+
+/// Write `text` to a new file in the temporary directory, readable only by the user, and edit it.
+///
+/// The file has a random name, so that it cannot be guessed by others, nor clash with a file kept by
+/// an earlier edit. Returns the path of the file, which is left for the caller to remove, and the
+/// edited text. The file is removed if editing fails.
+pub(crate) fn edit_text(text: &str, extension: &str) -> Result<(PathBuf, String)> {
+    let (path, mut file) = create_temporary(extension)?;
+    let edited = file
+        .write_all(text.as_bytes())
+        .context("writing file to edit")
+        .and_then(|()| edit(&path))
+        .and_then(|()| std::fs::read_to_string(&path).context("reading edited file"));
+    match edited {
+        Ok(edited) => Ok((path, edited)),
+        Err(err) => {
+            let _ = std::fs::remove_file(&path);
+            Err(err)
+        }
+    }
+}
+
+fn create_temporary(extension: &str) -> Result<(PathBuf, File)> {
+    loop {
+        let mut bytes = [0; 8];
+        getrandom::getrandom(&mut bytes).context("generating file name")?;
+        let name: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+        let path = std::env::temp_dir().join(format!("checklist-{name}.{extension}"));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+        match file {
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            file => {
+                let file = file.with_context(|| format!("creating {}", path.display()))?;
+                return Ok((path, file));
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    ffi::OsString,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context as _, Result};
+use checklist::{
+    key_provider::{
+        derived_encryption_config, raw_encryption_config, CommandKeyProvider, EnvKeyProvider,
+        FileKeyProvider, PromptKeyProvider,
+    },
+    EncryptionConfig, KdfCost, KeyProvider,
+};
+use zeroize::Zeroizing;
+
+/// Where to obtain encryption key material.
+///
+/// Parsed from one of:
+///
+/// - `file:<path>`: read the key from a file
+/// - `env:<var>`: read the key from an environment variable
+/// - `prompt`: prompt for the key on the terminal without echoing it
+/// - `command:<shell command>`: run a command and use its output, e.g. `command:pass show checklist`
+/// - `insecure-default`: the guessable key "$USER@$NAME" which older versions used when no key was
+///   given, only for opening databases created with it so that they can be rekeyed
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    File(PathBuf),
+    Env(OsString),
+    Prompt,
+    Command(String),
+    InsecureDefault,
+}
+
+impl KeySource {
+    pub(crate) fn provider(&self) -> Box<dyn KeyProvider> {
+        match self {
+            Self::File(path) => Box::new(FileKeyProvider::new(path)),
+            Self::Env(var) => Box::new(EnvKeyProvider::new(var)),
+            Self::Prompt => Box::new(PromptKeyProvider::default()),
+            Self::Command(command) => Box::new(CommandKeyProvider::new("sh", ["-c", command])),
+            Self::InsecureDefault => Box::new(DefaultKey),
+        }
+    }
+}
+
+impl FromStr for KeySource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "prompt" => return Ok(Self::Prompt),
+            "insecure-default" => return Ok(Self::InsecureDefault),
+            _ => {}
+        }
+
+        let Some((kind, value)) = s.split_once(':') else {
+            bail!(
+                "expected one of `file:<path>`, `env:<var>`, `prompt`, `command:<command>`, \
+                `insecure-default`"
+            );
+        };
+        match kind {
+            "file" => Ok(Self::File(value.into())),
+            "env" => Ok(Self::Env(value.into())),
+            "command" => Ok(Self::Command(value.to_owned())),
+            _ => bail!("unknown key source kind: {kind}"),
+        }
+    }
+}
+
+/// A key given literally on the command line.
+pub(crate) struct LiteralKey(pub(crate) Vec<u8>);
+
+impl KeyProvider for LiteralKey {
+    fn key(&self) -> checklist::Result<Zeroizing<Vec<u8>>> {
+        Ok(Zeroizing::new(self.0.clone()))
+    }
+}
+
+/// The key which older versions fell back on: "$USER@$NAME".
+struct DefaultKey;
+
+impl KeyProvider for DefaultKey {
+    fn key(&self) -> checklist::Result<Zeroizing<Vec<u8>>> {
+        let mut out = Zeroizing::new(Vec::new());
+        out.extend_from_slice(std::env::var_os("USER").unwrap_or_default().as_bytes());
+        out.push(b'@');
+        out.extend_from_slice(std::env::var_os("NAME").unwrap_or_default().as_bytes());
+        Ok(out)
+    }
+}
+
+/// A new passphrase, prompted for twice on the terminal so that a typing mistake is caught.
+pub(crate) struct NewPassphrase;
+
+impl KeyProvider for NewPassphrase {
+    fn key(&self) -> checklist::Result<Zeroizing<Vec<u8>>> {
+        let passphrase = PromptKeyProvider::new("New passphrase: ").key()?;
+        let repeated = PromptKeyProvider::new("Repeat new passphrase: ").key()?;
+        if passphrase != repeated {
+            return Err(checklist::Error::key_provider(
+                "the passphrases do not match",
+            ));
+        }
+        Ok(passphrase)
+    }
+}
+
+/// Build an encryption config from a key provider.
+///
+/// When `derive` is set, the provider's output is treated as a passphrase.
+pub(crate) fn encryption_config(
+    provider: &dyn KeyProvider,
+    derive: Option<(&Path, KdfCost)>,
+) -> Result<EncryptionConfig> {
+    match derive {
+        Some((path, cost)) => derived_encryption_config(provider, path, cost)
+            .context("deriving encryption key from passphrase"),
+        None => raw_encryption_config(provider).context("obtaining encryption key"),
+    }
+}
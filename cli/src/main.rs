@@ -1,120 +1,610 @@
+// Arguments must be given explicitly, since macro hygiene keeps `cformat!` from capturing them.
+
+/// Like [`color_print::cprintln`], but without colors unless standard output is a terminal.
+macro_rules! cprintln {
+    ($($arg:tt)*) => {
+        anstream::println!("{}", color_print::cformat!($($arg)*))
+    };
+}
+
+/// Like [`color_print::ceprintln`], but without colors unless standard error is a terminal.
+macro_rules! ceprintln {
+    ($($arg:tt)*) => {
+        anstream::eprintln!("{}", color_print::cformat!($($arg)*))
+    };
+}
+
+mod changes;
 mod cli;
+mod config;
+mod editor;
+mod key_source;
+mod output;
+mod resolve;
+mod rpc;
+mod serve;
+mod shell;
+mod tui;
+mod views;
+mod web;
+
 use anyhow::Context;
-use bytes::Bytes;
-use checklist::{Checklist, Cipher, Db, EncryptionConfig, Item};
+use checklist::{formats, sync, Checklist, ChecklistId, Db, Error, Item, Uuid};
 use clap::Parser as _;
 use cli::{
-    Cli, ItemVerb, ItemVerbAction, ListVerb, ListVerbAction, NewChecklist, NewItem,
-    RemoveChecklist, RemoveItem, ShowAllChecklists, ShowAllItems, ToggleItem,
+    ApplyChangeset, BackupDb, Cli, ConfigAction, DbVerb, DbVerbAction, DecryptDb, EditChecklist,
+    EncryptDb, Export, ExportChangeset, ExportMarkdown, Format, Import, ImportMarkdown, ItemVerb,
+    ItemVerbAction, ListVerb, ListVerbAction, NewChecklist, NewItem, Ref, RemoveChecklist,
+    RemoveItem, RestoreDb, ShowAllChecklists, ShowAllItems, SyncAction, SyncVerb, ToggleItem,
+};
+use std::{
+    io::{Read as _, Write as _},
+    path::Path,
+    process::ExitCode,
+    sync::Arc,
+};
+use views::{
+    ApplyView, ChecklistView, EditView, ImportView, ItemView, PathView, StatusView, SyncView,
 };
-use color_print::cprintln;
 
 #[tokio::main]
-async fn main() -> anyhow::Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            ceprintln!("<red,bold>error:</> {:#}", err);
+            exit_code(&err)
+        }
+    }
+}
+
+/// The exit status for a failed command, as documented in `--help`.
+fn exit_code(err: &anyhow::Error) -> ExitCode {
+    match err.downcast_ref::<Error>() {
+        Some(Error::Ambiguous { .. }) => ExitCode::from(2),
+        Some(Error::MissingChecklist | Error::MissingItem) => ExitCode::from(3),
+        Some(Error::Conflict { .. }) => ExitCode::from(4),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+async fn run(mut cli: Cli) -> anyhow::Result<()> {
+    // the configuration file must not be needed to repair it
+    if let cli::Noun::Config(ConfigAction { verb }) = &cli.noun {
+        return config::run(&cli, verb);
+    }
+    let profile = config::profile(&cli)?;
+    cli.apply(profile)?;
 
     let path = cli.path()?;
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent).context("creating checklist data directory")?;
     }
-    let encryption_key = Bytes::from(cli.encryption_key()?);
+    let db_options = cli.db_options(&path)?;
 
-    let encryption_config = EncryptionConfig {
-        cipher: Cipher::Aes256Cbc,
-        encryption_key,
-    };
+    if let cli::Noun::Db(DbVerbAction {
+        verb: DbVerb::Encrypt(EncryptDb { plain }),
+    }) = &cli.noun
+    {
+        let encryption_config = db_options
+            .encryption
+            .context("cannot encrypt into a database with `--cipher none`")?;
+        Db::encrypt_from_plain(plain, &path, encryption_config)
+            .await
+            .context("encrypting plaintext database")?;
+        return Ok(());
+    }
 
-    let db = Db::new(path, encryption_config)
+    let db = Db::open(&path, db_options)
         .await
         .context("connecting to database")?;
+    let db = Arc::new(db);
+
+    if let cli::Noun::Shell(shell) = &cli.noun {
+        return shell::run(&cli, &path, db, shell).await;
+    }
+    execute(&cli, &path, &db, &cli.noun, cli.checklist()).await
+}
 
-    match cli.noun {
+/// Run a command against the open database.
+///
+/// `cli` supplies the global options, such as the keys with which to open other databases, and
+/// `using` is the checklist for item commands which do not name one.
+async fn execute(
+    cli: &Cli,
+    path: &Path,
+    db: &Arc<Db>,
+    noun: &cli::Noun,
+    using: Option<&Ref<ChecklistId>>,
+) -> anyhow::Result<()> {
+    let format = cli.format();
+    match noun {
         cli::Noun::List(ListVerbAction {
-            verb: ListVerb::ShowAll(ShowAllChecklists {}),
+            verb: ListVerb::ShowAll(ShowAllChecklists { uuids }),
         }) => {
-            for checklist in Checklist::all(&db).await.context("getting checklists")? {
-                show_checklist(&checklist);
-            }
+            let checklists = Checklist::all(db).await.context("getting checklists")?;
+            let checklists: Vec<_> = checklists.into_iter().map(ChecklistView::from).collect();
+            output::records(format, &checklists, |checklist| {
+                show_checklist(checklist, *uuids)
+            })?;
         }
         cli::Noun::List(ListVerbAction {
             verb: ListVerb::New(NewChecklist { name }),
         }) => {
-            let checklist = Checklist::new(&db, &name)
+            let checklist = Checklist::new(db, name)
                 .await
                 .context("creating checklist")?;
-            show_checklist(&checklist);
+            output::record(format, &checklist.into(), |checklist| {
+                show_checklist(checklist, false)
+            })?;
         }
         cli::Noun::List(ListVerbAction {
             verb: ListVerb::Remove(RemoveChecklist { id }),
         }) => {
-            Checklist::delete(&db, id)
+            let checklist = resolve::checklist(db, id)
+                .await
+                .context("getting checklist")?;
+            // fails rather than deleting changes which would not be shown
+            Checklist::delete_if_version(db, checklist.id, checklist.version)
                 .await
                 .context("deleting checklist")?;
+            output::record(format, &checklist.into(), |checklist| {
+                show_checklist(checklist, false)
+            })?;
+        }
+        cli::Noun::List(ListVerbAction {
+            verb: ListVerb::ExportMd(ExportMarkdown { id, output }),
+        }) => {
+            let checklist = resolve::checklist(db, id)
+                .await
+                .context("getting checklist")?;
+            let markdown = checklist
+                .to_markdown(db)
+                .await
+                .context("writing checklist as markdown")?;
+            write_output(output.as_deref(), &markdown)?;
+        }
+        cli::Noun::List(ListVerbAction {
+            verb: ListVerb::ImportMd(ImportMarkdown { file }),
+        }) => {
+            let markdown = read_input(file.as_deref())?;
+            let checklist = Checklist::from_markdown(db, &markdown)
+                .await
+                .context("importing checklist from markdown")?;
+            output::record(format, &checklist.into(), |checklist| {
+                show_checklist(checklist, false)
+            })?;
+        }
+        cli::Noun::List(ListVerbAction {
+            verb: ListVerb::Edit(EditChecklist { id }),
+        }) => {
+            let checklist = resolve::checklist(db, id)
+                .await
+                .context("getting checklist")?;
+            let markdown = checklist
+                .to_markdown(db)
+                .await
+                .context("writing checklist as markdown")?;
+            let (path, edited) = editor::edit_text(&format!("{markdown}{EDIT_HELP}"), "md")?;
+            let summary = if edited.strip_suffix(EDIT_HELP) == Some(markdown.as_str()) {
+                formats::EditSummary::default()
+            } else {
+                checklist
+                    .apply_markdown(db, &markdown, &edited)
+                    .await
+                    .with_context(|| {
+                        format!("applying edits; they are kept in {}", path.display())
+                    })?
+            };
+            let _ = std::fs::remove_file(&path);
+            output::record(format, &EditView::from(summary), |summary| {
+                if summary.renamed {
+                    cprintln!("renamed the checklist");
+                }
+                cprintln!(
+                    "added <bold>{}</bold>, removed <bold>{}</bold>, edited <bold>{}</bold>, \
+                    toggled <bold>{}</bold> and moved <bold>{}</bold> items",
+                    summary.added,
+                    summary.removed,
+                    summary.edited,
+                    summary.toggled,
+                    summary.moved
+                )
+            })?;
         }
         cli::Noun::Item(ItemVerbAction {
             verb:
                 ItemVerb::ShowAll(ShowAllItems {
-                    checklist_id,
+                    checklist_id: checklist,
                     omit_header,
+                    uuids,
                 }),
         }) => {
-            let checklist = Checklist::load(&db, checklist_id)
+            let checklist = item_checklist(db, checklist.as_ref(), using).await?;
+            let items = checklist.items(db).await.context("getting items")?;
+            let items = ItemView::load_all(db, items)
                 .await
-                .context("getting checklist")?
-                .context("checklist not found")?;
+                .context("getting item status")?;
 
-            if !omit_header {
-                show_checklist(&checklist);
+            if !omit_header && format == cli::OutputFormat::Text {
+                show_checklist(&checklist.into(), *uuids);
                 println!("=========================")
             }
-
-            for item in checklist.items(&db).await.context("getting items")? {
-                let checked = item.is_set(&db).await.context("getting item status")?;
-                show_item(&item, checked);
-            }
+            output::records(format, &items, |item| show_item(item, *uuids))?;
         }
         cli::Noun::Item(ItemVerbAction {
-            verb: ItemVerb::New(NewItem { checklist_id, name }),
+            verb:
+                ItemVerb::New(NewItem {
+                    checklist_id: checklist,
+                    name,
+                }),
         }) => {
-            let item = Item::new(&db, checklist_id, name)
+            let checklist = item_checklist(db, checklist.as_ref(), using).await?;
+            let item = Item::new(db, checklist.id, name.clone())
                 .await
                 .context("creating item")?;
-            show_item(&item, false);
+            let item = ItemView::load(db, item)
+                .await
+                .context("getting item status")?;
+            output::record(format, &item, |item| show_item(item, false))?;
         }
         cli::Noun::Item(ItemVerbAction {
             verb: ItemVerb::Remove(RemoveItem { id }),
         }) => {
-            Item::delete(&db, id).await.context("deleting item")?;
+            let item = resolve::item(db, id, using).await.context("getting item")?;
+            let item = ItemView::load(db, item)
+                .await
+                .context("getting item status")?;
+            // fails rather than deleting changes which would not be shown
+            Item::delete_if_version(db, item.id, item.version)
+                .await
+                .context("deleting item")?;
+            output::record(format, &item, |item| show_item(item, false))?;
         }
         cli::Noun::Item(ItemVerbAction {
             verb: ItemVerb::Toggle(ToggleItem { id }),
         }) => {
-            let item = Item::load(&db, id)
+            let item = resolve::item(db, id, using)
+                .await
+                .context("loading item from db")?;
+            let checked = item.is_set(db).await.context("getting item check status")?;
+            // another process may have toggled the item since it was loaded
+            item.set_checked_if_version(db, !checked, item.version)
+                .await
+                .context("updating item check status")?;
+            let item = resolve::item(db, &cli::Ref::Id(item.id), None)
                 .await
-                .context("loading item from db")?
-                .context("item not found")?;
-            let checked = item
-                .is_set(&db)
+                .context("loading item from db")?;
+            let item = ItemView::load(db, item)
                 .await
                 .context("getting item check status")?;
-            item.set_checked(&db, !checked)
+            output::record(format, &item, |item| show_item(item, false))?;
+        }
+        cli::Noun::Db(DbVerbAction {
+            verb: DbVerb::Rekey(rekey),
+        }) => {
+            rekey.rekey(db, cli.kdf_cost.cost()).await?;
+            output::record(
+                format,
+                &PathView {
+                    path: path.to_owned(),
+                },
+                |_| (),
+            )?;
+        }
+        cli::Noun::Db(DbVerbAction {
+            verb: DbVerb::Encrypt(_),
+        }) => anyhow::bail!("`db encrypt` creates the database, so it cannot run once it is open"),
+        cli::Noun::Db(DbVerbAction {
+            verb:
+                DbVerb::Decrypt(DecryptDb {
+                    output,
+                    confirm_plaintext,
+                }),
+        }) => {
+            if !*confirm_plaintext {
+                anyhow::bail!(
+                    "this writes every checklist unencrypted to {}; pass `--confirm-plaintext` to proceed",
+                    output.display()
+                );
+            }
+            db.export_plain(output)
                 .await
-                .context("updating item check status")?;
-            show_item(&item, !checked);
+                .context("writing decrypted copy of database")?;
+            output::record(
+                format,
+                &PathView {
+                    path: output.clone(),
+                },
+                |_| (),
+            )?;
+        }
+        cli::Noun::Db(DbVerbAction {
+            verb: DbVerb::Backup(BackupDb { output, keep }),
+        }) => {
+            let output = match output {
+                Some(output) => {
+                    db.backup_to(output).await.context("backing up database")?;
+                    output.clone()
+                }
+                None => db
+                    .backup_rotating(*keep)
+                    .await
+                    .context("backing up database")?,
+            };
+            output::record(format, &PathView { path: output }, |backup| {
+                println!("{}", backup.path.display())
+            })?;
+        }
+        cli::Noun::Db(DbVerbAction {
+            verb: DbVerb::Restore(RestoreDb { backup }),
+        }) => {
+            db.restore_from(backup)
+                .await
+                .context("restoring database from backup")?;
+            output::record(
+                format,
+                &PathView {
+                    path: path.to_owned(),
+                },
+                |_| (),
+            )?;
+        }
+        cli::Noun::Export(Export {
+            format: document_format,
+            output,
+        }) => {
+            let document = formats::Document::export(db)
+                .await
+                .context("reading checklists for export")?;
+            let exported = encode(&document, *document_format)?;
+            write_output(output.as_deref(), &exported)?;
+        }
+        cli::Noun::Import(Import {
+            input,
+            format: document_format,
+            mode,
+        }) => {
+            let input = read_input(input.as_deref())?;
+            let document = decode(&input, *document_format)?;
+            let summary = document
+                .import(db, mode.unwrap_or(document_format.default_import_mode()))
+                .await
+                .context("importing checklists")?;
+            output::record(format, &ImportView::from(summary), |summary| {
+                cprintln!(
+                    "imported <bold>{}</bold> checklists and <bold>{}</bold> items",
+                    summary.checklists,
+                    summary.items
+                )
+            })?;
+        }
+        cli::Noun::Serve(serve) => {
+            let token = serve::load_token(&serve.token_file()?)?;
+            serve::run(db.clone(), serve.listen, token).await?;
+        }
+        cli::Noun::Rpc => rpc::run(db.clone()).await?,
+        cli::Noun::Tui => tui::run(db.clone()).await?,
+        cli::Noun::Shell(_) => anyhow::bail!("the shell is already running"),
+        cli::Noun::Config(ConfigAction { verb }) => config::run(cli, verb)?,
+        cli::Noun::Sync(SyncAction {
+            verb: None,
+            other: Some(other),
+        }) => {
+            let other_options = cli.db_options(other)?;
+            let other = Db::open(other, other_options)
+                .await
+                .context("connecting to other database")?;
+            let report = db.sync_with(&other).await.context("syncing databases")?;
+            output::record(format, &SyncView::from(&report), |report| {
+                cprintln!(
+                    "pulled <bold>{}</bold> changes and pushed <bold>{}</bold> changes",
+                    report.pulled,
+                    report.pushed
+                );
+                show_skipped(report.skipped);
+                show_conflicts(&report.conflicts);
+            })?;
+        }
+        cli::Noun::Sync(SyncAction { verb: None, .. }) => {
+            unreachable!("clap requires either a subcommand or the other database")
+        }
+        cli::Noun::Sync(SyncAction {
+            verb:
+                Some(SyncVerb::Export(ExportChangeset {
+                    since,
+                    peer,
+                    output,
+                })),
+            ..
+        }) => {
+            let changeset = match peer {
+                Some(peer) => db.changes_for(*peer).await,
+                None => db.changes_since(since.unwrap_or_default()).await,
+            }
+            .context("collecting changes")?;
+            let key = cli.changeset_key()?;
+            let bytes = changeset
+                .to_bytes(key.as_deref().map(Vec::as_slice))
+                .context("encoding changeset")?;
+            write_output(output.as_deref(), bytes)?;
+            ceprintln!(
+                "exported <bold>{}</bold> records up to version <bold>{}</bold>",
+                changeset.rows.len(),
+                changeset.version
+            );
+        }
+        cli::Noun::Sync(SyncAction {
+            verb: Some(SyncVerb::Apply(ApplyChangeset { input })),
+            ..
+        }) => {
+            let bytes = read_input_bytes(input.as_deref())?;
+            let key = cli.changeset_key()?;
+            let changeset = sync::Changeset::from_bytes(&bytes, key.as_deref().map(Vec::as_slice))
+                .context("decoding changeset")?;
+            let summary = db
+                .apply_changeset(&changeset)
+                .await
+                .context("applying changeset")?;
+            output::record(format, &ApplyView::from(&summary), |summary| {
+                cprintln!(
+                    "inserted <bold>{}</bold>, updated <bold>{}</bold> and deleted <bold>{}</bold> records",
+                    summary.inserted,
+                    summary.updated,
+                    summary.deleted
+                );
+                show_skipped(summary.skipped);
+                show_conflicts(&summary.conflicts);
+            })?;
+        }
+        cli::Noun::Sync(SyncAction {
+            verb: Some(SyncVerb::Status),
+            ..
+        }) => {
+            let status = StatusView::load(db).await.context("getting sync status")?;
+            output::record(format, &status, |status| {
+                cprintln!(
+                    "<bold>{}</bold> (this database) at version {}",
+                    status.site,
+                    status.version
+                );
+                for peer in &status.peers {
+                    cprintln!(
+                        "{}: received up to {}, acknowledged up to {}",
+                        peer.site,
+                        peer.received,
+                        peer.acknowledged
+                    );
+                }
+            })?;
         }
     }
 
     Ok(())
 }
 
-fn show_checklist(Checklist { id, name }: &Checklist) {
-    cprintln!("<dim>{id:>6}:</dim> {name}")
+/// Write a document in an interchange format.
+fn encode(document: &formats::Document, format: Format) -> anyhow::Result<String> {
+    Ok(match format {
+        Format::Json => formats::json::to_string(document).context("encoding json")? + "\n",
+        Format::Todotxt => formats::todotxt::to_string(document),
+        Format::Org => formats::org::to_string(document),
+        Format::Csv => {
+            let mut csv = Vec::new();
+            formats::csv::write(document, &mut csv).context("encoding csv")?;
+            String::from_utf8(csv).context("encoding csv")?
+        }
+        Format::Ics => formats::ics::to_string(document),
+    })
+}
+
+/// Read a document in an interchange format.
+fn decode(input: &str, format: Format) -> anyhow::Result<formats::Document> {
+    Ok(match format {
+        Format::Json => formats::json::from_str(input).context("decoding json")?,
+        Format::Todotxt => formats::todotxt::from_str(input).context("decoding todo.txt")?,
+        Format::Org => formats::org::from_str(input).context("decoding org")?,
+        Format::Csv => formats::csv::read(input.as_bytes()).context("decoding csv")?,
+        Format::Ics => formats::ics::from_str(input).context("decoding icalendar")?,
+    })
+}
+
+/// Read all of `path`, or standard input when `None`.
+fn read_input(path: Option<&Path>) -> anyhow::Result<String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path).context("reading input file"),
+        None => std::io::read_to_string(std::io::stdin()).context("reading standard input"),
+    }
+}
+
+/// Read all of `path` as bytes, or standard input when `None`.
+fn read_input_bytes(path: Option<&Path>) -> anyhow::Result<Vec<u8>> {
+    match path {
+        Some(path) => std::fs::read(path).context("reading input file"),
+        None => {
+            let mut bytes = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut bytes)
+                .context("reading standard input")?;
+            Ok(bytes)
+        }
+    }
+}
+
+/// Write `contents` to `path`, or standard output when `None`.
+fn write_output(path: Option<&Path>, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, contents).context("writing output file"),
+        None => std::io::stdout()
+            .write_all(contents.as_ref())
+            .context("writing standard output"),
+    }
+}
+
+/// Guidance appended to a checklist for `list edit`, which is ignored when reading it back.
+const EDIT_HELP: &str = "\n<!-- Change, add, remove and reorder items, and check them with [x]. \
+    The changes are applied when the editor exits. -->\n";
+
+/// Load the checklist named by an item command, or else the one in use.
+async fn item_checklist(
+    db: &Db,
+    checklist: Option<&Ref<ChecklistId>>,
+    using: Option<&Ref<ChecklistId>>,
+) -> anyhow::Result<Checklist> {
+    let checklist = checklist
+        .or(using)
+        .context("no checklist given, and none is in use or set by the profile")?;
+    resolve::checklist(db, checklist)
+        .await
+        .context("getting checklist")
+}
+
+/// Report records skipped while applying a changeset.
+fn show_skipped(skipped: usize) {
+    if skipped > 0 {
+        cprintln!(
+            "<yellow>skipped <bold>{}</bold> incomplete records</yellow>",
+            skipped
+        );
+    }
+}
+
+/// Report the conflicts resolved while applying a changeset.
+fn show_conflicts(conflicts: &[String]) {
+    for conflict in conflicts {
+        cprintln!("<yellow>conflict:</yellow> {}", conflict);
+    }
+}
+
+fn show_checklist(ChecklistView { id, uuid, name, .. }: &ChecklistView, uuids: bool) {
+    let id = label(id, uuid, uuids);
+    cprintln!("<dim>{}:</dim> {}", id, name)
+}
+
+fn show_item(
+    ItemView {
+        id,
+        uuid,
+        item,
+        checked,
+        ..
+    }: &ItemView,
+    uuids: bool,
+) {
+    let id = label(id, uuid, uuids);
+    if *checked {
+        cprintln!("<dim>{}:</dim> ☑ <strike>{}</strike>", id, item);
+    } else {
+        cprintln!("<dim>{}:</dim> ☐ {}", id, item);
+    }
 }
 
-fn show_item(Item { id, item, .. }: &Item, checked: bool) {
-    if checked {
-        cprintln!("<dim>{id:>6}:</dim> ☑ <strike>{item}</strike>");
+/// Format an id, and its uuid when requested, for display.
+fn label(id: impl std::fmt::Display, uuid: &Uuid, uuids: bool) -> String {
+    if uuids {
+        format!("{id:>6} {uuid}")
     } else {
-        cprintln!("<dim>{id:>6}:</dim> ☐ {item}");
+        format!("{id:>6}")
     }
 }
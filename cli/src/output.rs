@@ -0,0 +1,139 @@
+//! Printing the results of commands in the format chosen with `--format`.
+//!
+//! Records are the same views as `serve` and `rpc` return, so scripts see the same fields whichever
+//! interface they use:
+//!
+//! - `list show-all`, `new`, `remove` and `import-md` print checklists
+//! - `item show-all`, `new`, `remove` and `toggle` print items
+//! - `list edit` prints `renamed`, and `added`, `removed`, `edited`, `toggled` and `moved`, the
+//!   numbers of items changed
+//! - `import` prints `checklists` and `items`, the numbers imported
+//! - `sync <other>` prints `pulled`, `pushed`, `skipped` and `conflicts`
+//! - `sync apply` prints `inserted`, `updated`, `deleted`, `skipped` and `conflicts`
+//! - `sync status` prints `site`, `version` and `peers`
+//! - `db backup` prints `path`, that of the backup
+//! - `db decrypt`, `db rekey` and `db restore` print `path`, that of the decrypted copy or of the
+//!   database, except as text
+//!
+//! Checklists have `id`, `uuid`, `name` and `version`, and items have `id`, `uuid`, `checklist`,
+//! `item`, `checked` and `version`. `conflicts` are descriptions for people, and `peers` are
+//! objects with `site`, `received` and `acknowledged`. Removed records are printed as they were
+//! before removal. Other commands print documents in formats of their own, or nothing.
+//!
+//! Fields are only ever added. In CSV and TSV, columns are in the order listed, and arrays are
+//! written as JSON.
+
+use std::io::Write as _;
+
+use anyhow::Context as _;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    cli::OutputFormat,
+    views::{
+        ApplyView, ChecklistView, EditView, ImportView, ItemView, PathView, StatusView, SyncView,
+    },
+};
+
+/// A record which can be printed as a row.
+pub(crate) trait Record: Serialize {
+    /// Names of the fields, in the order of CSV and TSV columns
+    const FIELDS: &'static [&'static str];
+}
+
+impl Record for ChecklistView {
+    const FIELDS: &'static [&'static str] = &["id", "uuid", "name", "version"];
+}
+
+impl Record for ItemView {
+    const FIELDS: &'static [&'static str] =
+        &["id", "uuid", "checklist", "item", "checked", "version"];
+}
+
+impl Record for ImportView {
+    const FIELDS: &'static [&'static str] = &["checklists", "items"];
+}
+
+impl Record for EditView {
+    const FIELDS: &'static [&'static str] =
+        &["renamed", "added", "removed", "edited", "toggled", "moved"];
+}
+
+impl Record for SyncView {
+    const FIELDS: &'static [&'static str] = &["pulled", "pushed", "skipped", "conflicts"];
+}
+
+impl Record for ApplyView {
+    const FIELDS: &'static [&'static str] =
+        &["inserted", "updated", "deleted", "skipped", "conflicts"];
+}
+
+impl Record for StatusView {
+    const FIELDS: &'static [&'static str] = &["site", "version", "peers"];
+}
+
+impl Record for PathView {
+    const FIELDS: &'static [&'static str] = &["path"];
+}
+
+/// Print a single record, using `text` to print it for people.
+pub(crate) fn record<R: Record>(
+    format: OutputFormat,
+    record: &R,
+    text: impl FnOnce(&R),
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            text(record);
+            Ok(())
+        }
+        OutputFormat::Json | OutputFormat::Jsonl => json_line(record),
+        OutputFormat::Csv | OutputFormat::Tsv => table(format, std::slice::from_ref(record)),
+    }
+}
+
+/// Print a sequence of records, using `text` to print each for people.
+pub(crate) fn records<R: Record>(
+    format: OutputFormat,
+    records: &[R],
+    text: impl FnMut(&R),
+) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            records.iter().for_each(text);
+            Ok(())
+        }
+        OutputFormat::Json => json_line(&records),
+        OutputFormat::Jsonl => records.iter().try_for_each(json_line),
+        OutputFormat::Csv | OutputFormat::Tsv => table(format, records),
+    }
+}
+
+fn json_line(value: &impl Serialize) -> anyhow::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, value).context("encoding json")?;
+    writeln!(stdout).context("writing standard output")
+}
+
+fn table<R: Record>(format: OutputFormat, records: &[R]) -> anyhow::Result<()> {
+    let delimiter = if format == OutputFormat::Tsv {
+        b'\t'
+    } else {
+        b','
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(std::io::stdout().lock());
+    writer.write_record(R::FIELDS).context("writing header")?;
+    for record in records {
+        let value = serde_json::to_value(record).context("encoding record")?;
+        let row = R::FIELDS.iter().map(|field| match &value[field] {
+            Value::Null => String::new(),
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        });
+        writer.write_record(row).context("writing record")?;
+    }
+    writer.flush().context("writing standard output")
+}
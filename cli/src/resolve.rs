@@ -0,0 +1,40 @@
+//! Finding the checklists and items which commands are given, by id, uuid, name or `@last`.
+
+use checklist::{Checklist, ChecklistId, Db, Error, Item, ItemId, Result};
+
+use crate::cli::Ref;
+
+/// Load a checklist, failing with [`Error::MissingChecklist`] if there is none, or with
+/// [`Error::Ambiguous`] if its name could mean several.
+pub(crate) async fn checklist(db: &Db, checklist: &Ref<ChecklistId>) -> Result<Checklist> {
+    let checklist = match checklist {
+        Ref::Id(id) => Checklist::load(db, *id).await?,
+        Ref::Uuid(uuid) => Checklist::load_by_uuid(db, *uuid).await?,
+        Ref::Name(name) => Some(Checklist::find(db, name).await?),
+        Ref::Last => Checklist::last_changed(db).await?,
+    };
+    checklist.ok_or(Error::MissingChecklist)
+}
+
+/// Load an item, failing with [`Error::MissingItem`] if there is none, or with [`Error::Ambiguous`]
+/// if its text could mean several.
+///
+/// Text and `@last` are looked for among the items of `within`, the checklist in use, if there is one,
+/// and otherwise in every checklist. Ids and uuids may name an item of any checklist.
+pub(crate) async fn item(
+    db: &Db,
+    item: &Ref<ItemId>,
+    within: Option<&Ref<ChecklistId>>,
+) -> Result<Item> {
+    let within = match (item, within) {
+        (Ref::Name(_) | Ref::Last, Some(within)) => Some(checklist(db, within).await?.id),
+        _ => None,
+    };
+    let item = match item {
+        Ref::Id(id) => Item::load(db, *id).await?,
+        Ref::Uuid(uuid) => Item::load_by_uuid(db, *uuid).await?,
+        Ref::Name(name) => Some(Item::find(db, within, name).await?),
+        Ref::Last => Item::last_changed(db, within).await?,
+    };
+    item.ok_or(Error::MissingItem)
+}
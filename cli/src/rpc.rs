@@ -0,0 +1,581 @@
+//! JSON-RPC 2.0 over standard input and output, for `checklist rpc`.
+//!
+//! Each message is a single line of JSON: requests, notifications and batches on standard input, and
+//! responses on standard output. Requests are handled in order, and take their parameters by name.
+//! Checklists and items are given by integer id, or by a string holding an id, uuid, name or
+//! `@last`, as on the command line.
+//!
+//! | Method                    | Parameters                      | Result                                 |
+//! |---------------------------|---------------------------------|----------------------------------------|
+//! | `checklist.all`           |                                 | checklists                             |
+//! | `checklist.get`           | `checklist`                     | checklist                              |
+//! | `checklist.new`           | `name`                          | checklist                              |
+//! | `checklist.rename`        | `checklist`, `name`, `version`? | checklist                              |
+//! | `checklist.delete`        | `checklist`, `version`?         | `null`                                 |
+//! | `checklist.items`         | `checklist`                     | items                                  |
+//! | `checklist.to_markdown`   | `checklist`                     | string                                 |
+//! | `checklist.from_markdown` | `markdown`                      | checklist                              |
+//! | `item.get`                | `item`                          | item                                   |
+//! | `item.new`                | `checklist`, `text`             | item                                   |
+//! | `item.rename`             | `item`, `text`, `version`?      | item                                   |
+//! | `item.set_checked`        | `item`, `checked`, `version`?   | item                                   |
+//! | `item.toggle`             | `item`, `version`?              | item                                   |
+//! | `item.move`               | `item`, `index`, `version`?     | item                                   |
+//! | `item.delete`             | `item`, `version`?              | `null`                                 |
+//! | `item.search`             | `query`                         | items                                  |
+//! | `export`                  | `format`?                       | string                                 |
+//! | `import`                  | `input`, `format`?, `mode`?     | `{checklists, items}`                  |
+//! | `sync.status`             |                                 | `{site, version, peers}`               |
+//! | `sync.with`               | `other`                         | `{pulled, pushed, skipped, conflicts}` |
+//! | `db.backup`               | `output`?, `keep`?              | path of the backup                     |
+//!
+//! Checklists and items are the same objects as the REST API of `checklist serve` returns. A mutation
+//! given a `version` fails with a conflict if the record has changed since that version. Formats and
+//! import modes are named as on the command line. `sync.with` opens the other database with the
+//! options of this one.
+//!
+//! Whenever the database changes, whether through this process or another, a `changed` notification
+//! carries its new `version`.
+
+use std::{path::PathBuf, str::FromStr, sync::Arc};
+
+use anyhow::Context as _;
+use checklist::{
+    formats::{Document, ImportMode},
+    Checklist, ChecklistId, Db, Error, Item, ItemId,
+};
+use clap::ValueEnum as _;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader},
+    sync::mpsc,
+};
+
+use crate::{
+    changes,
+    cli::{Format, Ref},
+    resolve,
+    views::{ChecklistView, ImportView, ItemView, StatusView, SyncView},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+/// The checklist or item does not exist
+const NOT_FOUND: i64 = -32001;
+/// The record has changed since the requested version; `data` holds the current `version`
+const CONFLICT: i64 = -32002;
+/// The input, such as a document to import, is malformed
+const INVALID_INPUT: i64 = -32003;
+/// A name could mean several checklists or items; `data` holds their `candidates`
+const AMBIGUOUS: i64 = -32004;
+
+/// Serve JSON-RPC requests from standard input until it is closed.
+pub(crate) async fn run(db: Arc<Db>) -> anyhow::Result<()> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<Value>();
+
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(message) = rx.recv().await {
+            let mut line = message.to_string();
+            line.push('\n');
+            stdout.write_all(line.as_bytes()).await?;
+            stdout.flush().await?;
+        }
+        Ok::<_, std::io::Error>(())
+    });
+
+    let mut versions = changes::watch(db.clone())
+        .await
+        .context("getting database version")?;
+    let notifications = tx.clone();
+    let notifier = tokio::spawn(async move {
+        while versions.changed().await.is_ok() {
+            let version = *versions.borrow_and_update();
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "changed",
+                "params": { "version": version },
+            });
+            if notifications.send(notification).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await.context("reading standard input")? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_line(&db, &line).await {
+            if tx.send(response).is_err() {
+                break;
+            }
+        }
+    }
+
+    notifier.abort();
+    drop(tx);
+    writer
+        .await
+        .context("writing standard output")?
+        .context("writing standard output")
+}
+
+/// Handle a request, notification or batch, returning the response if there is one.
+async fn handle_line(db: &Db, line: &str) -> Option<Value> {
+    let message = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(err) => {
+            let error = RpcError::new(PARSE_ERROR, format!("parse error: {err}"));
+            return Some(response(Value::Null, Err(error)));
+        }
+    };
+
+    let Value::Array(batch) = message else {
+        return handle(db, message).await;
+    };
+    if batch.is_empty() {
+        let error = RpcError::new(INVALID_REQUEST, "empty batch");
+        return Some(response(Value::Null, Err(error)));
+    }
+    let mut responses = Vec::new();
+    for message in batch {
+        responses.extend(handle(db, message).await);
+    }
+    (!responses.is_empty()).then_some(Value::Array(responses))
+}
+
+/// Handle a single request or notification.
+async fn handle(db: &Db, message: Value) -> Option<Value> {
+    let Value::Object(mut request) = message else {
+        let error = RpcError::new(INVALID_REQUEST, "request must be an object");
+        return Some(response(Value::Null, Err(error)));
+    };
+
+    let id = request.remove("id");
+    let method = match (request.remove("jsonrpc"), request.remove("method")) {
+        (Some(version), Some(Value::String(method))) if version == "2.0" => method,
+        _ => {
+            let error = RpcError::new(
+                INVALID_REQUEST,
+                "request must have `\"jsonrpc\": \"2.0\"` and a `method`",
+            );
+            return Some(response(id.unwrap_or_default(), Err(error)));
+        }
+    };
+    let params = request.remove("params").unwrap_or_default();
+
+    let result = call(db, &method, params).await;
+    // notifications have no id, and get no response
+    id.map(|id| response(id, result))
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(RpcError {
+            code,
+            message,
+            data,
+        }) => {
+            let mut error = json!({ "code": code, "message": message });
+            if let Some(data) = data {
+                error["data"] = data;
+            }
+            json!({ "jsonrpc": "2.0", "id": id, "error": error })
+        }
+    }
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+    data: Option<Value>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<Error> for RpcError {
+    fn from(err: Error) -> Self {
+        let (code, data) = match &err {
+            Error::MissingChecklist | Error::MissingItem => (NOT_FOUND, None),
+            Error::Conflict { current } => (CONFLICT, Some(json!({ "version": current }))),
+            Error::InvalidUuid(_) | Error::InvalidDocument { .. } | Error::InvalidChangeset(_) => {
+                (INVALID_INPUT, None)
+            }
+            Error::Ambiguous { candidates, .. } => {
+                (AMBIGUOUS, Some(json!({ "candidates": candidates })))
+            }
+            _ => (INTERNAL_ERROR, None),
+        };
+        Self {
+            code,
+            message: err.to_string(),
+            data,
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(err: anyhow::Error) -> Self {
+        let message = format!("{err:#}");
+        match err.downcast::<Error>() {
+            Ok(err) => Self {
+                message,
+                ..err.into()
+            },
+            Err(_) => Self::new(INTERNAL_ERROR, message),
+        }
+    }
+}
+
+/// Parameters given by name. Absent parameters are treated as an empty object.
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        Value::Object(_) => params,
+        _ => return Err(RpcError::new(INVALID_PARAMS, "params must be an object")),
+    };
+    serde_json::from_value(params)
+        .map_err(|err| RpcError::new(INVALID_PARAMS, format!("invalid params: {err}")))
+}
+
+fn to_value(value: impl serde::Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value)
+        .map_err(|err| RpcError::new(INTERNAL_ERROR, format!("encoding result: {err}")))
+}
+
+fn parse<T: FromStr<Err = impl std::fmt::Display>>(value: &str) -> Result<T, RpcError> {
+    value
+        .parse()
+        .map_err(|err| RpcError::new(INVALID_PARAMS, format!("invalid params: {err}")))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChecklistParams {
+    checklist: Ref<ChecklistId>,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NameParams {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenameChecklistParams {
+    checklist: Ref<ChecklistId>,
+    name: String,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MarkdownParams {
+    markdown: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemParams {
+    item: Ref<ItemId>,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewItemParams {
+    checklist: Ref<ChecklistId>,
+    text: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RenameItemParams {
+    item: Ref<ItemId>,
+    text: String,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SetCheckedParams {
+    item: Ref<ItemId>,
+    checked: bool,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MoveParams {
+    item: Ref<ItemId>,
+    index: usize,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchParams {
+    query: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportParams {
+    #[serde(default)]
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImportParams {
+    input: String,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    mode: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SyncParams {
+    other: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BackupParams {
+    #[serde(default)]
+    output: Option<PathBuf>,
+    #[serde(default)]
+    keep: Option<usize>,
+}
+
+fn format(format: Option<String>) -> Result<Format, RpcError> {
+    match format {
+        Some(format) => Format::from_str(&format, true)
+            .map_err(|err| RpcError::new(INVALID_PARAMS, format!("invalid params: {err}"))),
+        None => Ok(Format::default()),
+    }
+}
+
+async fn checklist_view(db: &Db, id: ChecklistId) -> Result<Value, RpcError> {
+    let checklist = Checklist::load(db, id)
+        .await?
+        .ok_or(Error::MissingChecklist)?;
+    to_value(ChecklistView::from(checklist))
+}
+
+async fn item_view(db: &Db, id: ItemId) -> Result<Value, RpcError> {
+    let item = Item::load(db, id).await?.ok_or(Error::MissingItem)?;
+    to_value(ItemView::load(db, item).await?)
+}
+
+async fn call(db: &Db, method: &str, raw: Value) -> Result<Value, RpcError> {
+    match method {
+        "checklist.all" => {
+            let checklists = Checklist::all(db).await?;
+            to_value(
+                checklists
+                    .into_iter()
+                    .map(ChecklistView::from)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        "checklist.get" => {
+            let ChecklistParams { checklist, .. } = params(raw)?;
+            let checklist = resolve::checklist(db, &checklist).await?;
+            to_value(ChecklistView::from(checklist))
+        }
+        "checklist.new" => {
+            let NameParams { name } = params(raw)?;
+            to_value(ChecklistView::from(Checklist::new(db, &name).await?))
+        }
+        "checklist.rename" => {
+            let RenameChecklistParams {
+                checklist,
+                name,
+                version,
+            } = params(raw)?;
+            let checklist = resolve::checklist(db, &checklist).await?;
+            match version {
+                Some(expected) => {
+                    checklist.rename_if_version(db, &name, expected).await?;
+                }
+                None => checklist.rename(db, &name).await?,
+            }
+            checklist_view(db, checklist.id).await
+        }
+        "checklist.delete" => {
+            let ChecklistParams { checklist, version } = params(raw)?;
+            let checklist = resolve::checklist(db, &checklist).await?;
+            match version {
+                Some(expected) => Checklist::delete_if_version(db, checklist.id, expected).await?,
+                None => Checklist::delete(db, checklist.id).await?,
+            }
+            Ok(Value::Null)
+        }
+        "checklist.items" => {
+            let ChecklistParams { checklist, .. } = params(raw)?;
+            let checklist = resolve::checklist(db, &checklist).await?;
+            let items = checklist.items(db).await?;
+            to_value(ItemView::load_all(db, items).await?)
+        }
+        "checklist.to_markdown" => {
+            let ChecklistParams { checklist, .. } = params(raw)?;
+            let checklist = resolve::checklist(db, &checklist).await?;
+            Ok(Value::String(checklist.to_markdown(db).await?))
+        }
+        "checklist.from_markdown" => {
+            let MarkdownParams { markdown } = params(raw)?;
+            let checklist = Checklist::from_markdown(db, &markdown).await?;
+            to_value(ChecklistView::from(checklist))
+        }
+        "item.get" => {
+            let ItemParams { item, .. } = params(raw)?;
+            let item = resolve::item(db, &item, None).await?;
+            to_value(ItemView::load(db, item).await?)
+        }
+        "item.new" => {
+            let NewItemParams { checklist, text } = params(raw)?;
+            let checklist = resolve::checklist(db, &checklist).await?;
+            let item = Item::new(db, checklist.id, text).await?;
+            to_value(ItemView::load(db, item).await?)
+        }
+        "item.rename" => {
+            let RenameItemParams {
+                item,
+                text,
+                version,
+            } = params(raw)?;
+            let item = resolve::item(db, &item, None).await?;
+            match version {
+                Some(expected) => {
+                    item.rename_if_version(db, &text, expected).await?;
+                }
+                None => item.rename(db, &text).await?,
+            }
+            item_view(db, item.id).await
+        }
+        "item.set_checked" => {
+            let SetCheckedParams {
+                item,
+                checked,
+                version,
+            } = params(raw)?;
+            let item = resolve::item(db, &item, None).await?;
+            match version {
+                Some(expected) => {
+                    item.set_checked_if_version(db, checked, expected).await?;
+                }
+                None => item.set_checked(db, checked).await?,
+            }
+            item_view(db, item.id).await
+        }
+        "item.toggle" => {
+            let ItemParams { item, version } = params(raw)?;
+            let item = resolve::item(db, &item, None).await?;
+            // conditional on the version read, so that concurrent toggles cannot cancel out
+            let checked = item.is_set(db).await?;
+            item.set_checked_if_version(db, !checked, version.unwrap_or(item.version))
+                .await?;
+            item_view(db, item.id).await
+        }
+        "item.move" => {
+            let MoveParams {
+                item,
+                index,
+                version,
+            } = params(raw)?;
+            let item = resolve::item(db, &item, None).await?;
+            match version {
+                Some(expected) => {
+                    item.move_to_if_version(db, index, expected).await?;
+                }
+                None => item.move_to(db, index).await?,
+            }
+            item_view(db, item.id).await
+        }
+        "item.delete" => {
+            let ItemParams { item, version } = params(raw)?;
+            let item = resolve::item(db, &item, None).await?;
+            match version {
+                Some(expected) => Item::delete_if_version(db, item.id, expected).await?,
+                None => Item::delete(db, item.id).await?,
+            }
+            Ok(Value::Null)
+        }
+        "item.search" => {
+            let SearchParams { query } = params(raw)?;
+            let items = Item::search(db, &query).await?;
+            to_value(ItemView::load_all(db, items).await?)
+        }
+        "export" => {
+            let ExportParams { format: name } = params(raw)?;
+            let document = Document::export(db).await?;
+            Ok(Value::String(crate::encode(&document, format(name)?)?))
+        }
+        "import" => {
+            let ImportParams {
+                input,
+                format: name,
+                mode,
+            } = params(raw)?;
+            let format = format(name)?;
+            let mode = match mode {
+                Some(mode) => parse::<ImportMode>(&mode)?,
+                None => format.default_import_mode(),
+            };
+            let document = crate::decode(&input, format)?;
+            let summary = document.import(db, mode).await?;
+            to_value(ImportView::from(summary))
+        }
+        "sync.status" => {
+            params::<serde::de::IgnoredAny>(raw)?;
+            to_value(StatusView::load(db).await?)
+        }
+        "sync.with" => {
+            let SyncParams { other } = params(raw)?;
+            let other = Db::open(&other, db.options()).await?;
+            let report = db.sync_with(&other).await?;
+            to_value(SyncView::from(&report))
+        }
+        "db.backup" => {
+            let BackupParams { output, keep } = params(raw)?;
+            let output = match output {
+                Some(output) => {
+                    db.backup_to(&output).await?;
+                    output
+                }
+                None => db.backup_rotating(keep.unwrap_or(5)).await?,
+            };
+            Ok(Value::String(output.display().to_string()))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("method not found: {method}"),
+        )),
+    }
+}
@@ -0,0 +1,568 @@
+//! A REST API and web UI over a single database, for `checklist serve`.
+//!
+//! Every request must carry `Authorization: Bearer <token>`. Checklists and items are JSON objects
+//! which include their `version`; responses for a single record also give it as a strong `ETag`.
+//! Mutations honor `If-Match`, and fail with `412 Precondition Failed` if the record has changed
+//! since that version.
+//!
+//! - `GET /checklists` lists checklists, and `POST /checklists` creates one from `{"name": ...}`.
+//! - `GET`, `PATCH` and `DELETE /checklists/{checklist}` get, rename and delete a checklist. `PATCH`
+//!   takes `{"name": ...}`.
+//! - `GET /checklists/{checklist}/items` lists its items, and `POST` creates one from
+//!   `{"item": ...}`.
+//! - `GET`, `PATCH` and `DELETE /items/{item}` get, change and delete an item. `PATCH` takes
+//!   `{"item": ..., "checked": ...}`, where each field is optional.
+//! - `POST /items/{item}/toggle` toggles an item's check status.
+//! - `POST /items/{item}/move` moves an item to `{"index": ...}` among the items of its checklist.
+//! - `GET /search?q={text}` lists the items in any checklist whose text contains `text`, ignoring
+//!   ASCII case.
+//! - `GET /export?format={format}` writes every checklist in one of the formats of
+//!   `checklist export`, JSON by default.
+//! - `GET /events` is a stream of server-sent `changed` events, each carrying the database's
+//!   `version`, sent on connecting and whenever the database changes.
+//!
+//! The web UI is served from `/`, and is the only part which does not require the token.
+//!
+//! Checklists and items are identified in paths by id, uuid, name or `@last`, as on the command
+//! line. A name which could mean several records is a bad request.
+//!
+//! Errors are JSON objects with an `error` message, and a status derived from the underlying
+//! [`checklist::Error`].
+
+use std::{
+    fs::OpenOptions, io::Write as _, net::SocketAddr, os::unix::fs::OpenOptionsExt as _,
+    path::Path, str::FromStr, sync::Arc,
+};
+
+use anyhow::Context as _;
+use axum::{
+    extract::{Path as UrlPath, Query, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use checklist::{formats, Checklist, Db, Error, Item};
+use clap::ValueEnum as _;
+use futures_util::{stream, Stream, StreamExt as _};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::{
+    changes,
+    cli::{Format, Ref},
+    resolve,
+    views::{ChecklistView, ItemView},
+    web,
+};
+
+struct AppState {
+    db: Arc<Db>,
+    token: String,
+    /// The database version, for event streams
+    versions: watch::Receiver<u64>,
+    /// Becomes true when the server is shutting down
+    shutdown: watch::Receiver<bool>,
+}
+
+type SharedState = State<Arc<AppState>>;
+
+/// Serve the REST API and web UI for `db` on `listen` until interrupted.
+///
+/// Prints the address once listening, which tells callers the port when `listen` gives port 0.
+pub(crate) async fn run(db: Arc<Db>, listen: SocketAddr, token: String) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .context("binding listen address")?;
+    let address = listener.local_addr().context("getting listen address")?;
+    println!("listening on http://{address}");
+
+    let versions = changes::watch(db.clone())
+        .await
+        .context("getting database version")?;
+    let (stop, shutdown) = watch::channel(false);
+    let state = Arc::new(AppState {
+        db,
+        token,
+        versions,
+        shutdown,
+    });
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async move {
+            let _ = tokio::signal::ctrl_c().await;
+            // ends event streams, which would otherwise hold the server open
+            let _ = stop.send(true);
+        })
+        .await
+        .context("serving http")
+}
+
+/// Read the bearer token from `path`, first writing a random one there if it does not exist.
+pub(crate) fn load_token(path: &Path) -> anyhow::Result<String> {
+    if !path.exists() {
+        let mut bytes = [0; 32];
+        getrandom::getrandom(&mut bytes).context("generating token")?;
+        let token: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("creating token directory")?;
+        }
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(path)
+            .context("creating token file")?;
+        writeln!(file, "{token}").context("writing token file")?;
+        eprintln!("wrote a new access token to {}", path.display());
+    }
+
+    let token = std::fs::read_to_string(path).context("reading token file")?;
+    let token = token.trim();
+    anyhow::ensure!(!token.is_empty(), "token file {} is empty", path.display());
+    Ok(token.to_owned())
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/checklists", get(list_checklists).post(create_checklist))
+        .route(
+            "/checklists/{checklist}",
+            get(get_checklist)
+                .patch(rename_checklist)
+                .delete(delete_checklist),
+        )
+        .route(
+            "/checklists/{checklist}/items",
+            get(list_items).post(create_item),
+        )
+        .route(
+            "/items/{item}",
+            get(get_item).patch(update_item).delete(delete_item),
+        )
+        .route("/items/{item}/toggle", post(toggle_item))
+        .route("/items/{item}/move", post(move_item))
+        .route("/search", get(search))
+        .route("/export", get(export))
+        .route("/events", get(events))
+        .route_layer(middleware::from_fn_with_state(state.clone(), authenticate))
+        // the web UI's assets hold no data, and it asks for the token itself
+        .merge(web::router())
+        .with_state(state)
+}
+
+/// Reject requests which do not carry the bearer token.
+async fn authenticate(State(state): SharedState, request: Request, next: Next) -> Response {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if presented.is_some_and(|presented| constant_time_eq(presented, &state.token)) {
+        return next.run(request).await;
+    }
+
+    let mut response = ApiError::Unauthorized.into_response();
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// Compare secrets in time which depends only on their lengths.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+enum ApiError {
+    Db(Error),
+    BadRequest(String),
+    Unauthorized,
+    Internal(anyhow::Error),
+}
+
+impl From<Error> for ApiError {
+    fn from(err: Error) -> Self {
+        Self::Db(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            Self::Db(Error::MissingChecklist | Error::MissingItem) => StatusCode::NOT_FOUND,
+            Self::Db(Error::Conflict { .. }) => StatusCode::PRECONDITION_FAILED,
+            Self::Db(
+                Error::InvalidUuid(_)
+                | Error::InvalidDocument { .. }
+                | Error::InvalidChangeset(_)
+                | Error::Ambiguous { .. },
+            )
+            | Self::BadRequest(_) => StatusCode::BAD_REQUEST,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::Db(_) | Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let current = match &self {
+            Self::Db(Error::Conflict { current }) => Some(*current),
+            _ => None,
+        };
+        let error = match self {
+            Self::Db(err) => err.to_string(),
+            Self::BadRequest(message) => message,
+            Self::Unauthorized => "missing or incorrect bearer token".to_owned(),
+            Self::Internal(err) => format!("{err:#}"),
+        };
+
+        let mut response = (status, Json(serde_json::json!({ "error": error }))).into_response();
+        if let Some(current) = current {
+            response.headers_mut().insert(header::ETAG, etag(current));
+        }
+        response
+    }
+}
+
+type ApiResult<T = Response> = Result<T, ApiError>;
+
+fn etag(version: u64) -> HeaderValue {
+    HeaderValue::from_str(&format!("\"{version}\""))
+        .expect("quoted integers are valid header values")
+}
+
+/// A single record, with its version as its `ETag`.
+fn record(status: StatusCode, version: u64, body: impl Serialize) -> Response {
+    let mut response = (status, Json(body)).into_response();
+    response.headers_mut().insert(header::ETAG, etag(version));
+    response
+}
+
+/// A newly created record, with its location.
+fn created(location: String, version: u64, body: impl Serialize) -> Response {
+    let mut response = record(StatusCode::CREATED, version, body);
+    if let Ok(location) = HeaderValue::from_str(&location) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    response
+}
+
+/// The version required by the `If-Match` header, if any.
+///
+/// `If-Match: *` only requires that the record exists, which every mutation checks anyway.
+fn if_match(headers: &HeaderMap) -> ApiResult<Option<u64>> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+    let value = value.to_str().unwrap_or_default().trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .and_then(|value| value.parse().ok())
+        .map(Some)
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!("If-Match must be a single ETag, not {value:?}"))
+        })
+}
+
+fn parse_ref<Id: FromStr>(reference: &str) -> ApiResult<Ref<Id>> {
+    reference.parse().map_err(ApiError::BadRequest)
+}
+
+async fn load_checklist(db: &Db, checklist: &str) -> ApiResult<Checklist> {
+    Ok(resolve::checklist(db, &parse_ref(checklist)?).await?)
+}
+
+async fn load_item(db: &Db, item: &str) -> ApiResult<Item> {
+    Ok(resolve::item(db, &parse_ref(item)?, None).await?)
+}
+
+async fn list_checklists(State(state): SharedState) -> ApiResult<Json<Vec<ChecklistView>>> {
+    let checklists = Checklist::all(&state.db).await?;
+    Ok(Json(checklists.into_iter().map(Into::into).collect()))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChecklistFields {
+    name: String,
+}
+
+async fn create_checklist(
+    State(state): SharedState,
+    Json(ChecklistFields { name }): Json<ChecklistFields>,
+) -> ApiResult {
+    let checklist = Checklist::new(&state.db, &name).await?;
+    Ok(created(
+        format!("/checklists/{}", checklist.id),
+        checklist.version,
+        ChecklistView::from(checklist),
+    ))
+}
+
+async fn get_checklist(
+    State(state): SharedState,
+    UrlPath(checklist): UrlPath<String>,
+) -> ApiResult {
+    let checklist = load_checklist(&state.db, &checklist).await?;
+    Ok(record(
+        StatusCode::OK,
+        checklist.version,
+        ChecklistView::from(checklist),
+    ))
+}
+
+async fn rename_checklist(
+    State(state): SharedState,
+    UrlPath(checklist): UrlPath<String>,
+    headers: HeaderMap,
+    Json(ChecklistFields { name }): Json<ChecklistFields>,
+) -> ApiResult {
+    let db = &state.db;
+    let checklist = load_checklist(db, &checklist).await?;
+    match if_match(&headers)? {
+        Some(expected) => {
+            checklist.rename_if_version(db, &name, expected).await?;
+        }
+        None => checklist.rename(db, &name).await?,
+    }
+
+    let checklist = Checklist::load(db, checklist.id)
+        .await?
+        .ok_or(Error::MissingChecklist)?;
+    Ok(record(
+        StatusCode::OK,
+        checklist.version,
+        ChecklistView::from(checklist),
+    ))
+}
+
+async fn delete_checklist(
+    State(state): SharedState,
+    UrlPath(checklist): UrlPath<String>,
+    headers: HeaderMap,
+) -> ApiResult<StatusCode> {
+    let db = &state.db;
+    let checklist = load_checklist(db, &checklist).await?;
+    match if_match(&headers)? {
+        Some(expected) => Checklist::delete_if_version(db, checklist.id, expected).await?,
+        None => Checklist::delete(db, checklist.id).await?,
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_items(
+    State(state): SharedState,
+    UrlPath(checklist): UrlPath<String>,
+) -> ApiResult<Json<Vec<ItemView>>> {
+    let db = &state.db;
+    let checklist = load_checklist(db, &checklist).await?;
+    let items = checklist.items(db).await?;
+    Ok(Json(ItemView::load_all(db, items).await?))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewItemFields {
+    item: String,
+}
+
+async fn create_item(
+    State(state): SharedState,
+    UrlPath(checklist): UrlPath<String>,
+    Json(NewItemFields { item }): Json<NewItemFields>,
+) -> ApiResult {
+    let db = &state.db;
+    let checklist = load_checklist(db, &checklist).await?;
+    let item = Item::new(db, checklist.id, item).await?;
+    Ok(created(
+        format!("/items/{}", item.id),
+        item.version,
+        ItemView::load(db, item).await?,
+    ))
+}
+
+async fn get_item(State(state): SharedState, UrlPath(item): UrlPath<String>) -> ApiResult {
+    let db = &state.db;
+    let item = load_item(db, &item).await?;
+    Ok(record(
+        StatusCode::OK,
+        item.version,
+        ItemView::load(db, item).await?,
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemFields {
+    item: Option<String>,
+    checked: Option<bool>,
+}
+
+/// Change an item's text and check status. Each given field is changed in turn, so with `If-Match`
+/// the second change is conditional on the version left by the first.
+async fn update_item(
+    State(state): SharedState,
+    UrlPath(item): UrlPath<String>,
+    headers: HeaderMap,
+    Json(fields): Json<ItemFields>,
+) -> ApiResult {
+    let db = &state.db;
+    let item = load_item(db, &item).await?;
+    let mut expected = if_match(&headers)?;
+
+    if let Some(text) = &fields.item {
+        match expected {
+            Some(version) => expected = Some(item.rename_if_version(db, text, version).await?),
+            None => item.rename(db, text).await?,
+        }
+    }
+    if let Some(checked) = fields.checked {
+        match expected {
+            Some(version) => {
+                item.set_checked_if_version(db, checked, version).await?;
+            }
+            None => item.set_checked(db, checked).await?,
+        }
+    }
+
+    let item = Item::load(db, item.id).await?.ok_or(Error::MissingItem)?;
+    Ok(record(
+        StatusCode::OK,
+        item.version,
+        ItemView::load(db, item).await?,
+    ))
+}
+
+async fn delete_item(
+    State(state): SharedState,
+    UrlPath(item): UrlPath<String>,
+    headers: HeaderMap,
+) -> ApiResult<StatusCode> {
+    let db = &state.db;
+    let item = load_item(db, &item).await?;
+    match if_match(&headers)? {
+        Some(expected) => Item::delete_if_version(db, item.id, expected).await?,
+        None => Item::delete(db, item.id).await?,
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Toggle an item's check status. This is always conditional on the version which the toggle read,
+/// or on `If-Match` if given, so that concurrent toggles cannot cancel out.
+async fn toggle_item(
+    State(state): SharedState,
+    UrlPath(item): UrlPath<String>,
+    headers: HeaderMap,
+) -> ApiResult {
+    let db = &state.db;
+    let item = load_item(db, &item).await?;
+    let expected = if_match(&headers)?.unwrap_or(item.version);
+    let checked = item.is_set(db).await?;
+    item.set_checked_if_version(db, !checked, expected).await?;
+
+    let item = Item::load(db, item.id).await?.ok_or(Error::MissingItem)?;
+    Ok(record(
+        StatusCode::OK,
+        item.version,
+        ItemView::load(db, item).await?,
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MoveFields {
+    index: usize,
+}
+
+async fn move_item(
+    State(state): SharedState,
+    UrlPath(item): UrlPath<String>,
+    headers: HeaderMap,
+    Json(MoveFields { index }): Json<MoveFields>,
+) -> ApiResult {
+    let db = &state.db;
+    let item = load_item(db, &item).await?;
+    match if_match(&headers)? {
+        Some(expected) => {
+            item.move_to_if_version(db, index, expected).await?;
+        }
+        None => item.move_to(db, index).await?,
+    }
+
+    let item = Item::load(db, item.id).await?.ok_or(Error::MissingItem)?;
+    Ok(record(
+        StatusCode::OK,
+        item.version,
+        ItemView::load(db, item).await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+async fn search(
+    State(state): SharedState,
+    Query(SearchQuery { q }): Query<SearchQuery>,
+) -> ApiResult<Json<Vec<ItemView>>> {
+    let db = &state.db;
+    let items = Item::search(db, &q).await?;
+    Ok(Json(ItemView::load_all(db, items).await?))
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+}
+
+async fn export(
+    State(state): SharedState,
+    Query(ExportQuery { format }): Query<ExportQuery>,
+) -> ApiResult {
+    let format = match format {
+        Some(format) => Format::from_str(&format, true).map_err(ApiError::BadRequest)?,
+        None => Format::default(),
+    };
+    let document = formats::Document::export(&state.db).await?;
+    let exported = crate::encode(&document, format).map_err(ApiError::Internal)?;
+
+    let content_type = match format {
+        Format::Json => "application/json",
+        Format::Todotxt => "text/plain; charset=utf-8",
+        Format::Org => "text/org; charset=utf-8",
+        Format::Csv => "text/csv; charset=utf-8",
+        Format::Ics => "text/calendar; charset=utf-8",
+    };
+    Ok(([(header::CONTENT_TYPE, content_type)], exported).into_response())
+}
+
+/// Stream a `changed` event carrying the database version whenever the database changes, starting
+/// with the current version.
+async fn events(State(state): SharedState) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let mut versions = state.versions.clone();
+    versions.mark_changed();
+    let mut shutdown = state.shutdown.clone();
+
+    let events = stream::unfold(versions, |mut versions| async move {
+        versions.changed().await.ok()?;
+        let version = *versions.borrow_and_update();
+        let event = Event::default()
+            .event("changed")
+            .json_data(serde_json::json!({ "version": version }));
+        Some((event, versions))
+    });
+    Sse::new(events.take_until(async move {
+        let _ = shutdown.wait_for(|stopping| *stopping).await;
+    }))
+    .keep_alive(KeepAlive::default())
+}
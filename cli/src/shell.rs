@@ -0,0 +1,249 @@
+//! An interactive shell which runs commands against a database kept open between them.
+//!
+//! Lines are split like a POSIX shell would split them and parsed with the same grammar as the
+//! command line, plus `use <checklist>` and `exit`. Opening an encrypted database derives its key,
+//! which is slow by design, so this is much quicker than running `checklist` for each command.
+
+use std::{io::ErrorKind, path::Path, sync::Arc};
+
+use anyhow::Context as _;
+use checklist::{Checklist, ChecklistId, Db, ItemId};
+use clap::{CommandFactory as _, Parser as _};
+use rustyline::{
+    completion::{Completer, FilenameCompleter, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Editor, Helper,
+};
+
+use crate::{
+    cli::{Cli, Ref, Shell, ShellCommand, ShellLine, UseChecklist},
+    resolve,
+};
+
+pub(crate) async fn run(cli: &Cli, path: &Path, db: Arc<Db>, shell: &Shell) -> anyhow::Result<()> {
+    let mut editor =
+        Editor::<Completion, DefaultHistory>::new().context("setting up line editor")?;
+    editor.set_helper(Some(Completion::default()));
+
+    let history = (!shell.no_history).then(|| path.with_extension("history"));
+    if let Some(history) = &history {
+        match editor.load_history(history) {
+            Err(ReadlineError::Io(err)) if err.kind() == ErrorKind::NotFound => {}
+            result => result.context("reading shell history")?,
+        }
+    }
+
+    let mut using: Option<Checklist> = None;
+    loop {
+        // the checklist in use may have been renamed or deleted, here or elsewhere
+        if let Some(checklist) = &using {
+            using = Checklist::load(&db, checklist.id)
+                .await
+                .context("reloading checklist in use")?;
+            if using.is_none() {
+                cprintln!("<yellow>the checklist in use has been deleted</yellow>");
+            }
+        }
+        let completion = editor.helper_mut().expect("helper is set");
+        completion
+            .reload(&db, using.as_ref().map(|checklist| checklist.id))
+            .await;
+
+        let prompt = match &using {
+            Some(checklist) => format!("{}> ", checklist.name),
+            None => "checklist> ".to_owned(),
+        };
+        let line = match tokio::task::block_in_place(|| editor.readline(&prompt)) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err).context("reading command"),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor
+            .add_history_entry(line)
+            .context("adding to shell history")?;
+        if let Some(history) = &history {
+            editor
+                .save_history(history)
+                .context("writing shell history")?;
+        }
+
+        let words = match shell_words::split(line) {
+            Ok(words) => words,
+            Err(err) => {
+                ceprintln!("<red,bold>error:</> {}", err);
+                continue;
+            }
+        };
+        let command = match ShellLine::try_parse_from(&words) {
+            Ok(line) => line.command,
+            Err(err) => {
+                err.print().context("writing usage")?;
+                continue;
+            }
+        };
+        let result = match command {
+            ShellCommand::Noun(noun) => {
+                let checklist = using.as_ref().map(|checklist| Ref::Id(checklist.id));
+                let checklist = checklist.as_ref().or(cli.checklist());
+                crate::execute(cli, path, &db, &noun, checklist).await
+            }
+            ShellCommand::Use(UseChecklist { checklist: None }) => {
+                using = None;
+                Ok(())
+            }
+            ShellCommand::Use(UseChecklist {
+                checklist: Some(checklist),
+            }) => resolve::checklist(&db, &checklist)
+                .await
+                .map(|checklist| using = Some(checklist))
+                .context("getting checklist"),
+            ShellCommand::Exit => break,
+        };
+        if let Err(err) = result {
+            ceprintln!("<red,bold>error:</> {:#}", err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Completion of commands from the grammar, and of checklists, items and paths as their
+/// arguments.
+struct Completion {
+    commands: clap::Command,
+    checklists: Vec<(ChecklistId, String)>,
+    items: Vec<(ItemId, String)>,
+    files: FilenameCompleter,
+}
+
+impl Default for Completion {
+    fn default() -> Self {
+        Self {
+            commands: ShellLine::command(),
+            checklists: Vec::new(),
+            items: Vec::new(),
+            files: FilenameCompleter::new(),
+        }
+    }
+}
+
+impl Completion {
+    /// Load the checklists, and the items of the checklist in use or else of every checklist.
+    ///
+    /// Completion is a nicety, so failures leave it with what it had.
+    async fn reload(&mut self, db: &Db, using: Option<ChecklistId>) {
+        let Ok(checklists) = Checklist::all(db).await else {
+            return;
+        };
+        let mut items = Vec::new();
+        for checklist in &checklists {
+            if using.is_some_and(|id| id != checklist.id) {
+                continue;
+            }
+            let Ok(these) = checklist.items(db).await else {
+                return;
+            };
+            items.extend(these.into_iter().map(|item| (item.id, item.item)));
+        }
+        self.checklists = checklists
+            .into_iter()
+            .map(|checklist| (checklist.id, checklist.name))
+            .collect();
+        self.items = items;
+    }
+}
+
+/// Candidates whose id or name starts with `prefix`, replaced by their id.
+fn candidates<'a, Id: std::fmt::Display + 'a>(
+    records: impl IntoIterator<Item = &'a (Id, String)>,
+    prefix: &str,
+) -> Vec<Pair> {
+    let prefix = prefix.to_lowercase();
+    records
+        .into_iter()
+        .map(|(id, name)| (id.to_string(), name))
+        .filter(|(id, name)| id.starts_with(&prefix) || name.to_lowercase().starts_with(&prefix))
+        .map(|(id, name)| Pair {
+            display: format!("{id:>6}: {name}"),
+            replacement: id,
+        })
+        .collect()
+}
+
+impl Completer for Completion {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let Ok(before) = shell_words::split(&line[..start]) else {
+            return Ok((pos, Vec::new()));
+        };
+        let word = &line[start..pos];
+
+        // follow the subcommands given so far, counting the positional arguments after them
+        let mut path = Vec::new();
+        let mut command = &self.commands;
+        let mut positionals = 0;
+        for arg in &before {
+            match command.find_subcommand(arg) {
+                Some(subcommand) if positionals == 0 => {
+                    path.push(subcommand.get_name());
+                    command = subcommand;
+                }
+                _ if arg.starts_with('-') => {}
+                _ => positionals += 1,
+            }
+        }
+
+        if command.has_subcommands() && positionals == 0 {
+            let names = command
+                .get_subcommands()
+                .flat_map(|subcommand| {
+                    std::iter::once(subcommand.get_name()).chain(subcommand.get_visible_aliases())
+                })
+                .filter(|name| name.starts_with(word))
+                .map(|name| Pair {
+                    display: name.to_owned(),
+                    replacement: format!("{name} "),
+                })
+                .collect();
+            return Ok((start, names));
+        }
+
+        let arg = command
+            .get_positionals()
+            .nth(positionals)
+            .map(|arg| arg.get_id().as_str());
+        match (path.first().copied(), arg) {
+            (Some("list"), Some("id"))
+            | (Some("item"), Some("checklist_id"))
+            | (Some("use"), _) => Ok((start, candidates(&self.checklists, word))),
+            (Some("item"), Some("id")) => Ok((start, candidates(&self.items, word))),
+            _ => self.files.complete(line, pos, ctx),
+        }
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
@@ -0,0 +1,721 @@
+//! A full-screen terminal interface for working through checklists.
+//!
+//! The checklists are on the left and the items of the selected one on the right. Keys follow vim:
+//!
+//! | key                 | action                                              |
+//! |---------------------|-----------------------------------------------------|
+//! | `j`/`k`, arrows     | select the next or previous entry                   |
+//! | `g`/`G`             | select the first or last entry                      |
+//! | `h`/`l`, `Tab`      | switch between the checklists and items             |
+//! | `space`             | check or uncheck the selected item                  |
+//! | `a`/`o`             | add a checklist or item                             |
+//! | `e`/`i`             | edit the name of the checklist or text of the item  |
+//! | `d`                 | delete the selected checklist or item, once agreed  |
+//! | `J`/`K`             | move the selected item down or up                   |
+//! | `f`                 | show all items, only open ones, or only done ones   |
+//! | `/`                 | search checklist names and items                    |
+//! | `Esc`               | clear the search                                    |
+//! | `q`                 | quit                                                |
+//!
+//! Changes made by other processes are shown as they happen. Edits are conditional on the version
+//! of the record shown, so that they never overwrite changes made elsewhere in the meantime.
+
+use std::{io::IsTerminal as _, sync::Arc};
+
+use anyhow::Context as _;
+use checklist::{Checklist, ChecklistId, Db, Error, Item, ItemId};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt as _;
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Style, Stylize as _},
+    text::{Line, Span},
+    widgets::{Block, LineGauge, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use tokio::sync::watch;
+
+use crate::changes;
+
+pub(crate) async fn run(db: Arc<Db>) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        std::io::stdin().is_terminal() && std::io::stdout().is_terminal(),
+        "the terminal UI needs an interactive terminal"
+    );
+
+    let mut versions = changes::watch(db.clone())
+        .await
+        .context("getting database version")?;
+    let mut app = App {
+        lists: load(&db).await.context("loading checklists")?,
+        ..App::default()
+    };
+    app.clamp();
+
+    let mut terminal = ratatui::try_init().context("setting up terminal")?;
+    let result = app.run(&db, &mut terminal, &mut versions).await;
+    let restored = ratatui::try_restore().context("restoring terminal");
+    result.and(restored)
+}
+
+/// A checklist and its items, in order.
+struct Entry {
+    checklist: Checklist,
+    items: Vec<Row>,
+}
+
+struct Row {
+    item: Item,
+    checked: bool,
+}
+
+async fn load(db: &Db) -> checklist::Result<Vec<Entry>> {
+    let mut lists = Vec::new();
+    for checklist in Checklist::all(db).await? {
+        let mut items = Vec::new();
+        for item in checklist.items(db).await? {
+            let checked = item.is_set(db).await?;
+            items.push(Row { item, checked });
+        }
+        lists.push(Entry { checklist, items });
+    }
+    Ok(lists)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Pane {
+    #[default]
+    Checklists,
+    Items,
+}
+
+/// Which items are shown, by their check status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Filter {
+    #[default]
+    All,
+    Open,
+    Done,
+}
+
+impl Filter {
+    fn next(self) -> Self {
+        match self {
+            Self::All => Self::Open,
+            Self::Open => Self::Done,
+            Self::Done => Self::All,
+        }
+    }
+
+    fn accepts(self, checked: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::Open => !checked,
+            Self::Done => checked,
+        }
+    }
+}
+
+#[derive(Default)]
+enum Mode {
+    #[default]
+    Normal,
+    Input {
+        purpose: Purpose,
+        line: LineEdit,
+    },
+    /// Waiting for agreement to delete
+    Confirm(Target),
+}
+
+/// What the text being typed is for
+enum Purpose {
+    NewChecklist,
+    NewItem(ChecklistId),
+    RenameChecklist { id: ChecklistId, version: u64 },
+    RenameItem { id: ItemId, version: u64 },
+    Search,
+}
+
+impl Purpose {
+    fn prompt(&self) -> &'static str {
+        match self {
+            Self::NewChecklist => "New checklist: ",
+            Self::NewItem(_) => "New item: ",
+            Self::RenameChecklist { .. } => "Rename checklist: ",
+            Self::RenameItem { .. } => "Edit item: ",
+            Self::Search => "/",
+        }
+    }
+}
+
+enum Target {
+    Checklist {
+        id: ChecklistId,
+        version: u64,
+        name: String,
+    },
+    Item {
+        id: ItemId,
+        version: u64,
+        text: String,
+    },
+}
+
+/// A single line of text being edited, with the cursor at a byte offset.
+#[derive(Default)]
+struct LineEdit {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEdit {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            cursor: text.len(),
+        }
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.text[..self.cursor]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..]
+            .chars()
+            .next()
+            .map_or(self.cursor, |c| self.cursor + c.len_utf8())
+    }
+
+    /// Apply an editing key, ignoring any other.
+    fn key(&mut self, key: KeyEvent) {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.text.len(),
+            KeyCode::Char('u') if ctrl => {
+                self.text.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            KeyCode::Backspace => {
+                let start = self.prev_boundary();
+                self.text.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            KeyCode::Delete => {
+                let end = self.next_boundary();
+                self.text.drain(self.cursor..end);
+            }
+            KeyCode::Left => self.cursor = self.prev_boundary(),
+            KeyCode::Right => self.cursor = self.next_boundary(),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct App {
+    lists: Vec<Entry>,
+    pane: Pane,
+    /// Selection among the visible checklists
+    list_state: ListState,
+    /// Selection among the visible items of the selected checklist
+    item_state: ListState,
+    filter: Filter,
+    search: String,
+    mode: Mode,
+    /// The outcome of the last action, shown until the next key
+    message: Option<String>,
+    quit: bool,
+}
+
+impl App {
+    async fn run(
+        &mut self,
+        db: &Db,
+        terminal: &mut DefaultTerminal,
+        versions: &mut watch::Receiver<u64>,
+    ) -> anyhow::Result<()> {
+        let mut events = EventStream::new();
+        while !self.quit {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .context("drawing terminal")?;
+            tokio::select! {
+                event = events.next() => match event {
+                    Some(event) => {
+                        if let Event::Key(key) = event.context("reading terminal input")? {
+                            if key.kind == KeyEventKind::Press {
+                                self.key(db, key).await;
+                            }
+                        }
+                    }
+                    None => break,
+                },
+                Ok(()) = versions.changed() => self.reload(db).await,
+            }
+        }
+        Ok(())
+    }
+
+    /// Load everything again, keeping the same checklist and item selected.
+    async fn reload(&mut self, db: &Db) {
+        let checklist = self.current().map(|entry| entry.checklist.id);
+        let item = self.current_row().map(|row| row.item.id);
+        match load(db).await {
+            Ok(lists) => self.lists = lists,
+            Err(err) => self.message = Some(format!("reloading: {err}")),
+        }
+        self.select(checklist, item);
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        self.search.is_empty() || text.to_lowercase().contains(&self.search.to_lowercase())
+    }
+
+    /// Indices of the checklists which match the search, by name or by any of their items.
+    fn visible_lists(&self) -> Vec<usize> {
+        (0..self.lists.len())
+            .filter(|&i| {
+                let entry = &self.lists[i];
+                self.matches(&entry.checklist.name)
+                    || entry.items.iter().any(|row| self.matches(&row.item.item))
+            })
+            .collect()
+    }
+
+    /// Indices of the items of the selected checklist which pass the filter and search.
+    fn visible_items(&self) -> Vec<usize> {
+        let Some(entry) = self.current() else {
+            return Vec::new();
+        };
+        let whole_list = !self.search.is_empty() && self.matches(&entry.checklist.name);
+        (0..entry.items.len())
+            .filter(|&i| {
+                let row = &entry.items[i];
+                self.filter.accepts(row.checked) && (whole_list || self.matches(&row.item.item))
+            })
+            .collect()
+    }
+
+    fn current(&self) -> Option<&Entry> {
+        let index = *self.visible_lists().get(self.list_state.selected()?)?;
+        Some(&self.lists[index])
+    }
+
+    fn current_row(&self) -> Option<&Row> {
+        let index = *self.visible_items().get(self.item_state.selected()?)?;
+        Some(&self.current()?.items[index])
+    }
+
+    /// Select the given checklist and item where they are visible, or else stay near the current
+    /// selection.
+    fn select(&mut self, checklist: Option<ChecklistId>, item: Option<ItemId>) {
+        if let Some(id) = checklist {
+            let visible = self.visible_lists();
+            if let Some(i) = visible
+                .iter()
+                .position(|&i| self.lists[i].checklist.id == id)
+            {
+                self.list_state.select(Some(i));
+            }
+        }
+        if let Some(id) = item {
+            let visible = self.visible_items();
+            if let Some(entry) = self.current() {
+                if let Some(i) = visible.iter().position(|&i| entry.items[i].item.id == id) {
+                    self.item_state.select(Some(i));
+                }
+            }
+        }
+        self.clamp();
+    }
+
+    /// Keep the selections within the visible entries.
+    fn clamp(&mut self) {
+        fn clamp(state: &mut ListState, len: usize) {
+            state.select(match len {
+                0 => None,
+                len => Some(state.selected().unwrap_or(0).min(len - 1)),
+            });
+        }
+        let lists = self.visible_lists().len();
+        clamp(&mut self.list_state, lists);
+        let items = self.visible_items().len();
+        clamp(&mut self.item_state, items);
+    }
+
+    async fn key(&mut self, db: &Db, key: KeyEvent) {
+        self.message = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match std::mem::take(&mut self.mode) {
+            Mode::Normal => self.normal_key(db, key).await,
+            Mode::Input { purpose, mut line } => match key.code {
+                KeyCode::Enter => self.submit(db, purpose, line.text.trim()).await,
+                KeyCode::Esc => {
+                    if let Purpose::Search = purpose {
+                        self.search.clear();
+                        self.clamp();
+                    }
+                }
+                _ => {
+                    line.key(key);
+                    if let Purpose::Search = purpose {
+                        let current = self.current().map(|entry| entry.checklist.id);
+                        self.search.clone_from(&line.text);
+                        self.select(current, None);
+                    }
+                    self.mode = Mode::Input { purpose, line };
+                }
+            },
+            Mode::Confirm(target) => {
+                if let KeyCode::Char('y' | 'Y') = key.code {
+                    let result = match target {
+                        Target::Checklist { id, version, .. } => {
+                            Checklist::delete_if_version(db, id, version).await
+                        }
+                        Target::Item { id, version, .. } => {
+                            Item::delete_if_version(db, id, version).await
+                        }
+                    };
+                    self.finish(db, result).await;
+                }
+            }
+        }
+    }
+
+    async fn normal_key(&mut self, db: &Db, key: KeyEvent) {
+        match (key.code, self.pane) {
+            (KeyCode::Char('q'), _) => self.quit = true,
+            (KeyCode::Esc, _) => {
+                self.search.clear();
+                self.clamp();
+            }
+            (KeyCode::Tab | KeyCode::BackTab, Pane::Checklists)
+            | (KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter, _)
+                if self.current().is_some() =>
+            {
+                self.pane = Pane::Items;
+            }
+            (KeyCode::Tab | KeyCode::BackTab, Pane::Items)
+            | (KeyCode::Char('h') | KeyCode::Left, _) => self.pane = Pane::Checklists,
+            (KeyCode::Char('j') | KeyCode::Down, _) => self.step(1),
+            (KeyCode::Char('k') | KeyCode::Up, _) => self.step(-1),
+            (KeyCode::Char('g') | KeyCode::Home, _) => self.step(isize::MIN),
+            (KeyCode::Char('G') | KeyCode::End, _) => self.step(isize::MAX),
+            (KeyCode::Char('f'), _) => {
+                self.filter = self.filter.next();
+                self.clamp();
+            }
+            (KeyCode::Char('/'), _) => {
+                self.mode = Mode::Input {
+                    purpose: Purpose::Search,
+                    line: LineEdit::new(&self.search),
+                }
+            }
+            (KeyCode::Char('a' | 'o'), Pane::Checklists) => {
+                self.mode = Mode::Input {
+                    purpose: Purpose::NewChecklist,
+                    line: LineEdit::default(),
+                }
+            }
+            (KeyCode::Char('a' | 'o'), Pane::Items) => {
+                if let Some(entry) = self.current() {
+                    self.mode = Mode::Input {
+                        purpose: Purpose::NewItem(entry.checklist.id),
+                        line: LineEdit::default(),
+                    }
+                }
+            }
+            (KeyCode::Char('e' | 'i'), Pane::Checklists) => {
+                if let Some(Entry { checklist, .. }) = self.current() {
+                    self.mode = Mode::Input {
+                        purpose: Purpose::RenameChecklist {
+                            id: checklist.id,
+                            version: checklist.version,
+                        },
+                        line: LineEdit::new(&checklist.name),
+                    }
+                }
+            }
+            (KeyCode::Char('e' | 'i'), Pane::Items) => {
+                if let Some(Row { item, .. }) = self.current_row() {
+                    self.mode = Mode::Input {
+                        purpose: Purpose::RenameItem {
+                            id: item.id,
+                            version: item.version,
+                        },
+                        line: LineEdit::new(&item.item),
+                    }
+                }
+            }
+            (KeyCode::Char('d'), Pane::Checklists) => {
+                if let Some(Entry { checklist, .. }) = self.current() {
+                    self.mode = Mode::Confirm(Target::Checklist {
+                        id: checklist.id,
+                        version: checklist.version,
+                        name: checklist.name.clone(),
+                    });
+                }
+            }
+            (KeyCode::Char('d'), Pane::Items) => {
+                if let Some(Row { item, .. }) = self.current_row() {
+                    self.mode = Mode::Confirm(Target::Item {
+                        id: item.id,
+                        version: item.version,
+                        text: item.item.clone(),
+                    });
+                }
+            }
+            (KeyCode::Char(' ' | 'x'), Pane::Items) => {
+                if let Some(Row { item, checked }) = self.current_row() {
+                    let result = item
+                        .set_checked_if_version(db, !checked, item.version)
+                        .await
+                        .map(drop);
+                    self.finish(db, result).await;
+                }
+            }
+            (KeyCode::Char('J'), Pane::Items) => self.move_item(db, 1).await,
+            (KeyCode::Char('K'), Pane::Items) => self.move_item(db, -1).await,
+            _ => {}
+        }
+    }
+
+    /// Move the selection in the focused pane by `by`, saturating at either end.
+    fn step(&mut self, by: isize) {
+        let state = match self.pane {
+            Pane::Checklists => &mut self.list_state,
+            Pane::Items => &mut self.item_state,
+        };
+        if let Some(selected) = state.selected() {
+            state.select(Some(selected.saturating_add_signed(by)));
+        }
+        if self.pane == Pane::Checklists {
+            self.item_state.select(Some(0));
+        }
+        self.clamp();
+    }
+
+    /// Move the selected item past the next visible item in direction `by`.
+    async fn move_item(&mut self, db: &Db, by: isize) {
+        let (Some(entry), Some(selected)) = (self.current(), self.item_state.selected()) else {
+            return;
+        };
+        let visible = self.visible_items();
+        let Some(&neighbour) = selected.checked_add_signed(by).and_then(|i| visible.get(i)) else {
+            return;
+        };
+        let row = &entry.items[visible[selected]];
+        let result = row.item.move_to(db, neighbour).await;
+        let id = row.item.id;
+        self.finish(db, result).await;
+        self.select(None, Some(id));
+    }
+
+    async fn submit(&mut self, db: &Db, purpose: Purpose, text: &str) {
+        if text.is_empty() && !matches!(purpose, Purpose::Search) {
+            return;
+        }
+        match purpose {
+            Purpose::Search => {}
+            Purpose::NewChecklist => match Checklist::new(db, text).await {
+                Ok(checklist) => {
+                    self.reload(db).await;
+                    self.select(Some(checklist.id), None);
+                    self.pane = Pane::Items;
+                    self.mode = Mode::Input {
+                        purpose: Purpose::NewItem(checklist.id),
+                        line: LineEdit::default(),
+                    };
+                }
+                Err(err) => self.finish(db, Err(err)).await,
+            },
+            Purpose::NewItem(checklist) => {
+                match Item::new(db, checklist, text.to_owned()).await {
+                    Ok(item) => {
+                        self.reload(db).await;
+                        self.select(Some(checklist), Some(item.id));
+                        // keep adding until Esc
+                        self.mode = Mode::Input {
+                            purpose: Purpose::NewItem(checklist),
+                            line: LineEdit::default(),
+                        };
+                    }
+                    Err(err) => self.finish(db, Err(err)).await,
+                }
+            }
+            Purpose::RenameChecklist { id, version } => {
+                let result = match self.lists.iter().find(|entry| entry.checklist.id == id) {
+                    Some(entry) => entry
+                        .checklist
+                        .rename_if_version(db, text, version)
+                        .await
+                        .map(drop),
+                    None => Err(Error::MissingChecklist),
+                };
+                self.finish(db, result).await;
+            }
+            Purpose::RenameItem { id, version } => {
+                let row = self
+                    .lists
+                    .iter()
+                    .flat_map(|entry| &entry.items)
+                    .find(|row| row.item.id == id);
+                let result = match row {
+                    Some(row) => row
+                        .item
+                        .rename_if_version(db, text, version)
+                        .await
+                        .map(drop),
+                    None => Err(Error::MissingItem),
+                };
+                self.finish(db, result).await;
+            }
+        }
+    }
+
+    /// Report the outcome of a change, and show the database as it is now.
+    async fn finish(&mut self, db: &Db, result: checklist::Result<()>) {
+        if let Err(err) = result {
+            self.message = Some(match err {
+                Error::Conflict { .. } => {
+                    "this was changed elsewhere in the meantime; showing the latest version"
+                        .to_owned()
+                }
+                err => err.to_string(),
+            });
+        }
+        self.reload(db).await;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main);
+
+        let pane_block = |pane: Pane, title: Line<'static>| {
+            let block = Block::bordered().title(title);
+            if self.pane == pane {
+                block.border_style(Style::new().cyan())
+            } else {
+                block
+            }
+        };
+
+        let lists: Vec<_> = self
+            .visible_lists()
+            .into_iter()
+            .map(|i| {
+                let entry = &self.lists[i];
+                let done = entry.items.iter().filter(|row| row.checked).count();
+                ListItem::new(Line::from(vec![
+                    Span::raw(entry.checklist.name.clone()),
+                    Span::raw(format!(" {done}/{}", entry.items.len())).dim(),
+                ]))
+            })
+            .collect();
+        let lists = List::new(lists)
+            .block(pane_block(Pane::Checklists, Line::from(" Checklists ")))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(lists, left, &mut self.list_state);
+
+        let mut title = vec![Span::raw(" ")];
+        let mut ratio = 0.0;
+        if let Some(entry) = self.current() {
+            let done = entry.items.iter().filter(|row| row.checked).count();
+            let total = entry.items.len();
+            if total > 0 {
+                ratio = done as f64 / total as f64;
+            }
+            title.push(Span::raw(entry.checklist.name.clone()).bold());
+            title.push(Span::raw(format!(" {done}/{total} ")));
+        } else {
+            title.push(Span::raw("Items "));
+        }
+        match self.filter {
+            Filter::All => {}
+            Filter::Open => title.push(Span::raw("[open] ").yellow()),
+            Filter::Done => title.push(Span::raw("[done] ").yellow()),
+        }
+        if !self.search.is_empty() {
+            title.push(Span::raw(format!("/{} ", self.search)).yellow());
+        }
+        let block = pane_block(Pane::Items, Line::from(title));
+        let inner = block.inner(right);
+        frame.render_widget(block, right);
+        let [gauge, items_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+        frame.render_widget(
+            LineGauge::default()
+                .ratio(ratio)
+                .filled_style(Style::new().green()),
+            gauge,
+        );
+
+        let items: Vec<_> = match self.current() {
+            Some(entry) => self
+                .visible_items()
+                .into_iter()
+                .map(|i| {
+                    let row = &entry.items[i];
+                    if row.checked {
+                        ListItem::new(format!("[x] {}", row.item.item))
+                            .dim()
+                            .crossed_out()
+                    } else {
+                        ListItem::new(format!("[ ] {}", row.item.item))
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        let items = List::new(items).highlight_style(if self.pane == Pane::Items {
+            Style::new().reversed()
+        } else {
+            Style::new().underlined()
+        });
+        frame.render_stateful_widget(items, items_area, &mut self.item_state);
+
+        let line = match &self.mode {
+            Mode::Input { purpose, line } => {
+                let prompt = Span::raw(purpose.prompt()).bold();
+                let before = Span::raw(&line.text[..line.cursor]);
+                let x = status.x + (prompt.width() + before.width()) as u16;
+                frame.set_cursor_position((x.min(status.right().saturating_sub(1)), status.y));
+                Line::from(vec![prompt, Span::raw(line.text.as_str())])
+            }
+            Mode::Confirm(target) => {
+                let what = match target {
+                    Target::Checklist { name, .. } => format!("checklist \"{name}\" and its items"),
+                    Target::Item { text, .. } => format!("\"{text}\""),
+                };
+                Line::from(format!("Delete {what}? (y/n)")).bold()
+            }
+            Mode::Normal => match &self.message {
+                Some(message) => Line::from(message.as_str()).red(),
+                None => Line::from(
+                    "j/k move  h/l switch  space toggle  a add  e edit  d delete  J/K reorder  \
+                    f filter  / search  q quit",
+                )
+                .dim(),
+            },
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+}
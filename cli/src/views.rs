@@ -0,0 +1,203 @@
+//! JSON representations of checklists, items and the outcomes of commands, shared by the `serve`
+//! and `rpc` commands and the `--format` of every other command.
+
+use std::path::PathBuf;
+
+use checklist::{
+    formats::{EditSummary, ImportSummary},
+    sync::{Peer, SyncReport, SyncSummary},
+    Checklist, ChecklistId, Db, Item, ItemId, Result, Uuid,
+};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ChecklistView {
+    pub id: ChecklistId,
+    pub uuid: Uuid,
+    pub name: String,
+    pub version: u64,
+}
+
+impl From<Checklist> for ChecklistView {
+    fn from(
+        Checklist {
+            id,
+            uuid,
+            name,
+            version,
+        }: Checklist,
+    ) -> Self {
+        Self {
+            id,
+            uuid,
+            name,
+            version,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ItemView {
+    pub id: ItemId,
+    pub uuid: Uuid,
+    pub checklist: ChecklistId,
+    pub item: String,
+    pub checked: bool,
+    pub version: u64,
+}
+
+impl ItemView {
+    pub(crate) async fn load(db: &Db, item: Item) -> Result<Self> {
+        let checked = item.is_set(db).await?;
+        let Item {
+            id,
+            uuid,
+            checklist,
+            item,
+            version,
+        } = item;
+        Ok(Self {
+            id,
+            uuid,
+            checklist,
+            item,
+            checked,
+            version,
+        })
+    }
+
+    pub(crate) async fn load_all(db: &Db, items: Vec<Item>) -> Result<Vec<Self>> {
+        let mut views = Vec::with_capacity(items.len());
+        for item in items {
+            views.push(Self::load(db, item).await?);
+        }
+        Ok(views)
+    }
+}
+
+/// What an import added or replaced
+#[derive(Debug, Serialize)]
+pub(crate) struct ImportView {
+    pub checklists: usize,
+    pub items: usize,
+}
+
+impl From<ImportSummary> for ImportView {
+    fn from(ImportSummary { checklists, items }: ImportSummary) -> Self {
+        Self { checklists, items }
+    }
+}
+
+/// What editing a checklist as a task list changed
+#[derive(Debug, Serialize)]
+pub(crate) struct EditView {
+    pub renamed: bool,
+    pub added: usize,
+    pub removed: usize,
+    pub edited: usize,
+    pub toggled: usize,
+    pub moved: usize,
+}
+
+impl From<EditSummary> for EditView {
+    fn from(summary: EditSummary) -> Self {
+        Self {
+            renamed: summary.renamed,
+            added: summary.added,
+            removed: summary.removed,
+            edited: summary.edited,
+            toggled: summary.toggled,
+            moved: summary.moved,
+        }
+    }
+}
+
+/// What syncing with another database changed in each
+#[derive(Debug, Serialize)]
+pub(crate) struct SyncView {
+    pub pulled: usize,
+    pub pushed: usize,
+    pub skipped: usize,
+    /// Conflicts resolved in this database, described for people
+    pub conflicts: Vec<String>,
+}
+
+impl From<&SyncReport> for SyncView {
+    fn from(SyncReport { pulled, pushed }: &SyncReport) -> Self {
+        Self {
+            pulled: pulled.changed(),
+            pushed: pushed.changed(),
+            skipped: pulled.skipped + pushed.skipped,
+            conflicts: pulled.conflicts.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// What applying a changeset changed
+#[derive(Debug, Serialize)]
+pub(crate) struct ApplyView {
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub skipped: usize,
+    pub conflicts: Vec<String>,
+}
+
+impl From<&SyncSummary> for ApplyView {
+    fn from(summary: &SyncSummary) -> Self {
+        Self {
+            inserted: summary.inserted,
+            updated: summary.updated,
+            deleted: summary.deleted,
+            skipped: summary.skipped,
+            conflicts: summary.conflicts.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// This database's place in sync, and how far each peer has synced
+#[derive(Debug, Serialize)]
+pub(crate) struct StatusView {
+    pub site: Uuid,
+    pub version: u64,
+    pub peers: Vec<PeerView>,
+}
+
+impl StatusView {
+    pub(crate) async fn load(db: &Db) -> Result<Self> {
+        Ok(Self {
+            site: db.site().await?,
+            version: db.sync_version().await?,
+            peers: db.peers().await?.into_iter().map(PeerView::from).collect(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PeerView {
+    pub site: Uuid,
+    pub received: u64,
+    pub acknowledged: u64,
+}
+
+impl From<Peer> for PeerView {
+    fn from(
+        Peer {
+            site,
+            received,
+            acknowledged,
+        }: Peer,
+    ) -> Self {
+        Self {
+            site,
+            received,
+            acknowledged,
+        }
+    }
+}
+
+/// The file which a command wrote, such as a backup or the database itself
+#[derive(Debug, Serialize)]
+pub(crate) struct PathView {
+    pub path: PathBuf,
+}
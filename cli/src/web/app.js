@@ -0,0 +1,412 @@
+"use strict";
+
+// The token is kept in local storage, so that it survives reloads. It may also be given as a
+// `#token=...` fragment, which browsers never send to the server.
+const TOKEN_KEY = "checklist-token";
+let token = localStorage.getItem(TOKEN_KEY);
+
+/** Checklists, each with its `items` */
+let checklists = [];
+/** Items matching the search, or null when not searching */
+let results = null;
+/** The `data-key` of the checklist name or item text being edited */
+let editing = null;
+/** Set while re-rendering, when inputs lose focus without the user leaving them */
+let rendering = false;
+let refreshing = null;
+let refreshAgain = false;
+let listening = false;
+
+const $ = (id) => document.getElementById(id);
+
+function el(tag, props = {}, ...children) {
+  const node = document.createElement(tag);
+  for (const [key, value] of Object.entries(props)) {
+    if (key.startsWith("on")) {
+      node.addEventListener(key.slice(2), value);
+    } else if (key === "dataset") {
+      Object.assign(node.dataset, value);
+    } else {
+      node[key] = value;
+    }
+  }
+  node.append(...children.filter((child) => child != null));
+  return node;
+}
+
+class ApiError extends Error {
+  constructor(status, message) {
+    super(message);
+    this.status = status;
+  }
+}
+
+async function api(method, path, { body, version } = {}) {
+  const headers = { Authorization: `Bearer ${token}` };
+  if (body !== undefined) {
+    headers["Content-Type"] = "application/json";
+  }
+  if (version !== undefined) {
+    headers["If-Match"] = `"${version}"`;
+  }
+  const response = await fetch(path, {
+    method,
+    headers,
+    body: body === undefined ? undefined : JSON.stringify(body),
+  });
+  if (response.status === 401) {
+    signOut();
+    throw new ApiError(401, "signed out");
+  }
+  if (!response.ok) {
+    const { error } = await response.json().catch(() => ({ error: response.statusText }));
+    throw new ApiError(response.status, error);
+  }
+  return response.status === 204 ? null : response.json();
+}
+
+function showStatus(message) {
+  $("status").textContent = message;
+}
+
+/** Run a change, report any failure, and show the result. */
+async function act(change) {
+  try {
+    await change();
+    showStatus("");
+  } catch (err) {
+    if (err.status === 412) {
+      showStatus("That was changed elsewhere in the meantime; showing the latest version.");
+    } else if (err.status !== 401) {
+      showStatus(err.message);
+    }
+  }
+  await refresh();
+}
+
+/** Reload everything, coalescing refreshes requested while one is running. */
+function refresh() {
+  if (!token) {
+    return Promise.resolve();
+  }
+  if (refreshing) {
+    refreshAgain = true;
+    return refreshing;
+  }
+  refreshing = (async () => {
+    try {
+      do {
+        refreshAgain = false;
+        const lists = await api("GET", "/checklists");
+        await Promise.all(
+          lists.map(async (list) => {
+            list.items = await api("GET", `/checklists/${list.id}/items`);
+          }),
+        );
+        const query = $("search").value.trim();
+        results = query ? await api("GET", `/search?q=${encodeURIComponent(query)}`) : null;
+        checklists = lists;
+        render();
+      } while (refreshAgain);
+    } catch (err) {
+      if (err.status !== 401) {
+        showStatus(err.message);
+      }
+    } finally {
+      refreshing = null;
+    }
+  })();
+  return refreshing;
+}
+
+/** Re-render, keeping the focus and the contents of inputs. */
+function render() {
+  const main = $("lists");
+  const focused = document.activeElement?.dataset?.key;
+  const values = new Map(
+    [...main.querySelectorAll("input[data-key]")].map((input) => [input.dataset.key, input.value]),
+  );
+
+  rendering = true;
+  main.replaceChildren(...(results ? renderResults() : renderLists()));
+  rendering = false;
+
+  for (const input of main.querySelectorAll("input[data-key]")) {
+    if (values.has(input.dataset.key)) {
+      input.value = values.get(input.dataset.key);
+    }
+  }
+  if (focused) {
+    main.querySelector(`[data-key="${CSS.escape(focused)}"]`)?.focus();
+  }
+}
+
+function renderLists() {
+  const sections = checklists.map(renderList);
+  sections.push(
+    el(
+      "form",
+      {
+        className: "new-list",
+        onsubmit: (event) => {
+          event.preventDefault();
+          const input = event.target.elements.name;
+          const name = input.value.trim();
+          input.value = "";
+          if (name) {
+            act(() => api("POST", "/checklists", { body: { name } }));
+          }
+        },
+      },
+      el("input", {
+        name: "name",
+        placeholder: "New checklist",
+        autocomplete: "off",
+        dataset: { key: "new-list" },
+      }),
+    ),
+  );
+  return sections;
+}
+
+function renderList(list) {
+  const done = list.items.filter((item) => item.checked).length;
+  const total = list.items.length;
+  return el(
+    "section",
+    { className: "list" },
+    el(
+      "header",
+      {},
+      editable(`checklist-${list.id}`, list.name, "h2", (name) =>
+        api("PATCH", `/checklists/${list.id}`, { body: { name }, version: list.version }),
+      ),
+      el("progress", { max: Math.max(total, 1), value: done }),
+      el("span", { className: "count" }, `${done}/${total}`),
+      el(
+        "button",
+        {
+          title: `Delete ${list.name}`,
+          onclick: () => {
+            if (confirm(`Delete "${list.name}" and its ${total} items?`)) {
+              act(() => api("DELETE", `/checklists/${list.id}`, { version: list.version }));
+            }
+          },
+        },
+        "×",
+      ),
+    ),
+    el("ul", {}, ...list.items.map((item, index) => renderItem(item, index, total))),
+    el(
+      "form",
+      {
+        className: "new-item",
+        onsubmit: (event) => {
+          event.preventDefault();
+          const input = event.target.elements.item;
+          const item = input.value.trim();
+          input.value = "";
+          if (item) {
+            act(() => api("POST", `/checklists/${list.id}/items`, { body: { item } }));
+          }
+        },
+      },
+      el("input", {
+        name: "item",
+        placeholder: "Add item",
+        autocomplete: "off",
+        dataset: { key: `new-item-${list.id}` },
+      }),
+    ),
+  );
+}
+
+/** An item, with buttons to move it when its `index` among `count` items is given. */
+function renderItem(item, index, count) {
+  const move = (to, label, symbol) =>
+    el(
+      "button",
+      {
+        title: label,
+        disabled: to < 0 || to >= count,
+        onclick: () => act(() => api("POST", `/items/${item.id}/move`, { body: { index: to } })),
+      },
+      symbol,
+    );
+  return el(
+    "li",
+    { className: item.checked ? "checked" : "" },
+    el("input", {
+      type: "checkbox",
+      checked: item.checked,
+      title: item.checked ? "Uncheck" : "Check",
+      onchange: () => act(() => api("POST", `/items/${item.id}/toggle`, { version: item.version })),
+    }),
+    editable(`item-${item.id}`, item.item, "span", (text) =>
+      api("PATCH", `/items/${item.id}`, { body: { item: text }, version: item.version }),
+    ),
+    index === undefined ? null : move(index - 1, "Move up", "↑"),
+    index === undefined ? null : move(index + 1, "Move down", "↓"),
+    el(
+      "button",
+      {
+        title: "Delete item",
+        onclick: () => act(() => api("DELETE", `/items/${item.id}`, { version: item.version })),
+      },
+      "×",
+    ),
+  );
+}
+
+function renderResults() {
+  if (!results.length) {
+    return [el("p", { className: "empty" }, "No items match.")];
+  }
+  const names = new Map(checklists.map((list) => [list.id, list.name]));
+  const items = results.map((item) => {
+    const row = renderItem(item);
+    row.append(el("span", { className: "in" }, names.get(item.checklist) ?? ""));
+    return row;
+  });
+  return [el("section", { className: "list" }, el("ul", {}, ...items))];
+}
+
+/** Text which turns into an input when clicked, and is saved with `save` on Enter or blur. */
+function editable(key, text, tag, save) {
+  if (editing !== key) {
+    const start = () => {
+      editing = key;
+      render();
+      const input = document.querySelector(`[data-key="${CSS.escape(key)}"]`);
+      input?.focus();
+      input?.select();
+    };
+    return el(
+      tag,
+      {
+        className: "text",
+        tabIndex: 0,
+        title: "Click to edit",
+        onclick: start,
+        onkeydown: (event) => {
+          if (event.key === "Enter") {
+            start();
+          }
+        },
+      },
+      text,
+    );
+  }
+
+  let finished = false;
+  const finish = (keep) => {
+    if (finished) {
+      return;
+    }
+    finished = true;
+    editing = null;
+    const value = input.value.trim();
+    if (keep && value && value !== text) {
+      act(() => save(value));
+    } else {
+      render();
+    }
+  };
+  const input = el("input", {
+    className: "edit",
+    value: text,
+    dataset: { key },
+    onkeydown: (event) => {
+      if (event.key === "Enter") {
+        finish(true);
+      } else if (event.key === "Escape") {
+        finish(false);
+      }
+    },
+    onblur: () => {
+      if (!rendering) {
+        finish(true);
+      }
+    },
+  });
+  return input;
+}
+
+/** Refresh whenever the server reports a change, falling back to polling while it cannot. */
+async function listen() {
+  if (listening) {
+    return;
+  }
+  listening = true;
+  while (token) {
+    try {
+      const response = await fetch("/events", { headers: { Authorization: `Bearer ${token}` } });
+      if (response.status === 401) {
+        signOut();
+        break;
+      }
+      if (!response.ok || !response.body) {
+        throw new Error(response.statusText);
+      }
+      const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+      let buffer = "";
+      for (;;) {
+        const { value, done } = await reader.read();
+        if (done) {
+          break;
+        }
+        buffer += value;
+        const events = buffer.split("\n\n");
+        buffer = events.pop();
+        if (events.some((event) => /^event: ?changed$/m.test(event))) {
+          refresh();
+        }
+      }
+    } catch {
+      // the server may be restarting
+    }
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+    await refresh();
+  }
+  listening = false;
+}
+
+function signOut() {
+  token = null;
+  localStorage.removeItem(TOKEN_KEY);
+  $("login").hidden = false;
+  $("search").hidden = true;
+  $("lists").replaceChildren();
+}
+
+function start() {
+  if (!token) {
+    $("login").hidden = false;
+    return;
+  }
+  $("login").hidden = true;
+  $("search").hidden = false;
+  listen();
+}
+
+$("login").addEventListener("submit", (event) => {
+  event.preventDefault();
+  token = $("token").value.trim();
+  $("token").value = "";
+  localStorage.setItem(TOKEN_KEY, token);
+  start();
+});
+
+let searchTimer;
+$("search").addEventListener("input", () => {
+  clearTimeout(searchTimer);
+  searchTimer = setTimeout(refresh, 200);
+});
+
+const fragment = new URLSearchParams(location.hash.slice(1));
+if (fragment.has("token")) {
+  token = fragment.get("token");
+  localStorage.setItem(TOKEN_KEY, token);
+  history.replaceState(null, "", location.pathname);
+}
+start();
@@ -0,0 +1,48 @@
+// each test crate uses only some of these helpers
+#![allow(dead_code)]
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// A fresh directory in the temporary directory, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("checklist-cli-test-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&path).expect("creating temporary directory");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// The `checklist` binary, using an unencrypted database in this directory.
+    pub fn checklist(&self) -> Command {
+        let mut command = self.configured();
+        command
+            .arg("--path")
+            .arg(self.0.join("db.sqlite3"))
+            .args(["--cipher", "none"]);
+        command
+    }
+
+    /// The `checklist` binary, with its configuration in this directory and no other options.
+    pub fn configured(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_checklist"));
+        command
+            .env("XDG_CONFIG_HOME", &self.0)
+            .env_remove("CHECKLIST_PROFILE");
+        command
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use common::TempDir;
+
+fn run(command: &mut Command, args: &[&str]) -> Output {
+    command.args(args).output().expect("running checklist")
+}
+
+fn succeeds(command: &mut Command, args: &[&str]) -> String {
+    let output = run(command, args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+#[test]
+fn profiles_supply_defaults_for_the_global_options() {
+    let dir = TempDir::new("config-profiles");
+    let work = dir.path().join("work.sqlite3");
+    let config = dir.path().join("checklist/config.toml");
+    std::fs::create_dir_all(config.parent().unwrap()).unwrap();
+    let home = "# kept by config set\n[profiles.home]\nformat = \"csv\"\n";
+    std::fs::write(&config, home).unwrap();
+
+    for (setting, value) in [
+        ("path", work.to_str().unwrap()),
+        ("cipher", "none"),
+        ("format", "json"),
+        ("checklist", "groceries"),
+    ] {
+        succeeds(
+            &mut dir.configured(),
+            &["--profile", "work", "config", "set", setting, value],
+        );
+    }
+    let text = std::fs::read_to_string(&config).unwrap();
+    assert!(text.starts_with(home), "{text}");
+    assert!(text.contains("[profiles.work]"), "{text}");
+    assert!(!text.contains("[profiles]"), "{text}");
+
+    let work_profile = || {
+        let mut command = dir.configured();
+        command.env("CHECKLIST_PROFILE", "work");
+        command
+    };
+    let created = succeeds(&mut work_profile(), &["list", "new", "groceries"]);
+    assert!(created.starts_with('{'), "{created}");
+    // the profile's checklist is used by item commands which do not name one
+    let item = succeeds(&mut work_profile(), &["item", "new", "milk"]);
+    assert!(item.contains(r#""item":"milk""#), "{item}");
+    assert!(work.exists());
+
+    // options given on the command line take precedence
+    let text = succeeds(
+        &mut work_profile(),
+        &["--format", "text", "item", "show-all", "--omit-header"],
+    );
+    assert!(text.contains("milk") && !text.contains('{'), "{text}");
+
+    let path = succeeds(&mut work_profile(), &["config", "get", "path"]);
+    assert_eq!(path.trim(), work.to_str().unwrap());
+    succeeds(&mut work_profile(), &["config", "unset", "format"]);
+    let output = run(&mut work_profile(), &["config", "get", "format"]);
+    assert!(!output.status.success());
+
+    // without a profile, the built-in defaults apply
+    let output = run(&mut dir.configured(), &["config", "get", "path"]);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no profile is chosen"));
+    succeeds(
+        &mut dir.configured(),
+        &["config", "set", "default-profile", "work"],
+    );
+    let items = succeeds(&mut dir.configured(), &["item", "show-all", "@last"]);
+    assert!(items.contains("milk"), "{items}");
+}
+
+#[test]
+fn invalid_settings_are_refused() {
+    let dir = TempDir::new("config-invalid");
+    let config = dir.path().join("checklist/config.toml");
+
+    let output = run(
+        &mut dir.configured(),
+        &["--profile", "work", "config", "set", "cipher", "rot13"],
+    );
+    assert!(!output.status.success());
+    assert!(!config.exists());
+
+    let output = run(
+        &mut dir.configured(),
+        &["--profile", "home", "list", "show-all"],
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(r#"there is no profile "home""#), "{stderr}");
+
+    // an editor which breaks the file is reported, and the file can still be repaired
+    let output = run(
+        dir.configured().env("EDITOR", "echo 'colour = 1' >>"),
+        &["config", "edit"],
+    );
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("invalid configuration"), "{stderr}");
+    succeeds(
+        dir.configured()
+            .env("EDITOR", "echo 'default-profile = \"home\"' >"),
+        &["config", "edit"],
+    );
+    let shown = succeeds(&mut dir.configured(), &["config", "show"]);
+    assert_eq!(shown, "default-profile = \"home\"\n");
+}
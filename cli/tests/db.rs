@@ -0,0 +1,55 @@
+mod common;
+
+use common::TempDir;
+use serde_json::{json, Value};
+
+fn succeeds(dir: &TempDir, args: &[&str]) -> String {
+    let output = dir
+        .checklist()
+        .args(args)
+        .output()
+        .expect("running checklist");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+fn json(dir: &TempDir, args: &[&str]) -> Value {
+    let output = succeeds(dir, &[&["--format", "json"], args].concat());
+    serde_json::from_str(&output).expect("output is JSON")
+}
+
+#[test]
+fn database_commands_print_the_path_they_wrote() {
+    let dir = TempDir::new("db");
+    let db = dir.path().join("db.sqlite3");
+    let backup = dir.path().join("backup.sqlite3");
+    let plain = dir.path().join("plain.sqlite3");
+    succeeds(&dir, &["list", "new", "groceries"]);
+
+    let backup = backup.to_str().unwrap();
+    assert_eq!(
+        json(&dir, &["db", "backup", backup]),
+        json!({ "path": backup })
+    );
+    succeeds(&dir, &["list", "new", "chores"]);
+    assert_eq!(
+        json(&dir, &["db", "restore", backup]),
+        json!({ "path": db })
+    );
+    let plain = plain.to_str().unwrap();
+    assert_eq!(
+        json(&dir, &["db", "decrypt", "--confirm-plaintext", plain]),
+        json!({ "path": plain })
+    );
+
+    // as text, only the generated path of a rotating backup is printed
+    assert_eq!(succeeds(&dir, &["db", "restore", backup]), "");
+    let rotated = succeeds(&dir, &["db", "backup"]);
+    assert!(rotated.trim_end().ends_with(".bak"), "{rotated}");
+    let checklists = succeeds(&dir, &["--format", "csv", "list", "show-all"]);
+    assert_eq!(checklists.lines().count(), 2, "{checklists}");
+}
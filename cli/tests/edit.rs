@@ -0,0 +1,126 @@
+mod common;
+
+use std::{
+    io::Write as _,
+    process::{Output, Stdio},
+};
+
+use common::TempDir;
+
+fn run(dir: &TempDir, editor: &str, args: &[&str]) -> Output {
+    dir.checklist()
+        .env("EDITOR", editor)
+        .env_remove("VISUAL")
+        .args(args)
+        .output()
+        .expect("running checklist")
+}
+
+fn succeeds(dir: &TempDir, args: &[&str]) -> String {
+    let output = run(dir, "false", args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+/// The files named as keeping edits which could not be applied.
+fn kept_files(stderr: &str) -> Vec<String> {
+    stderr
+        .split("kept in ")
+        .skip(1)
+        .map(|rest| rest.split(": ").next().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn edits_are_applied_when_the_editor_exits() {
+    let dir = TempDir::new("edit");
+    succeeds(&dir, &["list", "new", "groceries"]);
+    succeeds(&dir, &["item", "new", "groceries", "milk"]);
+    succeeds(&dir, &["item", "new", "groceries", "eggs"]);
+
+    let editor = r"sed -i -e 's/\[ \] milk/[x] milk/' -e '/eggs/d' -e '$a - [ ] jam'";
+    let output = run(
+        &dir,
+        editor,
+        &["--format", "csv", "list", "edit", "groceries"],
+    );
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let summary = String::from_utf8_lossy(&output.stdout);
+    assert!(summary.ends_with("false,1,1,0,1,0\n"), "{summary}");
+
+    let items = succeeds(&dir, &["--format", "csv", "item", "show-all", "groceries"]);
+    assert!(items.contains(",milk,true,"), "{items}");
+    assert!(items.contains(",jam,false,"), "{items}");
+    assert!(!items.contains("eggs"), "{items}");
+
+    // an editor which fails changes nothing
+    let output = run(&dir, "false", &["list", "edit", "groceries"]);
+    assert!(!output.status.success());
+    assert_eq!(
+        succeeds(&dir, &["--format", "csv", "item", "show-all", "groceries"]),
+        items
+    );
+}
+
+#[test]
+fn edits_of_a_changed_checklist_are_kept() {
+    let dir = TempDir::new("edit-conflict");
+    succeeds(&dir, &["list", "new", "groceries"]);
+    succeeds(&dir, &["item", "new", "groceries", "milk"]);
+
+    // the editor toggles milk in the database while the list is being edited
+    let editor = format!(
+        "{} --path {} --cipher none item toggle milk >/dev/null; sed -i '$a - [ ] jam'",
+        env!("CARGO_BIN_EXE_checklist"),
+        dir.path().join("db.sqlite3").display(),
+    );
+    let output = run(&dir, &editor, &["list", "edit", "groceries"]);
+    assert_eq!(output.status.code(), Some(4));
+    let kept = kept_files(&String::from_utf8_lossy(&output.stderr));
+    let edited = std::fs::read_to_string(&kept[0]).unwrap();
+    std::fs::remove_file(&kept[0]).unwrap();
+    assert!(edited.contains("- [ ] jam"), "{edited}");
+
+    let items = succeeds(&dir, &["item", "show-all", "--omit-header", "groceries"]);
+    assert_eq!(items.lines().count(), 1, "{items}");
+}
+
+#[test]
+fn edits_kept_in_one_shell_session_do_not_clash() {
+    let dir = TempDir::new("edit-shell");
+    succeeds(&dir, &["list", "new", "groceries"]);
+
+    // an editor which removes the heading leaves a file which cannot be applied
+    let mut child = dir
+        .checklist()
+        .env("EDITOR", "sed -i '/^#/d'")
+        .arg("shell")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("starting shell");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(b"list edit groceries\nlist edit groceries\n")
+        .expect("writing commands");
+    let output = child.wait_with_output().expect("waiting for shell");
+
+    let kept = kept_files(&String::from_utf8_lossy(&output.stderr));
+    assert_eq!(kept.len(), 2, "{}", String::from_utf8_lossy(&output.stderr));
+    assert_ne!(kept[0], kept[1]);
+    for kept in kept {
+        assert!(std::fs::read_to_string(&kept).unwrap().contains("<!--"));
+        std::fs::remove_file(kept).unwrap();
+    }
+}
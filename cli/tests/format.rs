@@ -0,0 +1,104 @@
+mod common;
+
+use std::process::Output;
+
+use common::TempDir;
+use serde_json::Value;
+
+fn run(dir: &TempDir, args: &[&str]) -> Output {
+    dir.checklist()
+        .args(args)
+        .output()
+        .expect("running checklist")
+}
+
+/// Run a command which should succeed, returning its standard output.
+fn stdout(dir: &TempDir, args: &[&str]) -> String {
+    let output = run(dir, args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+fn json(dir: &TempDir, args: &[&str]) -> Value {
+    let stdout = stdout(dir, &[&["--format", "json"], args].concat());
+    serde_json::from_str(&stdout).expect("output is JSON")
+}
+
+#[test]
+fn changes_print_the_affected_record() {
+    let dir = TempDir::new("format-changes");
+    let checklist = json(&dir, &["list", "new", "groceries"]);
+    assert_eq!(checklist["id"], 1);
+    assert_eq!(checklist["name"], "groceries");
+    assert!(checklist["uuid"].is_string());
+
+    let item = json(&dir, &["item", "new", "1", "milk"]);
+    assert_eq!(item["checklist"], 1);
+    assert_eq!(item["item"], "milk");
+    assert_eq!(item["checked"], false);
+
+    let toggled = json(&dir, &["item", "toggle", "1"]);
+    assert_eq!(toggled["checked"], true);
+    assert_eq!(toggled["uuid"], item["uuid"]);
+
+    let removed = json(&dir, &["item", "remove", "1"]);
+    assert_eq!(removed["item"], "milk");
+    assert_eq!(json(&dir, &["item", "show-all", "1"]), Value::Array(vec![]));
+}
+
+#[test]
+fn many_records_print_as_lines_or_tables() {
+    let dir = TempDir::new("format-many");
+    stdout(&dir, &["list", "new", "groceries"]);
+    stdout(&dir, &["item", "new", "1", "milk"]);
+    stdout(&dir, &["item", "new", "1", "eggs, large"]);
+
+    let lines = stdout(&dir, &["--format", "jsonl", "item", "show-all", "1"]);
+    let items: Vec<Value> = lines
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("line is JSON"))
+        .collect();
+    assert_eq!(items.len(), 2, "{lines}");
+    assert_eq!(items[1]["item"], "eggs, large");
+
+    let csv = stdout(&dir, &["--format", "csv", "item", "show-all", "1"]);
+    let rows: Vec<_> = csv.lines().collect();
+    assert_eq!(rows[0], "id,uuid,checklist,item,checked,version");
+    assert!(rows[2].contains(",\"eggs, large\",false,"), "{csv}");
+
+    let tsv = stdout(&dir, &["--format", "tsv", "list", "show-all"]);
+    assert_eq!(tsv.lines().next(), Some("id\tuuid\tname\tversion"));
+    assert!(
+        tsv.lines().nth(1).unwrap().contains("\tgroceries\t"),
+        "{tsv}"
+    );
+}
+
+#[test]
+fn text_is_not_colored_when_piped() {
+    let dir = TempDir::new("format-text");
+    stdout(&dir, &["list", "new", "groceries"]);
+    let output = stdout(&dir, &["list", "show-all"]);
+    assert!(output.contains("groceries"), "{output}");
+    assert!(!output.contains('\x1b'), "{output:?}");
+}
+
+#[test]
+fn exit_status_distinguishes_missing_records() {
+    let dir = TempDir::new("format-status");
+    stdout(&dir, &["list", "new", "groceries"]);
+
+    let missing = run(&dir, &["item", "toggle", "7"]);
+    assert_eq!(missing.status.code(), Some(3));
+    assert!(!String::from_utf8_lossy(&missing.stderr).contains('\x1b'));
+
+    let missing = run(&dir, &["item", "new", "9", "milk"]);
+    assert_eq!(missing.status.code(), Some(3));
+
+    let invalid = run(&dir, &["--format", "xml", "list", "show-all"]);
+    assert_eq!(invalid.status.code(), Some(2));
+}
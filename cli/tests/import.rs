@@ -0,0 +1,56 @@
+mod common;
+
+use common::TempDir;
+
+fn succeeds(dir: &TempDir, args: &[&str]) -> String {
+    let output = dir
+        .checklist()
+        .args(args)
+        .output()
+        .expect("running checklist");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+#[test]
+fn reimporting_an_ics_export_does_not_duplicate_to_dos() {
+    let dir = TempDir::new("import-ics");
+    succeeds(&dir, &["list", "new", "groceries"]);
+    succeeds(&dir, &["item", "new", "groceries", "milk"]);
+    succeeds(&dir, &["item", "new", "groceries", "eggs"]);
+    succeeds(&dir, &["item", "toggle", "eggs"]);
+
+    let path = dir.path().join("export.ics");
+    let path = path.to_str().unwrap();
+    succeeds(&dir, &["export", "--format", "ics", "--output", path]);
+    let exported = std::fs::read_to_string(path).unwrap();
+    succeeds(&dir, &["import", "--format", "ics", path]);
+    succeeds(&dir, &["export", "--format", "ics", "--output", path]);
+    let reexported = std::fs::read_to_string(path).unwrap();
+
+    let uids = |ics: &str| {
+        let mut uids: Vec<_> = ics
+            .lines()
+            .filter(|line| line.starts_with("UID:"))
+            .map(str::to_owned)
+            .collect();
+        uids.sort();
+        uids
+    };
+    assert_eq!(uids(&exported).len(), 3, "{exported}");
+    assert_eq!(uids(&reexported), uids(&exported));
+    let checklists = succeeds(&dir, &["--format", "csv", "list", "show-all"]);
+    assert_eq!(checklists.lines().count(), 2, "{checklists}");
+
+    // merging must still be asked for explicitly
+    succeeds(
+        &dir,
+        &["import", "--format", "ics", "--mode", "merge", path],
+    );
+    let items = succeeds(&dir, &["--format", "csv", "item", "show-all", "1"]);
+    assert_eq!(items.lines().count(), 3, "{items}");
+}
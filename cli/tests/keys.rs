@@ -0,0 +1,128 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use common::TempDir;
+
+/// The binary, using an encrypted database in `dir` and no key options.
+fn encrypted(dir: &TempDir) -> Command {
+    let mut command = dir.configured();
+    command
+        .arg("--path")
+        .arg(dir.path().join("db.sqlite3"))
+        .env_remove("KEY");
+    command
+}
+
+fn run(command: &mut Command, args: &[&str]) -> Output {
+    command.args(args).output().expect("running checklist")
+}
+
+fn succeeds(command: &mut Command, args: &[&str]) -> String {
+    let output = run(command, args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+#[test]
+fn encrypted_databases_need_a_key() {
+    let dir = TempDir::new("keys-required");
+    let output = run(&mut encrypted(&dir), &["list", "new", "groceries"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("no encryption key given"), "{stderr}");
+
+    // the old default key cannot create a database, but still opens one made with it
+    let output = run(
+        &mut encrypted(&dir),
+        &[
+            "--key-source",
+            "insecure-default",
+            "list",
+            "new",
+            "groceries",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(!dir.path().join("db.sqlite3").exists());
+    succeeds(
+        encrypted(&dir).env("USER", "me").env("NAME", "laptop"),
+        &["--encryption-key", "me@laptop", "list", "new", "groceries"],
+    );
+    let output = run(
+        encrypted(&dir).env("USER", "me").env("NAME", "laptop"),
+        &["--key-source", "insecure-default", "list", "show-all"],
+    );
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("warning:"), "{stderr}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("groceries"));
+}
+
+#[test]
+fn rekeying_with_a_passphrase_writes_a_fresh_salt() {
+    let dir = TempDir::new("keys-rekey");
+    let header = dir.path().join("db.sqlite3.kdf");
+    let old = ["--key-source", "env:KEY", "--derive-key"];
+    let with_key = |key: &str| {
+        let mut command = encrypted(&dir);
+        command.env("KEY", key).args(old);
+        command
+    };
+    succeeds(
+        &mut with_key("old passphrase"),
+        &["list", "new", "groceries"],
+    );
+    let old_header = std::fs::read_to_string(&header).unwrap();
+
+    succeeds(
+        with_key("old passphrase").env("NEW_KEY", "new passphrase"),
+        &[
+            "--kdf-iterations",
+            "3",
+            "db",
+            "rekey",
+            "--new-key-source",
+            "env:NEW_KEY",
+            "--derive-new-key",
+        ],
+    );
+    let new_header = std::fs::read_to_string(&header).unwrap();
+    assert!(new_header.contains("iterations=3"), "{new_header}");
+    let salt = |header: &str| header.lines().last().unwrap().to_owned();
+    assert_ne!(salt(&new_header), salt(&old_header));
+
+    let output = run(&mut with_key("old passphrase"), &["list", "show-all"]);
+    assert!(!output.status.success());
+    let checklists = succeeds(&mut with_key("new passphrase"), &["list", "show-all"]);
+    assert!(checklists.contains("groceries"), "{checklists}");
+
+    // a key which is not derived leaves no header behind
+    let key_file = dir.path().join("key");
+    std::fs::write(&key_file, "raw key").unwrap();
+    let rekeyed = succeeds(
+        &mut with_key("new passphrase"),
+        &[
+            "--format",
+            "json",
+            "db",
+            "rekey",
+            "--new-key-file",
+            key_file.to_str().unwrap(),
+        ],
+    );
+    let db = dir.path().join("db.sqlite3");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&rekeyed).unwrap(),
+        serde_json::json!({ "path": db })
+    );
+    assert!(!header.exists());
+    succeeds(
+        encrypted(&dir).arg("--encryption-key-file").arg(&key_file),
+        &["list", "show-all"],
+    );
+}
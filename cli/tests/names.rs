@@ -0,0 +1,63 @@
+mod common;
+
+use std::process::Output;
+
+use common::TempDir;
+
+fn run(dir: &TempDir, args: &[&str]) -> Output {
+    dir.checklist()
+        .args(args)
+        .output()
+        .expect("running checklist")
+}
+
+fn succeeds(dir: &TempDir, args: &[&str]) -> String {
+    let output = run(dir, args);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("output is UTF-8")
+}
+
+#[test]
+fn checklists_and_items_are_given_by_name() {
+    let dir = TempDir::new("names");
+    succeeds(&dir, &["list", "new", "groceries"]);
+    succeeds(&dir, &["list", "new", "garden"]);
+    succeeds(&dir, &["item", "new", "groceries", "milk"]);
+    succeeds(&dir, &["item", "new", "groc", "oat milk"]);
+    succeeds(&dir, &["item", "new", "gdn", "weed"]);
+
+    let items = succeeds(&dir, &["item", "show-all", "--omit-header", "GROCERIES"]);
+    assert_eq!(items.lines().count(), 2, "{items}");
+    assert!(items.contains("oat milk"), "{items}");
+
+    succeeds(&dir, &["item", "toggle", "weed"]);
+    let toggled = succeeds(&dir, &["--format", "jsonl", "item", "show-all", "@last"]);
+    assert!(
+        toggled.contains(r#""item":"weed","checked":true"#),
+        "{toggled}"
+    );
+
+    succeeds(&dir, &["item", "toggle", "@last"]);
+    let items = succeeds(&dir, &["--format", "csv", "item", "show-all", "garden"]);
+    assert!(items.contains(",weed,false,"), "{items}");
+}
+
+#[test]
+fn ambiguous_names_list_the_candidates() {
+    let dir = TempDir::new("names-ambiguous");
+    succeeds(&dir, &["list", "new", "groceries"]);
+    succeeds(&dir, &["list", "new", "garden"]);
+
+    let output = run(&dir, &["item", "new", "g", "milk"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("groceries (1)"), "{stderr}");
+    assert!(stderr.contains("garden (2)"), "{stderr}");
+
+    let output = run(&dir, &["list", "remove", "shopping"]);
+    assert_eq!(output.status.code(), Some(3));
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use std::{
+    io::{BufRead as _, BufReader, Write as _},
+    process::{Child, ChildStdin, Stdio},
+    sync::mpsc,
+    time::Duration,
+};
+
+use common::TempDir;
+use serde_json::{json, Value};
+
+/// `checklist rpc` with a fresh database, killed when dropped.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    messages: mpsc::Receiver<Value>,
+    notifications: Vec<Value>,
+    next_id: u64,
+    dir: TempDir,
+}
+
+impl Client {
+    fn start(name: &str) -> Self {
+        let dir = TempDir::new(&format!("rpc-{name}"));
+        let mut child = dir
+            .checklist()
+            .arg("rpc")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("starting rpc server");
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (tx, messages) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let message = serde_json::from_str(&line.expect("reading stdout"));
+                if tx.send(message.expect("messages are json")).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            child,
+            stdin,
+            messages,
+            notifications: Vec::new(),
+            next_id: 1,
+            dir,
+        }
+    }
+
+    fn send(&mut self, message: &str) {
+        writeln!(self.stdin, "{message}").expect("writing request");
+    }
+
+    fn receive(&mut self) -> Value {
+        self.messages
+            .recv_timeout(Duration::from_secs(10))
+            .expect("receiving message")
+    }
+
+    /// Send a request, and return its response, keeping notifications which arrive first.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        self.send(&request.to_string());
+        loop {
+            let message = self.receive();
+            if message.get("id").is_some() {
+                assert_eq!(message["id"], id);
+                return message;
+            }
+            self.notifications.push(message);
+        }
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        let response = self.request(method, params);
+        assert!(response.get("error").is_none(), "{response}");
+        response["result"].clone()
+    }
+
+    /// Wait for a `changed` notification, returning the version it carries.
+    fn changed(&mut self) -> u64 {
+        let notification = match self.notifications.pop() {
+            Some(notification) => notification,
+            None => self.receive(),
+        };
+        assert_eq!(notification["method"], "changed", "{notification}");
+        notification["params"]["version"].as_u64().unwrap()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn requests_and_errors() {
+    let mut client = Client::start("requests");
+
+    let groceries = client.call("checklist.new", json!({ "name": "groceries" }));
+    assert_eq!(groceries["version"], 1);
+    let milk = client.call(
+        "item.new",
+        json!({ "checklist": groceries["uuid"], "text": "milk" }),
+    );
+    let toggled = client.call("item.toggle", json!({ "item": milk["id"] }));
+    assert_eq!(toggled["checked"], true);
+
+    let items = client.call("checklist.items", json!({ "checklist": groceries["id"] }));
+    assert_eq!(items.as_array().unwrap().len(), 1);
+    let markdown = client.call(
+        "checklist.to_markdown",
+        json!({ "checklist": groceries["id"] }),
+    );
+    assert!(markdown.as_str().unwrap().contains("- [x] milk"));
+
+    let stale = client.request(
+        "item.rename",
+        json!({ "item": milk["id"], "text": "oat milk", "version": 1 }),
+    );
+    assert_eq!(stale["error"]["code"], -32002);
+    assert_eq!(stale["error"]["data"]["version"], 2);
+
+    let missing = client.request("item.get", json!({ "item": 999 }));
+    assert_eq!(missing["error"]["code"], -32001);
+    let unknown = client.request("item.explode", json!({}));
+    assert_eq!(unknown["error"]["code"], -32601);
+    let invalid = client.request("checklist.new", json!({ "title": "work" }));
+    assert_eq!(invalid["error"]["code"], -32602);
+
+    client.send("{ not json");
+    assert_eq!(client.receive()["error"]["code"], -32700);
+
+    // notifications get no response, so only the second request in the batch is answered
+    client.send(
+        &json!([
+            { "jsonrpc": "2.0", "method": "checklist.new", "params": { "name": "work" } },
+            { "jsonrpc": "2.0", "id": "all", "method": "checklist.all" },
+        ])
+        .to_string(),
+    );
+    let responses = loop {
+        let message = client.receive();
+        if message.is_array() {
+            break message;
+        }
+    };
+    assert_eq!(responses.as_array().unwrap().len(), 1);
+    assert_eq!(responses[0]["id"], "all");
+    assert_eq!(responses[0]["result"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn changes_by_other_processes_are_notified() {
+    let mut client = Client::start("notifications");
+    client.call("checklist.new", json!({ "name": "groceries" }));
+    let version = client.changed();
+
+    let status = client
+        .dir
+        .checklist()
+        .args(["item", "new", "1", "eggs"])
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert!(client.changed() > version);
+    let items = client.call("item.search", json!({ "query": "EGG" }));
+    assert_eq!(items[0]["item"], "eggs");
+}
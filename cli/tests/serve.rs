@@ -0,0 +1,295 @@
+mod common;
+
+use std::{
+    io::{BufRead as _, BufReader},
+    process::{Child, Stdio},
+};
+
+use common::TempDir;
+use serde_json::{json, Value};
+
+const TOKEN: &str = "test-token";
+
+/// `checklist serve` on an ephemeral port, with a fresh database, killed when dropped.
+struct Server {
+    child: Child,
+    base: String,
+    _dir: TempDir,
+}
+
+impl Server {
+    fn start(name: &str) -> Self {
+        let dir = TempDir::new(&format!("serve-{name}"));
+        let token = dir.path().join("token");
+        std::fs::write(&token, format!("{TOKEN}\n")).expect("writing token file");
+
+        let mut child = dir
+            .checklist()
+            .args(["serve", "--listen", "127.0.0.1:0", "--token-file"])
+            .arg(token)
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("starting server");
+
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().expect("stdout is piped"))
+            .read_line(&mut line)
+            .expect("reading listen address");
+        let base = line
+            .trim()
+            .strip_prefix("listening on ")
+            .expect("server prints its address")
+            .to_owned();
+
+        Self {
+            child,
+            base,
+            _dir: dir,
+        }
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        ureq::request(method, &format!("{}{path}", self.base))
+            .set("Authorization", &format!("Bearer {TOKEN}"))
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The status of a response, whether or not it was successful.
+fn status(response: Result<ureq::Response, ureq::Error>) -> u16 {
+    match response {
+        Ok(response) => response.status(),
+        Err(ureq::Error::Status(status, _)) => status,
+        Err(err) => panic!("request failed: {err}"),
+    }
+}
+
+#[test]
+fn crud_search_and_export() {
+    let server = Server::start("crud");
+
+    let unauthenticated = ureq::get(&format!("{}/checklists", server.base)).call();
+    assert_eq!(status(unauthenticated), 401);
+
+    let response = server
+        .request("POST", "/checklists")
+        .send_json(json!({ "name": "groceries" }))
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.header("ETag"), Some("\"1\""));
+    let location = response.header("Location").unwrap().to_owned();
+    let checklist: Value = response.into_json().unwrap();
+    assert_eq!(location, format!("/checklists/{}", checklist["id"]));
+
+    for item in ["milk", "oat milk", "eggs"] {
+        let response = server
+            .request("POST", &format!("{location}/items"))
+            .send_json(json!({ "item": item }))
+            .unwrap();
+        assert_eq!(response.status(), 201);
+    }
+
+    // checklists can also be addressed by uuid
+    let uuid = checklist["uuid"].as_str().unwrap();
+    let items: Value = server
+        .request("GET", &format!("/checklists/{uuid}/items"))
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(items.as_array().unwrap().len(), 3);
+    let milk = &items[0];
+    assert_eq!(milk["checked"], false);
+
+    let toggled: Value = server
+        .request("POST", &format!("/items/{}/toggle", milk["id"]))
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(toggled["checked"], true);
+    assert_eq!(toggled["version"], 2);
+
+    let found: Value = server
+        .request("GET", "/search")
+        .query("q", "MILK")
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let found: Vec<_> = found
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| &item["item"])
+        .collect();
+    assert_eq!(found, ["milk", "oat milk"]);
+
+    let exported = server
+        .request("GET", "/export")
+        .query("format", "todotxt")
+        .call()
+        .unwrap();
+    assert_eq!(exported.content_type(), "text/plain");
+    let exported = exported.into_string().unwrap();
+    let milk_line = exported
+        .lines()
+        .find(|line| line.contains(" milk +groceries"));
+    assert!(milk_line.unwrap().starts_with("x "), "{exported}");
+    let unknown_format = server
+        .request("GET", "/export")
+        .query("format", "xml")
+        .call();
+    assert_eq!(status(unknown_format), 400);
+
+    let item = format!("/items/{}", milk["id"]);
+    assert_eq!(status(server.request("DELETE", &item).call()), 204);
+    assert_eq!(status(server.request("GET", &item).call()), 404);
+    // anything else is a name, which no item has
+    assert_eq!(
+        status(server.request("GET", "/items/not-an-id").call()),
+        404
+    );
+}
+
+#[test]
+fn if_match_rejects_stale_versions() {
+    let server = Server::start("if-match");
+
+    let checklist: Value = server
+        .request("POST", "/checklists")
+        .send_json(json!({ "name": "work" }))
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let path = format!("/checklists/{}", checklist["id"]);
+
+    let response = server
+        .request("PATCH", &path)
+        .set("If-Match", "\"1\"")
+        .send_json(json!({ "name": "office" }))
+        .unwrap();
+    assert_eq!(response.header("ETag"), Some("\"2\""));
+
+    // a client which loaded version 1 must not overwrite the rename
+    let stale = server
+        .request("PATCH", &path)
+        .set("If-Match", "\"1\"")
+        .send_json(json!({ "name": "job" }));
+    match stale {
+        Err(ureq::Error::Status(412, response)) => {
+            assert_eq!(response.header("ETag"), Some("\"2\""));
+        }
+        other => panic!("expected 412 Precondition Failed, got {other:?}"),
+    }
+    let stale = server
+        .request("DELETE", &path)
+        .set("If-Match", "\"1\"")
+        .call();
+    assert_eq!(status(stale), 412);
+    let malformed = server.request("DELETE", &path).set("If-Match", "1").call();
+    assert_eq!(status(malformed), 400);
+
+    let current: Value = server
+        .request("GET", &path)
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(current["name"], "office");
+
+    let deleted = server
+        .request("DELETE", &path)
+        .set("If-Match", "\"2\"")
+        .call();
+    assert_eq!(status(deleted), 204);
+}
+
+#[test]
+fn web_ui_moves_and_events() {
+    let server = Server::start("web");
+
+    // the page and its assets are public; the data they load is not
+    let page = ureq::get(&format!("{}/", server.base)).call().unwrap();
+    assert_eq!(page.content_type(), "text/html");
+    assert!(page.into_string().unwrap().contains("app.js"));
+    let script = ureq::get(&format!("{}/app.js", server.base)).call();
+    assert_eq!(status(script), 200);
+
+    let checklist: Value = server
+        .request("POST", "/checklists")
+        .send_json(json!({ "name": "packing" }))
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let items = format!("/checklists/{}/items", checklist["id"]);
+    for item in ["passport", "charger", "socks"] {
+        server
+            .request("POST", &items)
+            .send_json(json!({ "item": item }))
+            .unwrap();
+    }
+    let listed: Value = server
+        .request("GET", &items)
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let socks = &listed[2];
+
+    let moved: Value = server
+        .request("POST", &format!("/items/{}/move", socks["id"]))
+        .send_json(json!({ "index": 0 }))
+        .unwrap()
+        .into_json()
+        .unwrap();
+    assert_eq!(moved["item"], "socks");
+    let listed: Value = server
+        .request("GET", &items)
+        .call()
+        .unwrap()
+        .into_json()
+        .unwrap();
+    let order: Vec<_> = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| &item["item"])
+        .collect();
+    assert_eq!(order, ["socks", "passport", "charger"]);
+
+    // moving is conditional on If-Match like any other change
+    let move_path = format!("/items/{}/move", socks["id"]);
+    let stale = server
+        .request("POST", &move_path)
+        .set("If-Match", &format!("\"{}\"", socks["version"]))
+        .send_json(json!({ "index": 2 }));
+    assert_eq!(status(stale), 412);
+    let response = server
+        .request("POST", &move_path)
+        .set("If-Match", &format!("\"{}\"", moved["version"]))
+        .send_json(json!({ "index": 2 }))
+        .unwrap();
+    let version = moved["version"].as_u64().unwrap() + 1;
+    assert_eq!(
+        response.header("ETag"),
+        Some(format!("\"{version}\"").as_str())
+    );
+
+    let unauthenticated = ureq::get(&format!("{}/events", server.base)).call();
+    assert_eq!(status(unauthenticated), 401);
+    let events = server.request("GET", "/events").call().unwrap();
+    assert_eq!(events.content_type(), "text/event-stream");
+    // the current version is sent straight away
+    let mut lines = BufReader::new(events.into_reader()).lines();
+    let first = lines.next().unwrap().unwrap();
+    assert_eq!(first, "event: changed");
+    let data = lines.next().unwrap().unwrap();
+    assert!(data.starts_with("data: {\"version\":"), "{data}");
+}
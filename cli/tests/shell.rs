@@ -0,0 +1,109 @@
+mod common;
+
+use std::{
+    io::Write as _,
+    process::{Output, Stdio},
+};
+
+use common::TempDir;
+
+/// Run `checklist shell` with `lines` as its input.
+fn shell(dir: &TempDir, args: &[&str], lines: &str) -> Output {
+    let mut child = dir
+        .checklist()
+        .arg("shell")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("starting shell");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(lines.as_bytes())
+        .expect("writing commands");
+    child.wait_with_output().expect("waiting for shell")
+}
+
+#[test]
+fn commands_share_one_database_and_checklist_context() {
+    let dir = TempDir::new("shell");
+    let output = shell(
+        &dir,
+        &[],
+        "list new groceries\n\
+         use 1\n\
+         item new milk\n\
+         item new \"oat milk\"\n\
+         item toggle 2\n\
+         item show-all --omit-header\n\
+         item new too many words\n\
+         use\n\
+         item new eggs\n\
+         exit\n\
+         list new unreachable\n",
+    );
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    let items: Vec<_> = stdout.lines().skip(4).collect();
+    assert_eq!(items.len(), 2, "{stdout}");
+    assert!(items[0].ends_with("☐ milk"), "{stdout}");
+    assert!(
+        items[1].contains("☑") && items[1].contains("oat milk"),
+        "{stdout}"
+    );
+    // errors are reported without leaving the shell
+    assert!(stderr.contains("unexpected argument 'words'"), "{stderr}");
+    assert!(stderr.contains("no checklist given"), "{stderr}");
+    assert!(!stdout.contains("unreachable"), "{stdout}");
+
+    let history = std::fs::read_to_string(dir.path().join("db.history")).unwrap();
+    assert!(history.contains("item new \"oat milk\""), "{history}");
+}
+
+#[test]
+fn history_can_be_disabled() {
+    let dir = TempDir::new("shell-no-history");
+    let output = shell(&dir, &["--no-history"], "list show-all\n");
+    assert!(output.status.success());
+    assert!(!dir.path().join("db.history").exists());
+}
+
+#[test]
+fn item_text_is_looked_for_in_the_checklist_in_use() {
+    let dir = TempDir::new("shell-item-names");
+    let output = shell(
+        &dir,
+        &[],
+        "list new groceries\n\
+         list new chores\n\
+         item new groceries milk\n\
+         item new chores milk\n\
+         item toggle milk\n\
+         use chores\n\
+         item toggle milk\n\
+         item toggle @last\n\
+         item toggle milk\n",
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    // without a checklist in use, "milk" could mean either item
+    assert!(stderr.contains("could mean"), "{stderr}");
+    let toggled: Vec<_> = stdout
+        .lines()
+        .filter(|line| line.contains("milk"))
+        .skip(2)
+        .collect();
+    assert_eq!(toggled.len(), 3, "{stdout}");
+    assert!(
+        toggled
+            .iter()
+            .all(|line| line.trim_start().starts_with("2:")),
+        "{stdout}"
+    );
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use std::process::Stdio;
+
+use common::TempDir;
+
+#[test]
+fn refuses_to_start_without_a_terminal() {
+    let dir = TempDir::new("tui");
+    let output = dir
+        .checklist()
+        .arg("tui")
+        .stdin(Stdio::null())
+        .output()
+        .expect("running checklist tui");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("the terminal UI needs an interactive terminal"),
+        "{stderr}"
+    );
+    // nothing was drawn, so the terminal is left as it was
+    assert!(output.stdout.is_empty());
+}
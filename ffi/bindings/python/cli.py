@@ -32,8 +32,25 @@ from xdg_base_dirs import xdg_data_home
     "--encryption-key",
     help="Encryption key for data at rest; default '$USER@$NAME'",
 )
+@click.option(
+    "-P",
+    "--passphrase",
+    help="Passphrase from which to derive the encryption key for data at rest",
+)
+@click.option(
+    "--unencrypted",
+    is_flag=True,
+    help="Store the database in plaintext; only for throwaway and test databases",
+)
 @click.pass_context
-def cli(ctx, path: str, encryption_key_file: str | None, encryption_key: str | None):
+def cli(
+    ctx,
+    path: str,
+    encryption_key_file: str | None,
+    encryption_key: str | None,
+    passphrase: str | None,
+    unencrypted: bool,
+):
     if path is None:
         path = xdg_data_home() / "checklist/db.sqlite3"
 
@@ -42,6 +59,16 @@ def cli(ctx, path: str, encryption_key_file: str | None, encryption_key: str | N
         path.parent.mkdir(parents=True, exist_ok=True)
     path = str(path)
 
+    if unencrypted:
+        ctx.obj = asyncio.run(
+            checklist_ffi.db_open(path, checklist_ffi.EncryptionMode.NONE, b"")
+        )
+        return
+
+    if passphrase is not None:
+        ctx.obj = asyncio.run(checklist_ffi.db_new_with_passphrase(path, passphrase))
+        return
+
     key = None
     if encryption_key_file is not None:
         with open(encryption_key_file, "r") as fd:
@@ -106,7 +133,8 @@ def toggle_item(ctx, item_id: int):
         item = await checklist_ffi.item_load(db, item_id)
         state = await item.is_set(db)
         state = not state
-        await item.set_checked(db, state)
+        # fails rather than undoing a toggle made by another process since the item was loaded
+        await item.set_checked_if_version(db, state, item.version())
         return state
 
     db = ctx.find_object(checklist_ffi.Db)
@@ -52,6 +52,21 @@ pub async fn checklist_load(db: &Db, id: ChecklistId) -> Result<Option<Marc<Chec
     load_impl(db, id).await
 }
 
+async fn load_by_uuid_impl(db: &Db, uuid: &str) -> Result<Option<Marc<Checklist>>> {
+    let uuid = checklist::Uuid::try_parse(uuid)
+        .map_err(|_| checklist::Error::InvalidUuid(uuid.to_owned()))?;
+    checklist::Checklist::load_by_uuid(db, uuid)
+        .await
+        .map(|option| option.map(Checklist::marc))
+        .map_err(Into::into)
+}
+
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+pub async fn checklist_load_by_uuid(db: &Db, uuid: &str) -> Result<Option<Marc<Checklist>>> {
+    load_by_uuid_impl(db, uuid).await
+}
+
 async fn all_impl(db: &Db) -> Result<Vec<Marc<Checklist>>> {
     checklist::Checklist::all(db)
         .await
@@ -77,6 +92,19 @@ pub async fn checklist_delete(db: &Db, id: ChecklistId) -> Result<()> {
     delete_impl(db, id).await
 }
 
+async fn delete_if_version_impl(db: &Db, id: ChecklistId, expected: u64) -> Result<()> {
+    checklist::Checklist::delete_if_version(db, id.into(), expected)
+        .await
+        .map_err(Into::into)
+}
+
+/// Delete a checklist, provided that it is still at the `expected` version.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+pub async fn checklist_delete_if_version(db: &Db, id: ChecklistId, expected: u64) -> Result<()> {
+    delete_if_version_impl(db, id, expected).await
+}
+
 // associated functions cannot be exported via uniffi
 #[cfg(not(feature = "uniffi"))]
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
@@ -90,6 +118,10 @@ impl Checklist {
         load_impl(db, id).await
     }
 
+    pub async fn load_by_uuid(db: &Db, uuid: &str) -> Result<Option<Marc<Checklist>>> {
+        load_by_uuid_impl(db, uuid).await
+    }
+
     pub async fn all(db: &Db) -> Result<Vec<Marc<Checklist>>> {
         all_impl(db).await
     }
@@ -97,6 +129,10 @@ impl Checklist {
     pub async fn delete(db: &Db, id: ChecklistId) -> Result<()> {
         delete_impl(db, id).await
     }
+
+    pub async fn delete_if_version(db: &Db, id: ChecklistId, expected: u64) -> Result<()> {
+        delete_if_version_impl(db, id, expected).await
+    }
 }
 
 #[cfg_attr(feature = "uniffi", uniffi::export)]
@@ -110,11 +146,35 @@ impl Checklist {
             .map_err(Into::into)
     }
 
+    pub async fn rename(&self, db: &Db, name: &str) -> Result<()> {
+        self.inner.rename(db, name).await.map_err(Into::into)
+    }
+
+    /// Rename the checklist, provided that it is still at the `expected` version.
+    ///
+    /// Returns the new version.
+    pub async fn rename_if_version(&self, db: &Db, name: &str, expected: u64) -> Result<u64> {
+        self.inner
+            .rename_if_version(db, name, expected)
+            .await
+            .map_err(Into::into)
+    }
+
     pub fn id(&self) -> ChecklistId {
         self.inner.id.into()
     }
 
+    /// Globally unique id, as a hyphenated string
+    pub fn uuid(&self) -> String {
+        self.inner.uuid.to_string()
+    }
+
     pub fn name(&self) -> String {
         self.inner.name.clone()
     }
+
+    /// Version of the checklist when it was loaded
+    pub fn version(&self) -> u64 {
+        self.inner.version
+    }
 }
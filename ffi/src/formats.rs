@@ -0,0 +1,50 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{libchecklist, Db, Result};
+
+/// How to combine an imported document with the existing contents of a database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub enum ImportMode {
+    /// Add everything in the document as new checklists and items, assigning fresh ids
+    ///
+    /// Uuids are kept, except those which already exist in the database.
+    Merge,
+    /// Delete everything in the database, then insert the document, keeping its ids and uuids where
+    /// present
+    Replace,
+    /// Overwrite checklists and items which already exist, and insert the rest
+    ///
+    /// Records are matched by uuid where present, and by id otherwise. Checklists with neither, as read
+    /// from formats which identify checklists only by name, are matched by name.
+    Update,
+}
+
+impl From<ImportMode> for libchecklist::formats::ImportMode {
+    fn from(mode: ImportMode) -> Self {
+        match mode {
+            ImportMode::Merge => Self::Merge,
+            ImportMode::Replace => Self::Replace,
+            ImportMode::Update => Self::Update,
+        }
+    }
+}
+
+#[cfg_attr(feature = "uniffi", uniffi::export)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Db {
+    /// Write every checklist and item as a versioned JSON document.
+    pub async fn export_json(&self) -> Result<String> {
+        let document = libchecklist::formats::Document::export(self).await?;
+        libchecklist::formats::json::to_string(&document).map_err(Into::into)
+    }
+
+    /// Import checklists and items from a JSON document in a single transaction.
+    pub async fn import_json(&self, json: &str, mode: ImportMode) -> Result<()> {
+        let document = libchecklist::formats::json::from_str(json)?;
+        document.import(self, mode.into()).await?;
+        Ok(())
+    }
+}
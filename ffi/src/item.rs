@@ -52,6 +52,21 @@ pub async fn item_load(db: &Db, item_id: ItemId) -> Result<Option<Marc<Item>>> {
     load_impl(db, item_id).await
 }
 
+async fn load_by_uuid_impl(db: &Db, uuid: &str) -> Result<Option<Marc<Item>>> {
+    let uuid = checklist::Uuid::try_parse(uuid)
+        .map_err(|_| checklist::Error::InvalidUuid(uuid.to_owned()))?;
+    checklist::Item::load_by_uuid(db, uuid)
+        .await
+        .map(|option| option.map(Item::marc))
+        .map_err(Into::into)
+}
+
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+pub async fn item_load_by_uuid(db: &Db, uuid: &str) -> Result<Option<Marc<Item>>> {
+    load_by_uuid_impl(db, uuid).await
+}
+
 async fn delete_impl(db: &Db, item_id: ItemId) -> Result<()> {
     checklist::Item::delete(db, item_id.into())
         .await
@@ -64,6 +79,33 @@ pub async fn item_delete(db: &Db, item_id: ItemId) -> Result<()> {
     delete_impl(db, item_id).await
 }
 
+async fn delete_if_version_impl(db: &Db, item_id: ItemId, expected: u64) -> Result<()> {
+    checklist::Item::delete_if_version(db, item_id.into(), expected)
+        .await
+        .map_err(Into::into)
+}
+
+/// Delete an item, provided that it is still at the `expected` version.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+pub async fn item_delete_if_version(db: &Db, item_id: ItemId, expected: u64) -> Result<()> {
+    delete_if_version_impl(db, item_id, expected).await
+}
+
+async fn search_impl(db: &Db, query: &str) -> Result<Vec<Marc<Item>>> {
+    checklist::Item::search(db, query)
+        .await
+        .map(|items| items.into_iter().map(Item::marc).collect())
+        .map_err(Into::into)
+}
+
+/// Find the items in any checklist whose text contains `query`, ignoring ASCII case.
+#[cfg(feature = "uniffi")]
+#[uniffi::export]
+pub async fn item_search(db: &Db, query: &str) -> Result<Vec<Marc<Item>>> {
+    search_impl(db, query).await
+}
+
 // associated functions cannot be exported via uniffi
 #[cfg(not(feature = "uniffi"))]
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
@@ -77,9 +119,21 @@ impl Item {
         load_impl(db, item_id).await
     }
 
+    pub async fn load_by_uuid(db: &Db, uuid: &str) -> Result<Option<Marc<Item>>> {
+        load_by_uuid_impl(db, uuid).await
+    }
+
     pub async fn delete(db: &Db, item_id: ItemId) -> Result<()> {
         delete_impl(db, item_id).await
     }
+
+    pub async fn delete_if_version(db: &Db, item_id: ItemId, expected: u64) -> Result<()> {
+        delete_if_version_impl(db, item_id, expected).await
+    }
+
+    pub async fn search(db: &Db, query: &str) -> Result<Vec<Marc<Item>>> {
+        search_impl(db, query).await
+    }
 }
 
 #[cfg_attr(feature = "uniffi", uniffi::export)]
@@ -96,10 +150,53 @@ impl Item {
             .map_err(Into::into)
     }
 
+    /// Set the check status, provided that the item is still at the `expected` version.
+    ///
+    /// Returns the new version.
+    pub async fn set_checked_if_version(
+        &self,
+        db: &Db,
+        checked: bool,
+        expected: u64,
+    ) -> Result<u64> {
+        self.inner
+            .set_checked_if_version(db, checked, expected)
+            .await
+            .map_err(Into::into)
+    }
+
+    pub async fn rename(&self, db: &Db, item: &str) -> Result<()> {
+        self.inner.rename(db, item).await.map_err(Into::into)
+    }
+
+    /// Change the text, provided that the item is still at the `expected` version.
+    ///
+    /// Returns the new version.
+    pub async fn rename_if_version(&self, db: &Db, item: &str, expected: u64) -> Result<u64> {
+        self.inner
+            .rename_if_version(db, item, expected)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Move the item to `index` among the items of its checklist, or to the end if `index` is past
+    /// the last item.
+    pub async fn move_to(&self, db: &Db, index: u32) -> Result<()> {
+        self.inner
+            .move_to(db, index as usize)
+            .await
+            .map_err(Into::into)
+    }
+
     pub fn id(&self) -> ItemId {
         self.inner.id.into()
     }
 
+    /// Globally unique id, as a hyphenated string
+    pub fn uuid(&self) -> String {
+        self.inner.uuid.to_string()
+    }
+
     pub fn checklist_id(&self) -> ChecklistId {
         self.inner.checklist.into()
     }
@@ -107,4 +204,9 @@ impl Item {
     pub fn item(&self) -> String {
         self.inner.item.clone()
     }
+
+    /// Version of the item when it was loaded
+    pub fn version(&self) -> u64 {
+        self.inner.version
+    }
 }
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use crate::{libchecklist, Db, Result};
+
+/// Errors which a foreign [`KeyProvider`] can report.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum KeyProviderError {
+    #[error("{reason}")]
+    Failed { reason: String },
+    #[error("unexpected error in foreign key provider: {reason}")]
+    Unexpected { reason: String },
+}
+
+impl From<uniffi::UnexpectedUniFFICallbackError> for KeyProviderError {
+    fn from(err: uniffi::UnexpectedUniFFICallbackError) -> Self {
+        Self::Unexpected { reason: err.reason }
+    }
+}
+
+/// Source of encryption key material, implemented in foreign code.
+#[uniffi::export(with_foreign)]
+pub trait KeyProvider: Send + Sync {
+    fn key(&self) -> Result<Vec<u8>, KeyProviderError>;
+}
+
+/// Adapts a foreign key provider to the library's trait.
+struct ForeignKeyProvider(Arc<dyn KeyProvider>);
+
+impl libchecklist::KeyProvider for ForeignKeyProvider {
+    fn key(&self) -> libchecklist::Result<zeroize::Zeroizing<Vec<u8>>> {
+        self.0
+            .key()
+            .map(Into::into)
+            .map_err(libchecklist::Error::key_provider)
+    }
+}
+
+/// Open a database whose encryption key comes from a foreign key provider.
+///
+/// When `derive_key` is set, the provider's output is treated as a passphrase.
+#[uniffi::export]
+pub async fn db_new_with_key_provider(
+    path: &str,
+    provider: Arc<dyn KeyProvider>,
+    derive_key: bool,
+) -> Result<Db> {
+    let provider = ForeignKeyProvider(provider);
+    let encryption_config = if derive_key {
+        libchecklist::key_provider::derived_encryption_config(
+            &provider,
+            path,
+            libchecklist::KdfCost::default(),
+        )?
+    } else {
+        libchecklist::key_provider::raw_encryption_config(&provider)?
+    };
+    libchecklist::Db::new(path, encryption_config)
+        .await
+        .map(|inner| Db { inner })
+        .map_err(Into::into)
+}
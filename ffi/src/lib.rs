@@ -5,24 +5,38 @@ compile_error!(
 
 mod checklist;
 mod error;
+mod formats;
 mod item;
+#[cfg(feature = "uniffi")]
+mod key_provider;
 pub(crate) mod marc;
+mod sync;
 
 use ::checklist as libchecklist;
 use std::ops::Deref;
 
 pub use checklist::{Checklist, ChecklistId};
 pub use error::{Error, Result};
+pub use formats::ImportMode;
 pub use item::{Item, ItemId};
+pub use sync::{SyncConflict, SyncSummary};
 
 #[cfg(feature = "uniffi")]
 uniffi::setup_scaffolding!("checklist_ffi");
 
 #[cfg(feature = "uniffi")]
-pub use checklist::{checklist_all, checklist_delete, checklist_load, checklist_new};
+pub use checklist::{
+    checklist_all, checklist_delete, checklist_delete_if_version, checklist_load,
+    checklist_load_by_uuid, checklist_new,
+};
+
+#[cfg(feature = "uniffi")]
+pub use item::{
+    item_delete, item_delete_if_version, item_load, item_load_by_uuid, item_new, item_search,
+};
 
 #[cfg(feature = "uniffi")]
-pub use item::{item_delete, item_load, item_new};
+pub use key_provider::{db_new_with_key_provider, KeyProvider, KeyProviderError};
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
@@ -46,6 +60,59 @@ pub async fn db_new(path: &str, encryption_key: Vec<u8>) -> Result<Db> {
         .map_err(Into::into)
 }
 
+/// How a database is protected at rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Enum))]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub enum EncryptionMode {
+    /// Stored in plaintext; suitable only for throwaway and test databases
+    None,
+    /// AES 256 Bit CBC, no HMAC
+    Aes256Cbc,
+}
+
+impl From<EncryptionMode> for libchecklist::EncryptionMode {
+    fn from(mode: EncryptionMode) -> Self {
+        match mode {
+            EncryptionMode::None => Self::None,
+            EncryptionMode::Aes256Cbc => Self::Aes256Cbc,
+        }
+    }
+}
+
+/// Open a database with an explicit encryption mode.
+///
+/// `encryption_key` is ignored when `mode` is [`EncryptionMode::None`].
+#[cfg_attr(feature = "uniffi", uniffi::export)]
+pub async fn db_open(path: &str, mode: EncryptionMode, encryption_key: Vec<u8>) -> Result<Db> {
+    let encryption = libchecklist::EncryptionMode::from(mode)
+        .cipher()
+        .map(|cipher| libchecklist::EncryptionConfig {
+            cipher,
+            encryption_key: encryption_key.into(),
+        });
+    libchecklist::Db::open(path, libchecklist::DbOptions { encryption })
+        .await
+        .map(|inner| Db { inner })
+        .map_err(Into::into)
+}
+
+/// Open a database whose encryption key is derived from a passphrase.
+///
+/// A random salt is generated and stored next to the database the first time it is opened this way.
+#[cfg_attr(feature = "uniffi", uniffi::export)]
+pub async fn db_new_with_passphrase(path: &str, passphrase: &str) -> Result<Db> {
+    let encryption_config = libchecklist::passphrase_encryption_config(
+        path,
+        passphrase.as_bytes(),
+        libchecklist::KdfCost::default(),
+    )?;
+    libchecklist::Db::new(path, encryption_config)
+        .await
+        .map(|inner| Db { inner })
+        .map_err(Into::into)
+}
+
 #[cfg_attr(feature = "uniffi", uniffi::export)]
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl Db {
@@ -54,6 +121,37 @@ impl Db {
     pub async fn new(path: &str, encryption_key: Vec<u8>) -> Result<Db> {
         db_new(path, encryption_key).await
     }
+
+    #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    pub async fn open(path: &str, mode: EncryptionMode, encryption_key: Vec<u8>) -> Result<Db> {
+        db_open(path, mode, encryption_key).await
+    }
+
+    #[cfg_attr(feature = "uniffi", uniffi::constructor)]
+    pub async fn with_passphrase(path: &str, passphrase: &str) -> Result<Db> {
+        db_new_with_passphrase(path, passphrase).await
+    }
+
+    /// Re-encrypt the database with a new key, removing any passphrase key header.
+    pub async fn rekey(&self, encryption_key: Vec<u8>) -> Result<()> {
+        let encryption_key = encryption_key.into();
+        let encryption_config = libchecklist::EncryptionConfig {
+            cipher: libchecklist::Cipher::Aes256Cbc,
+            encryption_key,
+        };
+        self.inner
+            .rekey(encryption_config)
+            .await
+            .map_err(Into::into)
+    }
+
+    /// Re-encrypt the database with a key derived from a new passphrase, with a fresh salt.
+    pub async fn rekey_with_passphrase(&self, passphrase: &str) -> Result<()> {
+        self.inner
+            .rekey_with_passphrase(passphrase.as_bytes(), libchecklist::KdfCost::default())
+            .await
+            .map_err(Into::into)
+    }
 }
 
 impl Deref for Db {
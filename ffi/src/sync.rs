@@ -0,0 +1,106 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::{libchecklist, Db, Result};
+
+/// What applying a changeset changed.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+pub struct SyncSummary {
+    pub inserted: u32,
+    pub updated: u32,
+    pub deleted: u32,
+    /// Records which could not be applied, such as items of a checklist which does not exist here
+    pub skipped: u32,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+/// Concurrent changes to the same record, resolved while applying a changeset.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "uniffi", derive(uniffi::Record))]
+#[cfg_attr(feature = "wasm", wasm_bindgen(getter_with_clone))]
+pub struct SyncConflict {
+    /// Uuid of the checklist or item
+    pub uuid: String,
+    /// The field both databases wrote, or none if one deleted the record while the other edited it
+    pub field: Option<String>,
+    /// Whether the incoming change won
+    pub accepted: bool,
+    /// A description of the conflict and its resolution
+    pub description: String,
+}
+
+impl From<libchecklist::sync::SyncSummary> for SyncSummary {
+    fn from(summary: libchecklist::sync::SyncSummary) -> Self {
+        let count = |count: usize| count.try_into().unwrap_or(u32::MAX);
+        Self {
+            inserted: count(summary.inserted),
+            updated: count(summary.updated),
+            deleted: count(summary.deleted),
+            skipped: count(summary.skipped),
+            conflicts: summary.conflicts.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<libchecklist::sync::Conflict> for SyncConflict {
+    fn from(conflict: libchecklist::sync::Conflict) -> Self {
+        let field = match &conflict.kind {
+            libchecklist::sync::ConflictKind::Field { field, .. } => Some(field.clone()),
+            _ => None,
+        };
+        Self {
+            uuid: conflict.uuid.to_string(),
+            field,
+            accepted: conflict.accepted,
+            description: conflict.to_string(),
+        }
+    }
+}
+
+fn parse_uuid(uuid: &str) -> Result<libchecklist::Uuid> {
+    libchecklist::Uuid::try_parse(uuid)
+        .map_err(|_| libchecklist::Error::InvalidUuid(uuid.to_owned()).into())
+}
+
+#[cfg_attr(feature = "uniffi", uniffi::export)]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Db {
+    /// The random id which identifies this database to its sync peers.
+    pub async fn site(&self) -> Result<String> {
+        let site = self.inner.site().await?;
+        Ok(site.to_string())
+    }
+
+    /// The current version of this database, which advances with each change.
+    pub async fn sync_version(&self) -> Result<u64> {
+        self.inner.sync_version().await.map_err(Into::into)
+    }
+
+    /// Encode the changes made after version `since` as a changeset.
+    ///
+    /// The changeset is encrypted with a key derived from `key`, if given, which every peer must
+    /// share.
+    pub async fn changes_since(&self, since: u64, key: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        let changeset = self.inner.changes_since(since).await?;
+        changeset.to_bytes(key.as_deref()).map_err(Into::into)
+    }
+
+    /// Encode the changes which the peer with the given site has not yet acknowledged receiving.
+    pub async fn changes_for(&self, peer: &str, key: Option<Vec<u8>>) -> Result<Vec<u8>> {
+        let changeset = self.inner.changes_for(parse_uuid(peer)?).await?;
+        changeset.to_bytes(key.as_deref()).map_err(Into::into)
+    }
+
+    /// Merge a changeset produced by another database, in a single transaction.
+    pub async fn apply_changeset(
+        &self,
+        changeset: Vec<u8>,
+        key: Option<Vec<u8>>,
+    ) -> Result<SyncSummary> {
+        let changeset = libchecklist::sync::Changeset::from_bytes(&changeset, key.as_deref())?;
+        let summary = self.inner.apply_changeset(&changeset).await?;
+        Ok(summary.into())
+    }
+}